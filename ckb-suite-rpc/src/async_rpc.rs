@@ -0,0 +1,785 @@
+use crate::cache::{Cache, CacheStats};
+use crate::config::ClientConfig;
+use crate::endpoint::Endpoint;
+use crate::error::Error;
+use crate::indexer::{Cell, CellsCapacity, Order, Pagination, SearchKey, Tx};
+use crate::rate_limit::RateLimiter;
+use crate::record::{record_exchange, respond_locally, Recorder, Responder};
+use ckb_jsonrpc_types::{
+    BannedAddr, Block, BlockEconomicState, BlockReward, BlockTemplate, BlockView, CellWithStatus,
+    ChainInfo, Consensus, EpochView, HeaderView, JsonBytes, LocalNode, OutPoint, RawTxPool,
+    RemoteNode, SyncState, Transaction, TransactionProof, TransactionWithStatus, TxPoolInfo,
+    Uint32, Uint64, Version,
+};
+use ckb_types::{
+    core::{
+        BlockNumber as CoreBlockNumber, EpochNumber as CoreEpochNumber, Version as CoreVersion,
+    },
+    packed::Byte32,
+    prelude::*,
+    H256,
+};
+use ckb_util::Mutex;
+use hyper::body::Bytes;
+use hyper::client::HttpConnector;
+use hyper::header::{AUTHORIZATION, CONTENT_TYPE};
+use hyper::{Body, Client, Request};
+use serde::de::DeserializeOwned;
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::env::var;
+use std::ops::Range;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+
+// The JSON-RPC error code of the methods which the endpoint does not serve
+const METHOD_NOT_FOUND: i64 = -32601;
+
+// Requests of these methods change the node state, so never retry them
+const NON_IDEMPOTENT_METHODS: &[&str] = &[
+    "submit_block",
+    "send_transaction",
+    "broadcast_transaction",
+    "add_node",
+    "remove_node",
+    "process_block_without_verify",
+];
+
+/// Asynchronous JSON-RPC client of CKB, running on tokio 1.
+///
+/// The client is connected to an ordered list of endpoints. Every request is served by the first
+/// healthy endpoint, and fails over to the next one on transport failures or timeouts.
+#[derive(Clone)]
+pub struct AsyncJsonrpc {
+    endpoints: Arc<Vec<Endpoint>>,
+    // The genesis hash answered by each endpoint to the probe, in the order of the endpoints
+    genesis_hashes: Arc<Mutex<Vec<Option<H256>>>>,
+    client: Client<HttpConnector>,
+    authorization: Option<String>,
+    config: ClientConfig,
+    rate_limiter: Option<Arc<RateLimiter>>,
+    id: Arc<AtomicU64>,
+    blocks_cache: Arc<Cache<Byte32, BlockView>>,
+    headers_cache: Arc<Cache<Byte32, HeaderView>>,
+    // Canonical headers, invalidated via `invalidate_headers_since` on reorganization
+    headers_by_number_cache: Arc<Cache<CoreBlockNumber, HeaderView>>,
+    recorder: Option<Arc<dyn Recorder>>,
+    responder: Option<Arc<dyn Responder>>,
+}
+
+pub fn env_username() -> String {
+    var("CKB_RPC_USERNAME").unwrap_or_else(|_| "".to_owned())
+}
+
+pub fn env_password() -> String {
+    var("CKB_RPC_PASSWORD").unwrap_or_else(|_| "".to_owned())
+}
+
+#[derive(Deserialize)]
+struct Response {
+    #[serde(default)]
+    id: Value,
+    #[serde(default)]
+    result: Option<Value>,
+    #[serde(default)]
+    error: Option<ResponseError>,
+}
+
+fn decode_response<T: DeserializeOwned>(bytes: &[u8]) -> Result<T, Error> {
+    let response: Response = serde_json::from_slice(bytes)?;
+    response.into_result()
+}
+
+fn log_mismatched(endpoint: &Endpoint, genesis_hash: &H256, expected: &H256) {
+    log::error!(
+        "endpoint \"{}\" has genesis {:#x}, expected {:#x}, exclude it",
+        endpoint.uri,
+        genesis_hash,
+        expected,
+    );
+}
+
+impl Response {
+    fn into_result<T: DeserializeOwned>(self) -> Result<T, Error> {
+        if let Some(error) = self.error {
+            return Err(Error::Rpc {
+                code: error.code,
+                message: error.message,
+            });
+        }
+        let result = serde_json::from_value(self.result.unwrap_or(Value::Null))?;
+        Ok(result)
+    }
+}
+
+#[derive(Deserialize)]
+struct ResponseError {
+    code: i64,
+    message: String,
+}
+
+impl AsyncJsonrpc {
+    pub fn connect<U: AsRef<str>>(uris: &[U]) -> Self {
+        Self::connect_with_config(uris, ClientConfig::default())
+    }
+
+    pub fn connect_with_config<U: AsRef<str>>(uris: &[U], config: ClientConfig) -> Self {
+        assert!(!uris.is_empty(), "AsyncJsonrpc::connect requires endpoints");
+        let endpoints = uris
+            .iter()
+            .map(|uri| {
+                let uri = uri.as_ref();
+                uri.parse::<hyper::Uri>().unwrap_or_else(|err| {
+                    panic!("AsyncJsonrpc::connect(\"{}\"), error: {:?}", uri, err)
+                });
+                Endpoint::new(uri.to_string())
+            })
+            .collect::<Vec<_>>();
+        let genesis_hashes = vec![None; endpoints.len()];
+
+        let authorization = if !env_username().is_empty() && !env_password().is_empty() {
+            let credentials = format!("{}:{}", env_username(), env_password());
+            Some(format!("Basic {}", base64::encode(credentials)))
+        } else {
+            None
+        };
+        let rate_limiter = config
+            .rate_limit
+            .filter(|rate| *rate > 0)
+            .map(|rate| Arc::new(RateLimiter::new(rate, config.rate_limit_burst)));
+        let blocks_cache = Arc::new(Cache::new(config.blocks_cache_size));
+        let headers_cache = Arc::new(Cache::new(config.headers_cache_size));
+        let headers_by_number_cache = Arc::new(Cache::new(config.headers_by_number_cache_size));
+        Self {
+            endpoints: Arc::new(endpoints),
+            genesis_hashes: Arc::new(Mutex::new(genesis_hashes)),
+            client: Client::new(),
+            authorization,
+            config,
+            rate_limiter,
+            id: Arc::new(AtomicU64::new(0)),
+            blocks_cache,
+            headers_cache,
+            headers_by_number_cache,
+            recorder: None,
+            responder: None,
+        }
+    }
+
+    /// Pass every call served by the endpoints, and its response, to the recorder
+    pub fn with_recorder(mut self, recorder: Arc<dyn Recorder>) -> Self {
+        self.recorder = Some(recorder);
+        self
+    }
+
+    /// Answer every call by the responder instead of the endpoints
+    pub fn with_responder(mut self, responder: Arc<dyn Responder>) -> Self {
+        self.responder = Some(responder);
+        self
+    }
+
+    /// The uri of the primary endpoint
+    pub fn uri(&self) -> &String {
+        &self.endpoints[0].uri
+    }
+
+    /// The uri of every endpoint, and the number of requests it has served
+    pub fn served_counts(&self) -> Vec<(String, u64)> {
+        self.endpoints
+            .iter()
+            .map(|endpoint| (endpoint.uri.clone(), endpoint.served()))
+            .collect()
+    }
+
+    pub fn config(&self) -> &ClientConfig {
+        &self.config
+    }
+
+    /// Statistics of the blocks, headers and headers-by-number caches, named "blocks", "headers"
+    /// and "headers_by_number"
+    pub fn cache_stats(&self) -> Vec<(&'static str, CacheStats)> {
+        vec![
+            ("blocks", self.blocks_cache.stats()),
+            ("headers", self.headers_cache.stats()),
+            ("headers_by_number", self.headers_by_number_cache.stats()),
+        ]
+    }
+
+    /// Invalidate the cached canonical headers at or above `number`, as the chain has been
+    /// reorganized since `number`
+    pub fn invalidate_headers_since(&self, number: CoreBlockNumber) {
+        self.headers_by_number_cache
+            .retain(|cached| *cached < number);
+    }
+
+    async fn post(&self, body: &Value, idempotent: bool) -> Result<Bytes, Error> {
+        if let Some(ref responder) = self.responder {
+            return respond_locally(responder.as_ref(), body);
+        }
+
+        let bytes = self.post_to_endpoints(body, idempotent).await?;
+        if let Some(ref recorder) = self.recorder {
+            record_exchange(recorder.as_ref(), body, &bytes);
+        }
+        Ok(bytes)
+    }
+
+    /// Post the body to the first healthy endpoint. Fail over to the next endpoint on transport
+    /// failures and timeouts. Non-idempotent requests never fail over, as the failed endpoint may
+    /// have processed them.
+    async fn post_to_endpoints(&self, body: &Value, idempotent: bool) -> Result<Bytes, Error> {
+        let mut last_error = None;
+        for (index, endpoint) in self.endpoints.iter().enumerate() {
+            if endpoint.needs_probe() {
+                self.probe(index).await;
+            }
+            if !endpoint.is_healthy() {
+                continue;
+            }
+
+            match self.post_with_retry(endpoint, body, idempotent).await {
+                Ok(bytes) => {
+                    endpoint.record_served();
+                    log::trace!("request served by \"{}\"", endpoint.uri);
+                    return Ok(bytes);
+                }
+                Err(err) if err.is_transient() => {
+                    log::warn!("endpoint \"{}\" is unhealthy, error: {}", endpoint.uri, err);
+                    endpoint.mark_unhealthy(self.config.health_check_interval);
+                    last_error = Some(err);
+                    if !idempotent {
+                        break;
+                    }
+                }
+                Err(err) => return Err(err),
+            }
+        }
+        Err(last_error.unwrap_or(Error::NoAvailableEndpoint))
+    }
+
+    /// Probe the endpoint at `index` via `get_consensus`, and check its genesis hash. With
+    /// `indexer_only`, the endpoints which do not support `get_consensus` are accepted without the
+    /// genesis check.
+    async fn probe(&self, index: usize) {
+        let endpoint = &self.endpoints[index];
+        let body = json!({
+            "jsonrpc": "2.0",
+            "id": self.id.fetch_add(1, Ordering::SeqCst),
+            "method": "get_consensus",
+            "params": [],
+        });
+        let consensus: Consensus = match self.post_once(endpoint, &body).await {
+            Ok(bytes) => match decode_response(&bytes) {
+                Ok(consensus) => consensus,
+                Err(Error::Rpc { code, .. })
+                    if code == METHOD_NOT_FOUND && self.config.indexer_only =>
+                {
+                    endpoint.mark_healthy();
+                    return;
+                }
+                Err(err) => {
+                    log::warn!("probe endpoint \"{}\", error: {}", endpoint.uri, err);
+                    endpoint.mark_unhealthy(self.config.health_check_interval);
+                    return;
+                }
+            },
+            Err(err) => {
+                log::warn!("probe endpoint \"{}\", error: {}", endpoint.uri, err);
+                endpoint.mark_unhealthy(self.config.health_check_interval);
+                return;
+            }
+        };
+
+        self.check_genesis(index, consensus.genesis_hash);
+    }
+
+    /// The primary endpoint pins the genesis hash; fallback endpoints of other chains are excluded.
+    /// Until the primary answers, the first fallback which answers pins it provisionally, and the
+    /// fallbacks are checked again once the primary answers.
+    fn check_genesis(&self, index: usize, genesis_hash: H256) {
+        let mut genesis_hashes = self.genesis_hashes.lock();
+        genesis_hashes[index] = Some(genesis_hash.clone());
+        let endpoint = &self.endpoints[index];
+        if index == 0 {
+            endpoint.mark_healthy();
+            for (fallback, fallback_hash) in
+                self.endpoints.iter().zip(genesis_hashes.iter()).skip(1)
+            {
+                match fallback_hash {
+                    Some(fallback_hash) if fallback_hash != &genesis_hash => {
+                        if !fallback.is_mismatched() {
+                            log_mismatched(fallback, fallback_hash, &genesis_hash);
+                            fallback.mark_mismatched();
+                        }
+                    }
+                    // Excluded by a provisional genesis hash, probe it again
+                    Some(_) if fallback.is_mismatched() => fallback.mark_unknown(),
+                    _ => {}
+                }
+            }
+            return;
+        }
+
+        let expected = genesis_hashes[0].clone().or_else(|| {
+            self.endpoints
+                .iter()
+                .zip(genesis_hashes.iter())
+                .enumerate()
+                .skip(1)
+                .find(|(i, (fallback, fallback_hash))| {
+                    *i != index && fallback_hash.is_some() && !fallback.is_mismatched()
+                })
+                .and_then(|(_, (_, fallback_hash))| fallback_hash.clone())
+        });
+        match expected {
+            Some(expected) if expected != genesis_hash => {
+                log_mismatched(endpoint, &genesis_hash, &expected);
+                endpoint.mark_mismatched();
+            }
+            _ => endpoint.mark_healthy(),
+        }
+    }
+
+    /// Post the body to the endpoint, retrying on transport failures and timeouts if `idempotent`
+    async fn post_with_retry(
+        &self,
+        endpoint: &Endpoint,
+        body: &Value,
+        idempotent: bool,
+    ) -> Result<Bytes, Error> {
+        let max_retries = if idempotent {
+            self.config.max_retries
+        } else {
+            0
+        };
+        let mut retries = 0;
+        loop {
+            match self.post_once(endpoint, body).await {
+                Err(err) if err.is_transient() && retries < max_retries => {
+                    let backoff = self.config.retry_backoff * 2u32.saturating_pow(retries);
+                    retries += 1;
+                    tokio::time::sleep(backoff).await;
+                }
+                result => return result,
+            }
+        }
+    }
+
+    async fn post_once(&self, endpoint: &Endpoint, body: &Value) -> Result<Bytes, Error> {
+        if let Some(ref rate_limiter) = self.rate_limiter {
+            rate_limiter.acquire().await;
+        }
+
+        let mut builder =
+            Request::post(endpoint.uri.as_str()).header(CONTENT_TYPE, "application/json");
+        if let Some(ref authorization) = self.authorization {
+            builder = builder.header(AUTHORIZATION, authorization.as_str());
+        }
+        let request = builder
+            .body(Body::from(body.to_string()))
+            .expect("build jsonrpc request");
+        let post = async {
+            let response = self.client.request(request).await?;
+            let bytes = hyper::body::to_bytes(response.into_body()).await?;
+            Ok::<_, Error>(bytes)
+        };
+        tokio::time::timeout(self.config.timeout, post)
+            .await
+            .map_err(|_| Error::Timeout)?
+    }
+
+    async fn request<T: DeserializeOwned>(&self, method: &str, params: Value) -> Result<T, Error> {
+        let id = self.id.fetch_add(1, Ordering::SeqCst);
+        let body = json!({
+            "jsonrpc": "2.0",
+            "id": id,
+            "method": method,
+            "params": params,
+        });
+        let idempotent = !NON_IDEMPOTENT_METHODS.contains(&method);
+        let bytes = self.post(&body, idempotent).await?;
+        decode_response(&bytes)
+    }
+
+    /// Like `request`, but for methods that respond `null` when the object does not exist, which
+    /// is turned into `Error::NotFound`.
+    async fn request_some<T: DeserializeOwned>(
+        &self,
+        method: &str,
+        params: Value,
+    ) -> Result<T, Error> {
+        let ret: Option<T> = self.request(method, params.clone()).await?;
+        ret.ok_or_else(|| Error::NotFound(format!("{}({})", method, params)))
+    }
+
+    /// Send the calls of the same method in one JSON-RPC batch request. The results are in the
+    /// same order as `batch_params`.
+    async fn batch_request<T: DeserializeOwned>(
+        &self,
+        method: &str,
+        batch_params: Vec<Value>,
+    ) -> Result<Vec<T>, Error> {
+        if batch_params.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let size = batch_params.len();
+        let first_id = self.id.fetch_add(size as u64, Ordering::SeqCst);
+        let body = batch_params
+            .into_iter()
+            .enumerate()
+            .map(|(i, params)| {
+                json!({
+                    "jsonrpc": "2.0",
+                    "id": first_id + i as u64,
+                    "method": method,
+                    "params": params,
+                })
+            })
+            .collect::<Vec<_>>();
+        let idempotent = !NON_IDEMPOTENT_METHODS.contains(&method);
+        let bytes = self.post(&Value::Array(body), idempotent).await?;
+        let responses: Vec<Response> = serde_json::from_slice(&bytes)?;
+
+        // The server may respond the batch in any order, so rearrange them by id
+        let mut ordered: Vec<Option<Response>> = (0..size).map(|_| None).collect();
+        for response in responses {
+            if let Some(index) = response
+                .id
+                .as_u64()
+                .and_then(|id| id.checked_sub(first_id))
+                .filter(|index| *index < size as u64)
+            {
+                ordered[index as usize] = Some(response);
+            }
+        }
+        ordered
+            .into_iter()
+            .enumerate()
+            .map(|(i, response)| match response {
+                Some(response) => response.into_result(),
+                None => Err(Error::Decode(serde::de::Error::custom(format!(
+                    "missing response of batch request id {}",
+                    first_id + i as u64
+                )))),
+            })
+            .collect()
+    }
+
+    pub async fn get_block(&self, hash: Byte32) -> Result<BlockView, Error> {
+        if let Some(block) = self.blocks_cache.get(&hash) {
+            return Ok(block);
+        }
+
+        let h256: H256 = hash.unpack();
+        let block: BlockView = self.request_some("get_block", json!([h256])).await?;
+        self.blocks_cache
+            .put(block.header.hash.pack(), block.clone());
+        Ok(block)
+    }
+
+    pub async fn get_header(&self, hash: Byte32) -> Result<HeaderView, Error> {
+        if let Some(header) = self.lookup_header(&hash) {
+            return Ok(header);
+        }
+
+        let h256: H256 = hash.unpack();
+        let header: HeaderView = self.request_some("get_header", json!([h256])).await?;
+        self.headers_cache.put(header.hash.pack(), header.clone());
+        Ok(header)
+    }
+
+    /// Get the blocks by numbers in one batch request. Blocks not found are `None`.
+    pub async fn get_blocks_by_numbers(
+        &self,
+        numbers: Range<CoreBlockNumber>,
+    ) -> Result<Vec<Option<BlockView>>, Error> {
+        let batch_params = numbers
+            .map(|number| json!([Uint64::from(number)]))
+            .collect();
+        self.batch_request("get_block_by_number", batch_params)
+            .await
+    }
+
+    /// Get the headers by hashes. Cached headers are returned directly, the remaining are
+    /// fetched in one batch request. Headers not found are `None`.
+    pub async fn get_headers(&self, hashes: Vec<Byte32>) -> Result<Vec<Option<HeaderView>>, Error> {
+        let mut headers: Vec<Option<HeaderView>> =
+            hashes.iter().map(|hash| self.lookup_header(hash)).collect();
+        let missing = headers
+            .iter()
+            .enumerate()
+            .filter(|(_, header)| header.is_none())
+            .map(|(i, _)| i)
+            .collect::<Vec<_>>();
+        let batch_params = missing
+            .iter()
+            .map(|i| {
+                let h256: H256 = hashes[*i].unpack();
+                json!([h256])
+            })
+            .collect();
+        let fetched: Vec<Option<HeaderView>> =
+            self.batch_request("get_header", batch_params).await?;
+        for (i, header) in missing.into_iter().zip(fetched) {
+            if let Some(ref header) = header {
+                self.headers_cache.put(header.hash.pack(), header.clone());
+            }
+            headers[i] = header;
+        }
+        Ok(headers)
+    }
+
+    /// Look up the header in the headers cache, falling back to the blocks cache
+    fn lookup_header(&self, hash: &Byte32) -> Option<HeaderView> {
+        self.headers_cache.get(hash).or_else(|| {
+            self.blocks_cache
+                .get_uncounted(hash)
+                .map(|block| block.header)
+        })
+    }
+
+    /// Get the headers by numbers. Cached headers are returned directly, the remaining are
+    /// fetched in one batch request. Headers not found are `None`.
+    pub async fn get_headers_by_numbers(
+        &self,
+        numbers: Vec<CoreBlockNumber>,
+    ) -> Result<Vec<Option<HeaderView>>, Error> {
+        let mut headers: Vec<Option<HeaderView>> = numbers
+            .iter()
+            .map(|number| self.headers_by_number_cache.get(number))
+            .collect();
+        let missing = headers
+            .iter()
+            .enumerate()
+            .filter(|(_, header)| header.is_none())
+            .map(|(i, _)| i)
+            .collect::<Vec<_>>();
+        let batch_params = missing
+            .iter()
+            .map(|i| json!([Uint64::from(numbers[*i])]))
+            .collect();
+        let fetched: Vec<Option<HeaderView>> = self
+            .batch_request("get_header_by_number", batch_params)
+            .await?;
+        for (i, header) in missing.into_iter().zip(fetched) {
+            if let Some(ref header) = header {
+                self.headers_by_number_cache.put(numbers[i], header.clone());
+            }
+            headers[i] = header;
+        }
+        Ok(headers)
+    }
+
+    /// Get the fork blocks by hashes in one batch request. Blocks not found are `None`.
+    pub async fn get_fork_blocks(
+        &self,
+        hashes: Vec<Byte32>,
+    ) -> Result<Vec<Option<BlockView>>, Error> {
+        let batch_params = hashes
+            .into_iter()
+            .map(|hash| {
+                let h256: H256 = hash.unpack();
+                json!([h256])
+            })
+            .collect();
+        self.batch_request("get_fork_block", batch_params).await
+    }
+
+    pub async fn get_block_by_number(&self, number: CoreBlockNumber) -> Result<BlockView, Error> {
+        let json_number: Uint64 = number.into();
+        self.request_some("get_block_by_number", json!([json_number]))
+            .await
+    }
+
+    pub async fn get_transaction(&self, hash: Byte32) -> Result<TransactionWithStatus, Error> {
+        let h256: H256 = hash.unpack();
+        self.request_some("get_transaction", json!([h256])).await
+    }
+
+    pub async fn get_block_hash(&self, number: CoreBlockNumber) -> Result<H256, Error> {
+        let json_number: Uint64 = number.into();
+        self.request_some("get_block_hash", json!([json_number]))
+            .await
+    }
+
+    pub async fn get_tip_header(&self) -> Result<HeaderView, Error> {
+        self.request("get_tip_header", json!([])).await
+    }
+
+    pub async fn get_header_by_number(&self, number: CoreBlockNumber) -> Result<HeaderView, Error> {
+        if let Some(header) = self.headers_by_number_cache.get(&number) {
+            return Ok(header);
+        }
+
+        let json_number: Uint64 = number.into();
+        let header: HeaderView = self
+            .request_some("get_header_by_number", json!([json_number]))
+            .await?;
+        self.headers_by_number_cache.put(number, header.clone());
+        Ok(header)
+    }
+
+    pub async fn get_live_cell(&self, out_point: OutPoint) -> Result<CellWithStatus, Error> {
+        self.request("get_live_cell", json!([out_point, false]))
+            .await
+    }
+
+    pub async fn get_tip_block_number(&self) -> Result<CoreBlockNumber, Error> {
+        let number: Uint64 = self.request("get_tip_block_number", json!([])).await?;
+        Ok(number.into())
+    }
+
+    pub async fn local_node_info(&self) -> Result<LocalNode, Error> {
+        self.request("local_node_info", json!([])).await
+    }
+
+    pub async fn get_peers(&self) -> Result<Vec<RemoteNode>, Error> {
+        self.request("get_peers", json!([])).await
+    }
+
+    pub async fn get_block_template(
+        &self,
+        bytes_limit: Option<u64>,
+        proposals_limit: Option<u64>,
+        max_version: Option<CoreVersion>,
+    ) -> Result<BlockTemplate, Error> {
+        let bytes_limit: Option<Uint64> = bytes_limit.map(Into::into);
+        let proposals_limit: Option<Uint64> = proposals_limit.map(Into::into);
+        let max_version: Option<Version> = max_version.map(Into::into);
+        self.request(
+            "get_block_template",
+            json!([bytes_limit, proposals_limit, max_version]),
+        )
+        .await
+    }
+
+    pub async fn submit_block(&self, work_id: String, block: Block) -> Result<H256, Error> {
+        self.request("submit_block", json!([work_id, block])).await
+    }
+
+    pub async fn get_blockchain_info(&self) -> Result<ChainInfo, Error> {
+        self.request("get_blockchain_info", json!([])).await
+    }
+
+    pub async fn send_transaction(&self, tx: Transaction) -> Result<H256, Error> {
+        self.request("send_transaction", json!([tx])).await
+    }
+
+    pub async fn broadcast_transaction(&self, tx: Transaction) -> Result<H256, Error> {
+        self.request("broadcast_transaction", json!([tx])).await
+    }
+
+    pub async fn tx_pool_info(&self) -> Result<TxPoolInfo, Error> {
+        self.request("tx_pool_info", json!([])).await
+    }
+
+    pub async fn get_current_epoch(&self) -> Result<EpochView, Error> {
+        self.request("get_current_epoch", json!([])).await
+    }
+
+    pub async fn get_epoch_by_number(&self, number: CoreEpochNumber) -> Result<EpochView, Error> {
+        let json_number: Uint64 = number.into();
+        self.request_some("get_epoch_by_number", json!([json_number]))
+            .await
+    }
+
+    pub async fn get_fork_block(&self, hash: Byte32) -> Result<BlockView, Error> {
+        let h256: H256 = hash.unpack();
+        self.request_some("get_fork_block", json!([h256])).await
+    }
+
+    pub async fn add_node(&self, peer_id: String, address: String) -> Result<(), Error> {
+        self.request("add_node", json!([peer_id, address])).await
+    }
+
+    pub async fn remove_node(&self, peer_id: String) -> Result<(), Error> {
+        self.request("remove_node", json!([peer_id])).await
+    }
+
+    pub async fn process_block_without_verify(&self, block: Block) -> Result<Option<H256>, Error> {
+        self.request("process_block_without_verify", json!([block]))
+            .await
+    }
+
+    pub async fn get_consensus(&self) -> Result<Consensus, Error> {
+        self.request("get_consensus", json!([])).await
+    }
+
+    pub async fn get_raw_tx_pool(&self, verbose: bool) -> Result<RawTxPool, Error> {
+        self.request("get_raw_tx_pool", json!([verbose])).await
+    }
+
+    pub async fn get_cellbase_output_capacity_details(
+        &self,
+        hash: Byte32,
+    ) -> Result<BlockReward, Error> {
+        let h256: H256 = hash.unpack();
+        self.request_some("get_cellbase_output_capacity_details", json!([h256]))
+            .await
+    }
+
+    /// Not found until the block is finalized, i.e. 11 blocks after it
+    pub async fn get_block_economic_state(
+        &self,
+        hash: Byte32,
+    ) -> Result<BlockEconomicState, Error> {
+        let h256: H256 = hash.unpack();
+        self.request_some("get_block_economic_state", json!([h256]))
+            .await
+    }
+
+    pub async fn sync_state(&self) -> Result<SyncState, Error> {
+        self.request("sync_state", json!([])).await
+    }
+
+    pub async fn get_banned_addresses(&self) -> Result<Vec<BannedAddr>, Error> {
+        self.request("get_banned_addresses", json!([])).await
+    }
+
+    pub async fn get_transaction_proof(
+        &self,
+        tx_hashes: Vec<Byte32>,
+        block_hash: Option<Byte32>,
+    ) -> Result<TransactionProof, Error> {
+        let tx_hashes: Vec<H256> = tx_hashes.iter().map(Unpack::unpack).collect();
+        let block_hash: Option<H256> = block_hash.map(|hash| hash.unpack());
+        self.request("get_transaction_proof", json!([tx_hashes, block_hash]))
+            .await
+    }
+
+    /// Indexer method, the client should be connected to a ckb-indexer
+    pub async fn get_cells(
+        &self,
+        search_key: SearchKey,
+        order: Order,
+        limit: u32,
+        after_cursor: Option<JsonBytes>,
+    ) -> Result<Pagination<Cell>, Error> {
+        let limit: Uint32 = limit.into();
+        self.request("get_cells", json!([search_key, order, limit, after_cursor]))
+            .await
+    }
+
+    /// Indexer method, the client should be connected to a ckb-indexer
+    pub async fn get_transactions(
+        &self,
+        search_key: SearchKey,
+        order: Order,
+        limit: u32,
+        after_cursor: Option<JsonBytes>,
+    ) -> Result<Pagination<Tx>, Error> {
+        let limit: Uint32 = limit.into();
+        self.request(
+            "get_transactions",
+            json!([search_key, order, limit, after_cursor]),
+        )
+        .await
+    }
+
+    /// Indexer method, the client should be connected to a ckb-indexer
+    pub async fn get_cells_capacity(&self, search_key: SearchKey) -> Result<CellsCapacity, Error> {
+        self.request_some("get_cells_capacity", json!([search_key]))
+            .await
+    }
+}
@@ -0,0 +1,74 @@
+use ckb_util::Mutex;
+use lru::LruCache;
+use std::hash::Hash;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+/// Statistics of a cache. `hits` and `misses` are accumulated since the client connected.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct CacheStats {
+    pub hits: u64,
+    pub misses: u64,
+    pub len: usize,
+    pub capacity: usize,
+}
+
+/// LRU cache counting hits and misses
+pub(crate) struct Cache<K: Hash + Eq, V> {
+    lru: Mutex<LruCache<K, V>>,
+    hits: AtomicU64,
+    misses: AtomicU64,
+}
+
+impl<K: Hash + Eq + Clone, V: Clone> Cache<K, V> {
+    pub(crate) fn new(capacity: usize) -> Self {
+        Self {
+            lru: Mutex::new(LruCache::new(capacity)),
+            hits: AtomicU64::new(0),
+            misses: AtomicU64::new(0),
+        }
+    }
+
+    pub(crate) fn get(&self, key: &K) -> Option<V> {
+        let value = self.get_uncounted(key);
+        if value.is_some() {
+            self.hits.fetch_add(1, Ordering::Relaxed);
+        } else {
+            self.misses.fetch_add(1, Ordering::Relaxed);
+        }
+        value
+    }
+
+    /// Like `get`, but not counted. Used when looking up another cache as a fallback, e.g. headers
+    /// from the blocks cache.
+    pub(crate) fn get_uncounted(&self, key: &K) -> Option<V> {
+        self.lru.lock().get(key).cloned()
+    }
+
+    pub(crate) fn put(&self, key: K, value: V) {
+        self.lru.lock().put(key, value);
+    }
+
+    /// Remove the entries whose keys do not satisfy `keep`
+    pub(crate) fn retain<F: Fn(&K) -> bool>(&self, keep: F) {
+        let mut lru = self.lru.lock();
+        let removed = lru
+            .iter()
+            .map(|(key, _)| key)
+            .filter(|key| !keep(key))
+            .cloned()
+            .collect::<Vec<_>>();
+        for key in removed {
+            lru.pop(&key);
+        }
+    }
+
+    pub(crate) fn stats(&self) -> CacheStats {
+        let lru = self.lru.lock();
+        CacheStats {
+            hits: self.hits.load(Ordering::Relaxed),
+            misses: self.misses.load(Ordering::Relaxed),
+            len: lru.len(),
+            capacity: lru.cap(),
+        }
+    }
+}
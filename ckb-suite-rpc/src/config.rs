@@ -0,0 +1,44 @@
+use std::time::Duration;
+
+#[derive(Debug, Clone)]
+pub struct ClientConfig {
+    /// Timeout of every single HTTP request
+    pub timeout: Duration,
+    /// How many times an idempotent request is retried after transport failures or timeouts
+    pub max_retries: u32,
+    /// Backoff before the first retry, doubled on every following retry
+    pub retry_backoff: Duration,
+    /// Token-bucket refill rate, in requests per second. `None` or `Some(0)` means unlimited. A
+    /// batch request costs one token, the same as a single request
+    pub rate_limit: Option<u32>,
+    /// Token-bucket capacity, i.e. how many requests can be sent at once after idle
+    pub rate_limit_burst: u32,
+    /// How long an unhealthy endpoint is skipped before being probed again
+    pub health_check_interval: Duration,
+    /// Capacity of the blocks cache, keyed by block hash
+    pub blocks_cache_size: usize,
+    /// Capacity of the headers cache, keyed by block hash
+    pub headers_cache_size: usize,
+    /// Capacity of the canonical headers cache, keyed by block number
+    pub headers_by_number_cache_size: usize,
+    /// The endpoints are ckb-indexers, which do not serve `get_consensus`. They are accepted when
+    /// the probe is answered with "Method not found", without the genesis check
+    pub indexer_only: bool,
+}
+
+impl Default for ClientConfig {
+    fn default() -> Self {
+        Self {
+            timeout: Duration::from_secs(30),
+            max_retries: 3,
+            retry_backoff: Duration::from_millis(500),
+            rate_limit: None,
+            rate_limit_burst: 1,
+            health_check_interval: Duration::from_secs(10),
+            blocks_cache_size: 1000,
+            headers_cache_size: 1000,
+            headers_by_number_cache_size: 1000,
+            indexer_only: false,
+        }
+    }
+}
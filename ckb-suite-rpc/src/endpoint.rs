@@ -0,0 +1,72 @@
+use ckb_util::Mutex;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, Instant};
+
+/// One of the nodes which the client may send requests to.
+pub(crate) struct Endpoint {
+    pub(crate) uri: String,
+    state: Mutex<State>,
+    served: AtomicU64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum State {
+    /// Not probed yet
+    Unknown,
+    Healthy,
+    /// Failed recently, probe it again after the instant
+    Unhealthy(Instant),
+    /// Serves a chain with a different genesis, never use it unless the genesis is pinned again
+    Mismatched,
+}
+
+impl Endpoint {
+    pub(crate) fn new(uri: String) -> Self {
+        Self {
+            uri,
+            state: Mutex::new(State::Unknown),
+            served: AtomicU64::new(0),
+        }
+    }
+
+    /// Whether the endpoint should be probed before serving requests
+    pub(crate) fn needs_probe(&self) -> bool {
+        match *self.state.lock() {
+            State::Unknown => true,
+            State::Unhealthy(probe_at) => Instant::now() >= probe_at,
+            State::Healthy | State::Mismatched => false,
+        }
+    }
+
+    pub(crate) fn is_healthy(&self) -> bool {
+        *self.state.lock() == State::Healthy
+    }
+
+    pub(crate) fn is_mismatched(&self) -> bool {
+        *self.state.lock() == State::Mismatched
+    }
+
+    pub(crate) fn mark_unknown(&self) {
+        *self.state.lock() = State::Unknown;
+    }
+
+    pub(crate) fn mark_healthy(&self) {
+        *self.state.lock() = State::Healthy;
+    }
+
+    pub(crate) fn mark_unhealthy(&self, probe_after: Duration) {
+        *self.state.lock() = State::Unhealthy(Instant::now() + probe_after);
+    }
+
+    pub(crate) fn mark_mismatched(&self) {
+        *self.state.lock() = State::Mismatched;
+    }
+
+    pub(crate) fn record_served(&self) {
+        self.served.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub(crate) fn served(&self) -> u64 {
+        self.served.load(Ordering::Relaxed)
+    }
+}
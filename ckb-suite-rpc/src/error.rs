@@ -0,0 +1,62 @@
+use std::fmt;
+
+#[derive(Debug)]
+pub enum Error {
+    /// Failed to send the request or receive the response
+    Transport(hyper::Error),
+    /// The request did not complete in time
+    Timeout,
+    /// None of the endpoints is healthy
+    NoAvailableEndpoint,
+    /// The node responded with a JSON-RPC error object
+    Rpc { code: i64, message: String },
+    /// The requested object does not exist on the node
+    NotFound(String),
+    /// The response is not a valid JSON-RPC response of the expected type
+    Decode(serde_json::Error),
+    /// The responder has no response to the request, e.g. it is not in the replayed capture
+    NotRecorded(String),
+}
+
+impl Error {
+    /// Transport failures, timeouts and unavailable endpoints may succeed by retrying the same
+    /// request later.
+    pub fn is_transient(&self) -> bool {
+        matches!(
+            self,
+            Error::Transport(_) | Error::Timeout | Error::NoAvailableEndpoint
+        )
+    }
+
+    pub fn is_not_found(&self) -> bool {
+        matches!(self, Error::NotFound(_))
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Transport(err) => write!(f, "transport error: {}", err),
+            Error::Timeout => write!(f, "request timeout"),
+            Error::NoAvailableEndpoint => write!(f, "no available endpoint"),
+            Error::Rpc { code, message } => write!(f, "rpc error {}: {}", code, message),
+            Error::NotFound(what) => write!(f, "not found: {}", what),
+            Error::Decode(err) => write!(f, "decode error: {}", err),
+            Error::NotRecorded(what) => write!(f, "not recorded: {}", what),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+impl From<hyper::Error> for Error {
+    fn from(err: hyper::Error) -> Self {
+        Error::Transport(err)
+    }
+}
+
+impl From<serde_json::Error> for Error {
+    fn from(err: serde_json::Error) -> Self {
+        Error::Decode(err)
+    }
+}
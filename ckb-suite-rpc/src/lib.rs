@@ -1,5 +1,18 @@
+mod async_rpc;
+mod cache;
+mod config;
+mod endpoint;
+mod error;
+pub mod indexer;
+mod rate_limit;
+mod record;
 mod rpc;
 
+pub use async_rpc::AsyncJsonrpc;
+pub use cache::CacheStats;
+pub use config::ClientConfig;
+pub use error::Error;
+pub use record::{Recorder, Responder};
 pub use rpc::Jsonrpc;
 
 pub use ckb_jsonrpc_types;
@@ -0,0 +1,49 @@
+use ckb_util::Mutex;
+use std::time::{Duration, Instant};
+
+/// Token-bucket rate limiter. The bucket holds at most `capacity` tokens and is refilled with
+/// `rate` tokens per second; every request takes one token.
+pub(crate) struct RateLimiter {
+    rate: f64,
+    capacity: f64,
+    bucket: Mutex<Bucket>,
+}
+
+struct Bucket {
+    tokens: f64,
+    refilled_at: Instant,
+}
+
+impl RateLimiter {
+    pub(crate) fn new(rate: u32, capacity: u32) -> Self {
+        assert!(rate > 0, "rate limit should be greater than 0");
+        let capacity = f64::from(capacity.max(1));
+        Self {
+            rate: f64::from(rate),
+            capacity,
+            bucket: Mutex::new(Bucket {
+                tokens: capacity,
+                refilled_at: Instant::now(),
+            }),
+        }
+    }
+
+    /// Wait until a token is available, then take it
+    pub(crate) async fn acquire(&self) {
+        loop {
+            let wait = {
+                let mut bucket = self.bucket.lock();
+                let now = Instant::now();
+                let elapsed = now.duration_since(bucket.refilled_at).as_secs_f64();
+                bucket.tokens = (bucket.tokens + elapsed * self.rate).min(self.capacity);
+                bucket.refilled_at = now;
+                if bucket.tokens >= 1.0 {
+                    bucket.tokens -= 1.0;
+                    return;
+                }
+                Duration::from_secs_f64((1.0 - bucket.tokens) / self.rate)
+            };
+            tokio::time::sleep(wait).await;
+        }
+    }
+}
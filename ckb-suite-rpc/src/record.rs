@@ -0,0 +1,71 @@
+use crate::error::Error;
+use hyper::body::Bytes;
+use serde_json::{json, Value};
+
+/// Receives every JSON-RPC call served by the endpoints, e.g. to capture them into a file.
+/// `response` is the response object without "jsonrpc" and "id", i.e. `{"result": ..}` or
+/// `{"error": ..}`.
+pub trait Recorder: Send + Sync {
+    fn record(&self, method: &str, params: &Value, response: &Value);
+}
+
+/// Answers JSON-RPC calls in place of the endpoints, e.g. from a captured file. The returned
+/// response has the same form as the one passed to [`Recorder::record`]; `None` means the call
+/// cannot be answered.
+pub trait Responder: Send + Sync {
+    fn respond(&self, method: &str, params: &Value) -> Option<Value>;
+}
+
+/// Split the body into single calls and the raw response into the responses of them, paired by id
+pub(crate) fn record_exchange(recorder: &dyn Recorder, body: &Value, response: &[u8]) {
+    let response: Value = match serde_json::from_slice(response) {
+        Ok(response) => response,
+        Err(_) => return,
+    };
+    let calls = match body {
+        Value::Array(calls) => calls.iter().collect::<Vec<_>>(),
+        call => vec![call],
+    };
+    let responses = match response {
+        Value::Array(responses) => responses,
+        response => vec![response],
+    };
+    for call in calls {
+        if let Some(response) = responses
+            .iter()
+            .find(|response| response["id"] == call["id"])
+        {
+            let mut response = response.clone();
+            if let Some(response) = response.as_object_mut() {
+                response.remove("jsonrpc");
+                response.remove("id");
+            }
+            recorder.record(
+                call["method"].as_str().unwrap_or_default(),
+                &call["params"],
+                &response,
+            );
+        }
+    }
+}
+
+/// Build the raw response of the body from the responder
+pub(crate) fn respond_locally(responder: &dyn Responder, body: &Value) -> Result<Bytes, Error> {
+    let respond = |call: &Value| -> Result<Value, Error> {
+        let method = call["method"].as_str().unwrap_or_default();
+        let params = &call["params"];
+        let mut response = responder
+            .respond(method, params)
+            .ok_or_else(|| Error::NotRecorded(format!("{}({})", method, params)))?;
+        if let Some(response) = response.as_object_mut() {
+            response.insert("jsonrpc".to_string(), json!("2.0"));
+            response.insert("id".to_string(), call["id"].clone());
+        }
+        Ok(response)
+    };
+    let response = match body {
+        Value::Array(calls) => Value::Array(calls.iter().map(respond).collect::<Result<_, _>>()?),
+        call => respond(call)?,
+    };
+    Ok(Bytes::from(response.to_string()))
+}
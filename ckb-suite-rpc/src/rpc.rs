@@ -1,247 +1,136 @@
+use crate::async_rpc::AsyncJsonrpc;
+use crate::cache::CacheStats;
+use crate::config::ClientConfig;
+use crate::error::Error;
+use crate::indexer::{Cell, CellsCapacity, Order, Pagination, SearchKey, Tx};
 use ckb_jsonrpc_types::{
-    Block, BlockNumber, BlockTemplate, BlockView, CellWithStatus, ChainInfo, Consensus,
-    DryRunResult, EpochNumber, EpochView, HeaderView, LocalNode, OutPoint, RemoteNode, Transaction,
-    TransactionWithStatus, TxPoolInfo, Uint64, Version,
+    BannedAddr, Block, BlockEconomicState, BlockReward, BlockTemplate, BlockView, CellWithStatus,
+    ChainInfo, Consensus, EpochView, HeaderView, JsonBytes, LocalNode, OutPoint, RawTxPool,
+    RemoteNode, SyncState, Transaction, TransactionProof, TransactionWithStatus, TxPoolInfo,
 };
 use ckb_types::{
     core::{
         BlockNumber as CoreBlockNumber, EpochNumber as CoreEpochNumber, Version as CoreVersion,
     },
     packed::Byte32,
-    prelude::*,
     H256,
 };
-use ckb_util::Mutex;
-use hyper::header::{Authorization, Basic};
-use jsonrpc_client_core::{expand_params, jsonrpc_client, Result as JsonRpcResult};
-use jsonrpc_client_http::{HttpHandle, HttpTransport};
-use lru::LruCache;
-use std::env::var;
+use std::ops::Range;
 use std::sync::Arc;
+use tokio::runtime::Runtime;
 
+/// Blocking JSON-RPC client of CKB. It is a thin wrapper of [`AsyncJsonrpc`] which drives the
+/// requests on its own runtime, so never call it inside an async context.
 #[derive(Clone)]
 pub struct Jsonrpc {
-    uri: String,
-    inner: Arc<Mutex<Inner<HttpHandle>>>,
-    blocks_cache: Arc<Mutex<LruCache<Byte32, BlockView>>>,
-    headers_cache: Arc<Mutex<LruCache<Byte32, HeaderView>>>,
-}
-
-pub fn env_username() -> String {
-    var("CKB_RPC_USERNAME").unwrap_or_else(|_| "".to_owned())
-}
-
-pub fn env_password() -> String {
-    var("CKB_RPC_PASSWORD").unwrap_or_else(|_| "".to_owned())
+    inner: AsyncJsonrpc,
+    runtime: Arc<Runtime>,
 }
 
 impl Jsonrpc {
-    pub fn connect(uri: &str) -> Self {
-        let transport = HttpTransport::new().standalone().unwrap();
-        let mut transport = transport
-            .handle(uri)
-            .unwrap_or_else(|err| panic!("Jsonrpc::connect(\"{}\"), error: {:?}", uri, err));
-
-        if !env_username().is_empty() && !env_password().is_empty() {
-            transport.set_header(Authorization(Basic {
-                username: env_username(),
-                password: Some(env_password()),
-            }));
-        }
+    pub fn connect<U: AsRef<str>>(uris: &[U]) -> Self {
+        Self::connect_with_config(uris, ClientConfig::default())
+    }
+
+    pub fn connect_with_config<U: AsRef<str>>(uris: &[U], config: ClientConfig) -> Self {
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .unwrap_or_else(|err| panic!("Jsonrpc::connect, error: {:?}", err));
         Self {
-            uri: uri.to_string(),
-            inner: Arc::new(Mutex::new(Inner::new(transport))),
-            blocks_cache: Arc::new(Mutex::new(LruCache::new(1000))),
-            headers_cache: Arc::new(Mutex::new(LruCache::new(1000))),
+            inner: AsyncJsonrpc::connect_with_config(uris, config),
+            runtime: Arc::new(runtime),
         }
     }
 
     pub fn uri(&self) -> &String {
-        &self.uri
+        self.inner.uri()
+    }
+
+    pub fn served_counts(&self) -> Vec<(String, u64)> {
+        self.inner.served_counts()
     }
 
-    pub fn inner(&self) -> &Mutex<Inner<HttpHandle>> {
+    pub fn cache_stats(&self) -> Vec<(&'static str, CacheStats)> {
+        self.inner.cache_stats()
+    }
+
+    pub fn invalidate_headers_since(&self, number: CoreBlockNumber) {
+        self.inner.invalidate_headers_since(number)
+    }
+
+    pub fn inner(&self) -> &AsyncJsonrpc {
         &self.inner
     }
 
-    pub fn get_block(&self, hash: Byte32) -> Option<BlockView> {
-        if let Some(block) = self.blocks_cache.lock().get(&hash) {
-            return Some(block.clone());
-        }
+    pub fn get_block(&self, hash: Byte32) -> Result<BlockView, Error> {
+        self.runtime.block_on(self.inner.get_block(hash))
+    }
 
-        let ret = self
-            .inner
-            .lock()
-            .get_block(hash.unpack())
-            .call()
-            .unwrap_or_else(|err| {
-                panic!(
-                    "Jsonrpc::get_block(\"{}\", {}), error: {:?}",
-                    self.uri(),
-                    hash,
-                    err
-                )
-            });
-
-        if let Some(ref block) = ret {
-            self.blocks_cache
-                .lock()
-                .put(block.header.hash.pack(), block.clone());
-        }
-        ret
+    pub fn get_header(&self, hash: Byte32) -> Result<HeaderView, Error> {
+        self.runtime.block_on(self.inner.get_header(hash))
     }
 
-    pub fn get_header(&self, hash: Byte32) -> Option<HeaderView> {
-        if let Some(header) = self.headers_cache.lock().get(&hash) {
-            return Some(header.clone());
-        }
-        if let Some(block) = self.blocks_cache.lock().get(&hash) {
-            return Some(block.header.clone());
-        }
+    pub fn get_blocks_by_numbers(
+        &self,
+        numbers: Range<CoreBlockNumber>,
+    ) -> Result<Vec<Option<BlockView>>, Error> {
+        self.runtime
+            .block_on(self.inner.get_blocks_by_numbers(numbers))
+    }
 
-        let ret = self
-            .inner
-            .lock()
-            .get_header(hash.unpack())
-            .call()
-            .unwrap_or_else(|err| {
-                panic!(
-                    "Jsonrpc::get_header(\"{}\", {}), error: {:?}",
-                    self.uri(),
-                    hash,
-                    err
-                )
-            });
-        if let Some(ref header) = ret {
-            self.headers_cache
-                .lock()
-                .put(header.hash.pack(), header.clone());
-        }
-        ret
-    }
-
-    pub fn get_block_by_number(&self, number: CoreBlockNumber) -> Option<BlockView> {
-        self.inner
-            .lock()
-            .get_block_by_number(number.into())
-            .call()
-            .unwrap_or_else(|err| {
-                panic!(
-                    "Jsonrpc::get_block_by_number(\"{}\", {}), error: {:?}",
-                    self.uri(),
-                    number,
-                    err
-                )
-            })
-    }
-
-    pub fn get_transaction(&self, hash: Byte32) -> Option<TransactionWithStatus> {
-        self.inner
-            .lock()
-            .get_transaction(hash.unpack())
-            .call()
-            .unwrap_or_else(|err| {
-                panic!(
-                    "Jsonrpc::get_transaction(\"{}\", {}), error: {:?}",
-                    self.uri(),
-                    hash,
-                    err
-                )
-            })
-    }
-
-    pub fn get_block_hash(&self, number: CoreBlockNumber) -> Option<H256> {
-        self.inner
-            .lock()
-            .get_block_hash(number.into())
-            .call()
-            .unwrap_or_else(|err| {
-                panic!(
-                    "Jsonrpc::get_block_hash(\"{}\", {}), error: {:?}",
-                    self.uri(),
-                    number,
-                    err
-                )
-            })
-    }
-
-    pub fn get_tip_header(&self) -> HeaderView {
-        self.inner
-            .lock()
-            .get_tip_header()
-            .call()
-            .unwrap_or_else(|err| {
-                panic!(
-                    "Jsonrpc::get_tip_header(\"{}\"), error: {:?}",
-                    self.uri(),
-                    err
-                )
-            })
-    }
-
-    pub fn get_header_by_number(&self, number: CoreBlockNumber) -> Option<HeaderView> {
-        self.inner
-            .lock()
-            .get_header_by_number(number.into())
-            .call()
-            .unwrap_or_else(|err| {
-                panic!(
-                    "Jsonrpc::get_header_by_number(\"{}\", {}), error: {:?}",
-                    self.uri(),
-                    number,
-                    err
-                )
-            })
-    }
-
-    pub fn get_live_cell(&self, out_point: OutPoint) -> CellWithStatus {
-        self.inner
-            .lock()
-            .get_live_cell(out_point.clone())
-            .call()
-            .unwrap_or_else(|err| {
-                panic!(
-                    "Jsonrpc::get_live_cell(\"{}\", {:?}), error: {:?}",
-                    self.uri(),
-                    out_point,
-                    err
-                )
-            })
-    }
-
-    pub fn get_tip_block_number(&self) -> CoreBlockNumber {
-        self.inner
-            .lock()
-            .get_tip_block_number()
-            .call()
-            .unwrap_or_else(|err| {
-                panic!(
-                    "Jsonrpc::get_tip_block_number(\"{}\"), error: {:?}",
-                    self.uri(),
-                    err
-                )
-            })
-            .into()
-    }
-
-    pub fn local_node_info(&self) -> LocalNode {
-        self.inner
-            .lock()
-            .local_node_info()
-            .call()
-            .unwrap_or_else(|err| {
-                panic!(
-                    "Jsonrpc::local_node_info(\"{}\"), error: {:?}",
-                    self.uri(),
-                    err
-                )
-            })
-    }
-
-    pub fn get_peers(&self) -> Vec<RemoteNode> {
-        self.inner.lock().get_peers().call().unwrap_or_else(|err| {
-            panic!("Jsonrpc::get_peers(\"{}\"), error: {:?}", self.uri(), err)
-        })
+    pub fn get_headers(&self, hashes: Vec<Byte32>) -> Result<Vec<Option<HeaderView>>, Error> {
+        self.runtime.block_on(self.inner.get_headers(hashes))
+    }
+
+    pub fn get_headers_by_numbers(
+        &self,
+        numbers: Vec<CoreBlockNumber>,
+    ) -> Result<Vec<Option<HeaderView>>, Error> {
+        self.runtime
+            .block_on(self.inner.get_headers_by_numbers(numbers))
+    }
+
+    pub fn get_fork_blocks(&self, hashes: Vec<Byte32>) -> Result<Vec<Option<BlockView>>, Error> {
+        self.runtime.block_on(self.inner.get_fork_blocks(hashes))
+    }
+
+    pub fn get_block_by_number(&self, number: CoreBlockNumber) -> Result<BlockView, Error> {
+        self.runtime
+            .block_on(self.inner.get_block_by_number(number))
+    }
+
+    pub fn get_transaction(&self, hash: Byte32) -> Result<TransactionWithStatus, Error> {
+        self.runtime.block_on(self.inner.get_transaction(hash))
+    }
+
+    pub fn get_block_hash(&self, number: CoreBlockNumber) -> Result<H256, Error> {
+        self.runtime.block_on(self.inner.get_block_hash(number))
+    }
+
+    pub fn get_tip_header(&self) -> Result<HeaderView, Error> {
+        self.runtime.block_on(self.inner.get_tip_header())
+    }
+
+    pub fn get_header_by_number(&self, number: CoreBlockNumber) -> Result<HeaderView, Error> {
+        self.runtime
+            .block_on(self.inner.get_header_by_number(number))
+    }
+
+    pub fn get_live_cell(&self, out_point: OutPoint) -> Result<CellWithStatus, Error> {
+        self.runtime.block_on(self.inner.get_live_cell(out_point))
+    }
+
+    pub fn get_tip_block_number(&self) -> Result<CoreBlockNumber, Error> {
+        self.runtime.block_on(self.inner.get_tip_block_number())
+    }
+
+    pub fn local_node_info(&self) -> Result<LocalNode, Error> {
+        self.runtime.block_on(self.inner.local_node_info())
+    }
+
+    pub fn get_peers(&self) -> Result<Vec<RemoteNode>, Error> {
+        self.runtime.block_on(self.inner.get_peers())
     }
 
     pub fn get_block_template(
@@ -249,239 +138,122 @@ impl Jsonrpc {
         bytes_limit: Option<u64>,
         proposals_limit: Option<u64>,
         max_version: Option<CoreVersion>,
-    ) -> BlockTemplate {
-        let bytes_limit = bytes_limit.map(Into::into);
-        let proposals_limit = proposals_limit.map(Into::into);
-        let max_version = max_version.map(Into::into);
-        self.inner
-            .lock()
-            .get_block_template(bytes_limit, proposals_limit, max_version)
-            .call()
-            .unwrap_or_else(|err| {
-                panic!(
-                    "Jsonrpc::get_block_template(\"{}\", {:?}, {:?}, {:?}), error: {:?}",
-                    self.uri(),
-                    bytes_limit,
-                    proposals_limit,
-                    max_version,
-                    err
-                )
-            })
-    }
-
-    pub fn submit_block(&self, work_id: String, block: Block) -> Option<H256> {
-        self.inner
-            .lock()
-            .submit_block(work_id, block.clone())
-            .call()
-            .unwrap_or_else(|err| {
-                panic!(
-                    "Jsonrpc::submit_block(\"{}\", {:?}), error: {:?}",
-                    self.uri(),
-                    block,
-                    err
-                )
-            })
-    }
-
-    pub fn get_blockchain_info(&self) -> ChainInfo {
-        self.inner
-            .lock()
-            .get_blockchain_info()
-            .call()
-            .unwrap_or_else(|err| {
-                panic!(
-                    "Jsonrpc::get_blockchain_info(\"{}\"), error: {:?}",
-                    self.uri(),
-                    err
-                )
-            })
-    }
-
-    pub fn send_transaction(&self, tx: Transaction) -> H256 {
-        self.inner
-            .lock()
-            .send_transaction(tx.clone())
-            .call()
-            .unwrap_or_else(|err| {
-                panic!(
-                    "Jsonrpc::send_transaction(\"{}\", {:?}), error: {:?}",
-                    self.uri(),
-                    tx,
-                    err
-                )
-            })
-    }
-
-    pub fn broadcast_transaction(&self, tx: Transaction) -> H256 {
-        self.inner
-            .lock()
-            .broadcast_transaction(tx.clone())
-            .call()
-            .unwrap_or_else(|err| {
-                panic!(
-                    "Jsonrpc::broadcast_transaction(\"{}\", {:?}), error: {:?}",
-                    self.uri(),
-                    tx,
-                    err
-                )
-            })
-    }
-
-    pub fn send_transaction_result(&self, tx: Transaction) -> JsonRpcResult<H256> {
-        self.inner.lock().send_transaction(tx).call()
-    }
-
-    pub fn tx_pool_info(&self) -> TxPoolInfo {
-        self.inner
-            .lock()
-            .tx_pool_info()
-            .call()
-            .unwrap_or_else(|err| {
-                panic!(
-                    "Jsonrpc::tx_pool_info(\"{}\"), error: {:?}",
-                    self.uri(),
-                    err
-                )
-            })
-    }
-
-    pub fn get_current_epoch(&self) -> EpochView {
-        self.inner
-            .lock()
-            .get_current_epoch()
-            .call()
-            .unwrap_or_else(|err| {
-                panic!(
-                    "Jsonrpc::get_current_epoch(\"{}\"), error: {:?}",
-                    self.uri(),
-                    err
-                )
-            })
-    }
-
-    pub fn get_epoch_by_number(&self, number: CoreEpochNumber) -> Option<EpochView> {
-        self.inner
-            .lock()
-            .get_epoch_by_number(number.into())
-            .call()
-            .unwrap_or_else(|err| {
-                panic!(
-                    "Jsonrpc::get_epoch_by_number(\"{}\", {}), error: {:?}",
-                    self.uri(),
-                    number,
-                    err
-                )
-            })
-    }
-
-    pub fn get_fork_block(&self, hash: Byte32) -> Option<BlockView> {
-        self.inner
-            .lock()
-            .get_fork_block(hash.unpack())
-            .call()
-            .unwrap_or_else(|err| {
-                panic!(
-                    "Jsonrpc::get_fork_block(\"{}\", {}), error: {:?}",
-                    self.uri(),
-                    hash,
-                    err
-                )
-            })
-    }
-
-    pub fn add_node(&self, peer_id: String, address: String) {
-        self.inner
-            .lock()
-            .add_node(peer_id.clone(), address.clone())
-            .call()
-            .unwrap_or_else(|err| {
-                panic!(
-                    "Jsonrpc::add_node(\"{}\", {}, {}), error: {:?}",
-                    self.uri(),
-                    peer_id,
-                    address,
-                    err,
-                )
-            });
-    }
-
-    pub fn remove_node(&self, peer_id: String) {
-        self.inner
-            .lock()
-            .remove_node(peer_id.clone())
-            .call()
-            .unwrap_or_else(|err| {
-                panic!(
-                    "Jsonrpc::remove_node(\"{}\", {}), error: {:?}",
-                    self.uri(),
-                    peer_id,
-                    err
-                )
-            })
-    }
-
-    pub fn process_block_without_verify(&self, block: Block) -> Option<H256> {
-        self.inner
-            .lock()
-            .process_block_without_verify(block.clone())
-            .call()
-            .unwrap_or_else(|err| {
-                panic!(
-                    "Jsonrpc::process_block_without_verify(\"{}\", {:?}), error: {:?}",
-                    self.uri(),
-                    block,
-                    err
-                )
-            })
-    }
-
-    pub fn get_consensus(&self) -> Consensus {
-        self.inner
-            .lock()
-            .get_consensus()
-            .call()
-            .unwrap_or_else(|err| {
-                panic!(
-                    "Jsonrpc::get_consensus(\"{}\"), error: {:?}",
-                    self.uri(),
-                    err
-                )
-            })
+    ) -> Result<BlockTemplate, Error> {
+        self.runtime.block_on(self.inner.get_block_template(
+            bytes_limit,
+            proposals_limit,
+            max_version,
+        ))
     }
-}
 
-jsonrpc_client!(pub struct Inner {
-    pub fn get_block(&mut self, _hash: H256) -> RpcRequest<Option<BlockView>>;
-    pub fn get_header(&mut self, _hash: H256) -> RpcRequest<Option<HeaderView>>;
-    pub fn get_block_by_number(&mut self, _number: BlockNumber) -> RpcRequest<Option<BlockView>>;
-    pub fn get_header_by_number(&mut self, _number: BlockNumber) -> RpcRequest<Option<HeaderView>>;
-    pub fn get_transaction(&mut self, _hash: H256) -> RpcRequest<Option<TransactionWithStatus>>;
-    pub fn get_block_hash(&mut self, _number: BlockNumber) -> RpcRequest<Option<H256>>;
-    pub fn get_tip_header(&mut self) -> RpcRequest<HeaderView>;
-    pub fn get_live_cell(&mut self, _out_point: OutPoint) -> RpcRequest<CellWithStatus>;
-    pub fn get_tip_block_number(&mut self) -> RpcRequest<BlockNumber>;
-    pub fn local_node_info(&mut self) -> RpcRequest<LocalNode>;
-    pub fn get_peers(&mut self) -> RpcRequest<Vec<RemoteNode>>;
-    pub fn get_block_template(
-        &mut self,
-        bytes_limit: Option<Uint64>,
-        proposals_limit: Option<Uint64>,
-        max_version: Option<Version>
-    ) -> RpcRequest<BlockTemplate>;
-    pub fn submit_block(&mut self, _work_id: String, _data: Block) -> RpcRequest<Option<H256>>;
-    pub fn get_blockchain_info(&mut self) -> RpcRequest<ChainInfo>;
-    pub fn compute_transaction_hash(&mut self, tx: Transaction) -> RpcRequest<H256>;
-    pub fn dry_run_transaction(&mut self, _tx: Transaction) -> RpcRequest<DryRunResult>;
-    pub fn send_transaction(&mut self, tx: Transaction) -> RpcRequest<H256>;
-    pub fn broadcast_transaction(&mut self, tx: Transaction) -> RpcRequest<H256>;
-    pub fn tx_pool_info(&mut self) -> RpcRequest<TxPoolInfo>;
-    pub fn get_current_epoch(&mut self) -> RpcRequest<EpochView>;
-    pub fn get_epoch_by_number(&mut self, number: EpochNumber) -> RpcRequest<Option<EpochView>>;
-    pub fn get_fork_block(&mut self, _hash: H256) -> RpcRequest<Option<BlockView>>;
-
-    pub fn add_node(&mut self, peer_id: String, address: String) -> RpcRequest<()>;
-    pub fn remove_node(&mut self, peer_id: String) -> RpcRequest<()>;
-    pub fn process_block_without_verify(&mut self, _data: Block) -> RpcRequest<Option<H256>>;
-    pub fn get_consensus(&mut self) -> RpcRequest<Consensus>;
-});
+    pub fn submit_block(&self, work_id: String, block: Block) -> Result<H256, Error> {
+        self.runtime
+            .block_on(self.inner.submit_block(work_id, block))
+    }
+
+    pub fn get_blockchain_info(&self) -> Result<ChainInfo, Error> {
+        self.runtime.block_on(self.inner.get_blockchain_info())
+    }
+
+    pub fn send_transaction(&self, tx: Transaction) -> Result<H256, Error> {
+        self.runtime.block_on(self.inner.send_transaction(tx))
+    }
+
+    pub fn broadcast_transaction(&self, tx: Transaction) -> Result<H256, Error> {
+        self.runtime.block_on(self.inner.broadcast_transaction(tx))
+    }
+
+    pub fn tx_pool_info(&self) -> Result<TxPoolInfo, Error> {
+        self.runtime.block_on(self.inner.tx_pool_info())
+    }
+
+    pub fn get_current_epoch(&self) -> Result<EpochView, Error> {
+        self.runtime.block_on(self.inner.get_current_epoch())
+    }
+
+    pub fn get_epoch_by_number(&self, number: CoreEpochNumber) -> Result<EpochView, Error> {
+        self.runtime
+            .block_on(self.inner.get_epoch_by_number(number))
+    }
+
+    pub fn get_fork_block(&self, hash: Byte32) -> Result<BlockView, Error> {
+        self.runtime.block_on(self.inner.get_fork_block(hash))
+    }
+
+    pub fn add_node(&self, peer_id: String, address: String) -> Result<(), Error> {
+        self.runtime.block_on(self.inner.add_node(peer_id, address))
+    }
+
+    pub fn remove_node(&self, peer_id: String) -> Result<(), Error> {
+        self.runtime.block_on(self.inner.remove_node(peer_id))
+    }
+
+    pub fn process_block_without_verify(&self, block: Block) -> Result<Option<H256>, Error> {
+        self.runtime
+            .block_on(self.inner.process_block_without_verify(block))
+    }
+
+    pub fn get_consensus(&self) -> Result<Consensus, Error> {
+        self.runtime.block_on(self.inner.get_consensus())
+    }
+
+    pub fn get_raw_tx_pool(&self, verbose: bool) -> Result<RawTxPool, Error> {
+        self.runtime.block_on(self.inner.get_raw_tx_pool(verbose))
+    }
+
+    pub fn get_cellbase_output_capacity_details(&self, hash: Byte32) -> Result<BlockReward, Error> {
+        self.runtime
+            .block_on(self.inner.get_cellbase_output_capacity_details(hash))
+    }
+
+    pub fn get_block_economic_state(&self, hash: Byte32) -> Result<BlockEconomicState, Error> {
+        self.runtime
+            .block_on(self.inner.get_block_economic_state(hash))
+    }
+
+    pub fn sync_state(&self) -> Result<SyncState, Error> {
+        self.runtime.block_on(self.inner.sync_state())
+    }
+
+    pub fn get_banned_addresses(&self) -> Result<Vec<BannedAddr>, Error> {
+        self.runtime.block_on(self.inner.get_banned_addresses())
+    }
+
+    pub fn get_transaction_proof(
+        &self,
+        tx_hashes: Vec<Byte32>,
+        block_hash: Option<Byte32>,
+    ) -> Result<TransactionProof, Error> {
+        self.runtime
+            .block_on(self.inner.get_transaction_proof(tx_hashes, block_hash))
+    }
+
+    pub fn get_cells(
+        &self,
+        search_key: SearchKey,
+        order: Order,
+        limit: u32,
+        after_cursor: Option<JsonBytes>,
+    ) -> Result<Pagination<Cell>, Error> {
+        self.runtime
+            .block_on(self.inner.get_cells(search_key, order, limit, after_cursor))
+    }
+
+    pub fn get_transactions(
+        &self,
+        search_key: SearchKey,
+        order: Order,
+        limit: u32,
+        after_cursor: Option<JsonBytes>,
+    ) -> Result<Pagination<Tx>, Error> {
+        self.runtime.block_on(
+            self.inner
+                .get_transactions(search_key, order, limit, after_cursor),
+        )
+    }
+
+    pub fn get_cells_capacity(&self, search_key: SearchKey) -> Result<CellsCapacity, Error> {
+        self.runtime
+            .block_on(self.inner.get_cells_capacity(search_key))
+    }
+}
@@ -0,0 +1,154 @@
+// Every test crate uses a part of the helpers
+#![allow(dead_code)]
+
+use hyper::service::{make_service_fn, service_fn};
+use hyper::{Body, Request, Response, Server};
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::io;
+use std::net::SocketAddr;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+
+pub const GENESIS_HASH: &str = "0x92b197aa1fba0f63633922c61c92375c9c074a93e85963554f5499fe1450d0e5";
+pub const OTHER_GENESIS_HASH: &str =
+    "0x10639e0895502b5688a6be8cf69460d76541bfa4821629d86d62ba0aae3f9606";
+
+/// A local JSON-RPC server responding the scripted result of each method. Unscripted methods are
+/// responded with the "Method not found" error.
+pub struct MockServer {
+    pub uri: String,
+    requests: Arc<Mutex<Vec<Value>>>,
+    down: Arc<AtomicBool>,
+}
+
+impl MockServer {
+    /// Start a node, which responds `get_consensus` of `GENESIS_HASH` unless scripted
+    pub async fn start(mut results: Vec<(&str, Value)>) -> Self {
+        if results.iter().all(|(method, _)| *method != "get_consensus") {
+            results.push(("get_consensus", consensus(GENESIS_HASH)));
+        }
+        Self::start_indexer(results).await
+    }
+
+    /// Start an indexer, which does not serve `get_consensus`
+    pub async fn start_indexer(results: Vec<(&str, Value)>) -> Self {
+        let results: Arc<HashMap<String, Value>> = Arc::new(
+            results
+                .into_iter()
+                .map(|(method, result)| (method.to_string(), result))
+                .collect(),
+        );
+        let requests = Arc::new(Mutex::new(Vec::new()));
+        let down = Arc::new(AtomicBool::new(false));
+        let make_service = {
+            let requests = Arc::clone(&requests);
+            let down = Arc::clone(&down);
+            make_service_fn(move |_| {
+                let results = Arc::clone(&results);
+                let requests = Arc::clone(&requests);
+                let down = Arc::clone(&down);
+                async move {
+                    Ok::<_, io::Error>(service_fn(move |request| {
+                        handle(
+                            request,
+                            Arc::clone(&results),
+                            Arc::clone(&requests),
+                            Arc::clone(&down),
+                        )
+                    }))
+                }
+            })
+        };
+        let server = Server::bind(&SocketAddr::from(([127, 0, 0, 1], 0))).serve(make_service);
+        let uri = format!("http://{}", server.local_addr());
+        tokio::spawn(server);
+        Self {
+            uri,
+            requests,
+            down,
+        }
+    }
+
+    /// Drop the connection of every request while down, as if the server were unavailable
+    pub fn set_down(&self, down: bool) {
+        self.down.store(down, Ordering::SeqCst);
+    }
+
+    /// The params of the received requests of the method, in order
+    pub fn params_of(&self, method: &str) -> Vec<Value> {
+        self.requests
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|call| call["method"] == method)
+            .map(|call| call["params"].clone())
+            .collect()
+    }
+}
+
+async fn handle(
+    request: Request<Body>,
+    results: Arc<HashMap<String, Value>>,
+    requests: Arc<Mutex<Vec<Value>>>,
+    down: Arc<AtomicBool>,
+) -> Result<Response<Body>, io::Error> {
+    if down.load(Ordering::SeqCst) {
+        return Err(io::Error::new(io::ErrorKind::Other, "server is down"));
+    }
+    let bytes = hyper::body::to_bytes(request.into_body())
+        .await
+        .expect("read request body");
+    let body: Value = serde_json::from_slice(&bytes).expect("parse request body");
+    let response = match body {
+        Value::Array(batch) => Value::Array(
+            batch
+                .into_iter()
+                .map(|call| respond(call, &results, &requests))
+                .collect(),
+        ),
+        call => respond(call, &results, &requests),
+    };
+    Ok(Response::new(Body::from(response.to_string())))
+}
+
+fn respond(call: Value, results: &HashMap<String, Value>, requests: &Mutex<Vec<Value>>) -> Value {
+    requests.lock().unwrap().push(call.clone());
+    let id = call["id"].clone();
+    match results.get(call["method"].as_str().unwrap_or_default()) {
+        Some(result) => json!({ "jsonrpc": "2.0", "id": id, "result": result }),
+        None => json!({
+            "jsonrpc": "2.0",
+            "id": id,
+            "error": { "code": -32601, "message": "Method not found" },
+        }),
+    }
+}
+
+/// The mainnet consensus, except the genesis hash
+pub fn consensus(genesis_hash: &str) -> Value {
+    json!({
+        "id": "ckb",
+        "genesis_hash": genesis_hash,
+        "dao_type_hash": null,
+        "secp256k1_blake160_sighash_all_type_hash": null,
+        "secp256k1_blake160_multisig_all_type_hash": null,
+        "initial_primary_epoch_reward": "0x71afd498d000",
+        "secondary_epoch_reward": "0x37d0c8e28542",
+        "max_uncles_num": "0x2",
+        "orphan_rate_target": { "numer": "0x1", "denom": "0x28" },
+        "epoch_duration_target": "0x3840",
+        "tx_proposal_window": { "closest": "0x2", "farthest": "0xa" },
+        "proposer_reward_ratio": { "numer": "0x4", "denom": "0xa" },
+        "cellbase_maturity": "0x10000000000",
+        "median_time_block_count": "0x25",
+        "max_block_cycles": "0xd09dc300",
+        "max_block_bytes": "0x91c08",
+        "block_version": "0x0",
+        "tx_version": "0x0",
+        "type_id_code_hash": "0x00000000000000000000000000000000000000000000000000545950455f4944",
+        "max_block_proposals_limit": "0x5dc",
+        "primary_epoch_reward_halving_interval": "0x2238",
+        "permanent_difficulty_in_dummy": false
+    })
+}
@@ -0,0 +1,80 @@
+mod common;
+
+use ckb_suite_rpc::{AsyncJsonrpc, ClientConfig, Error};
+use common::{consensus, MockServer, OTHER_GENESIS_HASH};
+use serde_json::json;
+use std::time::Duration;
+
+#[tokio::test]
+async fn accept_indexer_endpoints_without_get_consensus() {
+    let server = MockServer::start_indexer(vec![("get_tip_block_number", json!("0x10"))]).await;
+    let config = ClientConfig {
+        indexer_only: true,
+        ..Default::default()
+    };
+    let jsonrpc = AsyncJsonrpc::connect_with_config(&[&server.uri], config);
+
+    assert_eq!(jsonrpc.get_tip_block_number().await.unwrap(), 16);
+}
+
+#[tokio::test]
+async fn exclude_node_endpoints_failing_get_consensus() {
+    let server = MockServer::start_indexer(vec![("get_tip_block_number", json!("0x10"))]).await;
+    let jsonrpc = AsyncJsonrpc::connect(&[&server.uri]);
+
+    let err = jsonrpc.get_tip_block_number().await.unwrap_err();
+    assert!(matches!(err, Error::NoAvailableEndpoint), "{}", err);
+    assert!(server.params_of("get_tip_block_number").is_empty());
+}
+
+#[tokio::test]
+async fn pin_the_genesis_of_the_primary_endpoint() {
+    let primary = MockServer::start(vec![("get_tip_block_number", json!("0x10"))]).await;
+    let other = MockServer::start(vec![
+        ("get_consensus", consensus(OTHER_GENESIS_HASH)),
+        ("get_tip_block_number", json!("0x20")),
+    ])
+    .await;
+    let fallback = MockServer::start(vec![("get_tip_block_number", json!("0x30"))]).await;
+    let config = ClientConfig {
+        max_retries: 0,
+        health_check_interval: Duration::from_millis(500),
+        ..Default::default()
+    };
+    let jsonrpc =
+        AsyncJsonrpc::connect_with_config(&[&primary.uri, &other.uri, &fallback.uri], config);
+
+    // The primary is down, the first fallback pins the genesis provisionally
+    primary.set_down(true);
+    assert_eq!(jsonrpc.get_tip_block_number().await.unwrap(), 32);
+
+    // The fallback of the primary's chain is excluded by the provisional genesis
+    other.set_down(true);
+    assert!(jsonrpc.get_tip_block_number().await.is_err());
+    assert!(fallback.params_of("get_tip_block_number").is_empty());
+
+    // The primary pins its genesis once it answers, excluding the fallback of the other chain
+    other.set_down(false);
+    primary.set_down(false);
+    tokio::time::sleep(Duration::from_millis(600)).await;
+    assert_eq!(jsonrpc.get_tip_block_number().await.unwrap(), 16);
+
+    // Fail over to the fallback of the primary's chain, checked again since the primary answered
+    primary.set_down(true);
+    assert_eq!(jsonrpc.get_tip_block_number().await.unwrap(), 48);
+    assert_eq!(other.params_of("get_tip_block_number").len(), 1);
+}
+
+#[tokio::test]
+async fn serve_requests_unlimited_by_a_zero_rate_limit() {
+    let server = MockServer::start(vec![("get_tip_block_number", json!("0x10"))]).await;
+    let config = ClientConfig {
+        rate_limit: Some(0),
+        ..Default::default()
+    };
+    let jsonrpc = AsyncJsonrpc::connect_with_config(&[&server.uri], config);
+
+    for _ in 0..10 {
+        assert_eq!(jsonrpc.get_tip_block_number().await.unwrap(), 16);
+    }
+}
@@ -0,0 +1,235 @@
+mod common;
+
+use ckb_suite_rpc::ckb_types::{packed::Byte32, prelude::*, H256};
+use ckb_suite_rpc::indexer::{Order, SearchKey};
+use ckb_suite_rpc::AsyncJsonrpc;
+use common::MockServer;
+use serde::Serialize;
+use serde_json::{json, Value};
+
+const BLOCK_HASH: &str = "0xa5f5c85987a15de25661e5a214f2c1449cd803f071acc7999820f25246471f40";
+const TX_HASH: &str = "0xa0ef4eb5f4ceeb08a4c8524d84c5da95dce2f608e0ca2ec8091191b0f330c6e3";
+const CODE_HASH: &str = "0x9bd7e06f3ecf4be0f2fcd2188b23f1b9fcc88e5d4b65a8637b17723bbda3cce8";
+
+fn byte32(hex: &str) -> Byte32 {
+    let h256: H256 = serde_json::from_value(json!(hex)).unwrap();
+    h256.pack()
+}
+
+fn search_key() -> Value {
+    json!({
+        "script": {
+            "code_hash": CODE_HASH,
+            "hash_type": "type",
+            "args": "0x8211f1b938a107cd53b6302cc752a6fc3965638d",
+        },
+        "script_type": "lock",
+        "filter": null,
+    })
+}
+
+/// Assert the typed response is decoded without losing any field
+fn assert_decoded<T: Serialize>(decoded: &T, result: &Value) {
+    assert_eq!(&serde_json::to_value(decoded).unwrap(), result);
+}
+
+#[tokio::test]
+async fn get_raw_tx_pool() {
+    let result = json!({ "pending": [TX_HASH], "proposed": [] });
+    let server = MockServer::start(vec![("get_raw_tx_pool", result.clone())]).await;
+    let jsonrpc = AsyncJsonrpc::connect(&[&server.uri]);
+
+    let raw_tx_pool = jsonrpc.get_raw_tx_pool(false).await.unwrap();
+    assert_decoded(&raw_tx_pool, &result);
+    assert_eq!(server.params_of("get_raw_tx_pool"), vec![json!([false])]);
+}
+
+#[tokio::test]
+async fn get_cellbase_output_capacity_details() {
+    let result = json!({
+        "total": "0x18e64efc04",
+        "primary": "0x18ce922bca",
+        "secondary": "0x17b93605",
+        "tx_fee": "0x0",
+        "proposal_reward": "0x0",
+    });
+    let server = MockServer::start(vec![(
+        "get_cellbase_output_capacity_details",
+        result.clone(),
+    )])
+    .await;
+    let jsonrpc = AsyncJsonrpc::connect(&[&server.uri]);
+
+    let reward = jsonrpc
+        .get_cellbase_output_capacity_details(byte32(BLOCK_HASH))
+        .await
+        .unwrap();
+    assert_decoded(&reward, &result);
+    assert_eq!(
+        server.params_of("get_cellbase_output_capacity_details"),
+        vec![json!([BLOCK_HASH])]
+    );
+}
+
+#[tokio::test]
+async fn get_block_economic_state() {
+    let result = json!({
+        "issuance": { "primary": "0x18ce922bca", "secondary": "0x7f02ec655" },
+        "miner_reward": {
+            "primary": "0x18ce922bca",
+            "secondary": "0x17b93605",
+            "committed": "0x0",
+            "proposal": "0x0",
+        },
+        "txs_fee": "0x0",
+        "finalized_at": BLOCK_HASH,
+    });
+    let server = MockServer::start(vec![("get_block_economic_state", result.clone())]).await;
+    let jsonrpc = AsyncJsonrpc::connect(&[&server.uri]);
+
+    let economic_state = jsonrpc
+        .get_block_economic_state(byte32(BLOCK_HASH))
+        .await
+        .unwrap();
+    assert_decoded(&economic_state, &result);
+}
+
+#[tokio::test]
+async fn get_block_economic_state_not_found() {
+    let server = MockServer::start(vec![("get_block_economic_state", Value::Null)]).await;
+    let jsonrpc = AsyncJsonrpc::connect(&[&server.uri]);
+
+    let err = jsonrpc
+        .get_block_economic_state(byte32(BLOCK_HASH))
+        .await
+        .unwrap_err();
+    assert!(err.is_not_found(), "{}", err);
+}
+
+#[tokio::test]
+async fn sync_state() {
+    let result = json!({
+        "ibd": true,
+        "best_known_block_number": "0x400",
+        "best_known_block_timestamp": "0x5cd2b117",
+        "orphan_blocks_count": "0x0",
+        "inflight_blocks_count": "0x0",
+        "fast_time": "0x3e8",
+        "normal_time": "0x4e2",
+        "low_time": "0x5dc",
+    });
+    let server = MockServer::start(vec![("sync_state", result.clone())]).await;
+    let jsonrpc = AsyncJsonrpc::connect(&[&server.uri]);
+
+    let sync_state = jsonrpc.sync_state().await.unwrap();
+    assert_decoded(&sync_state, &result);
+}
+
+#[tokio::test]
+async fn get_banned_addresses() {
+    let result = json!([{
+        "address": "192.168.0.2/32",
+        "ban_until": "0x1ac89236180",
+        "ban_reason": "",
+        "created_at": "0x16bde533338",
+    }]);
+    let server = MockServer::start(vec![("get_banned_addresses", result.clone())]).await;
+    let jsonrpc = AsyncJsonrpc::connect(&[&server.uri]);
+
+    let banned_addresses = jsonrpc.get_banned_addresses().await.unwrap();
+    assert_decoded(&banned_addresses, &result);
+}
+
+#[tokio::test]
+async fn get_transaction_proof() {
+    let result = json!({
+        "block_hash": BLOCK_HASH,
+        "witnesses_root": "0x2bb631f4a251ec39d943cc238fc1e39c7f0e99776e8a1e7be28a03c70c4f4853",
+        "proof": { "indices": ["0x0"], "lemmas": [] },
+    });
+    let server = MockServer::start(vec![("get_transaction_proof", result.clone())]).await;
+    let jsonrpc = AsyncJsonrpc::connect(&[&server.uri]);
+
+    let proof = jsonrpc
+        .get_transaction_proof(vec![byte32(TX_HASH)], None)
+        .await
+        .unwrap();
+    assert_decoded(&proof, &result);
+    assert_eq!(
+        server.params_of("get_transaction_proof"),
+        vec![json!([[TX_HASH], null])]
+    );
+}
+
+#[tokio::test]
+async fn get_cells() {
+    let result = json!({
+        "objects": [{
+            "output": {
+                "capacity": "0x2363e7f00",
+                "lock": search_key()["script"],
+                "type": null,
+            },
+            "output_data": "0x",
+            "out_point": { "tx_hash": TX_HASH, "index": "0x0" },
+            "block_number": "0x5b0",
+            "tx_index": "0x0",
+        }],
+        "last_cursor": "0x409bd7e06f3ecf4be0f2fcd2188b23f1b9fcc88e5d4b65a8637b17723bbda3cce801",
+    });
+    let server = MockServer::start(vec![("get_cells", result.clone())]).await;
+    let jsonrpc = AsyncJsonrpc::connect(&[&server.uri]);
+
+    let search_key: SearchKey = serde_json::from_value(search_key()).unwrap();
+    let cells = jsonrpc
+        .get_cells(search_key, Order::Asc, 10, None)
+        .await
+        .unwrap();
+    assert_decoded(&cells, &result);
+    assert_eq!(
+        server.params_of("get_cells"),
+        vec![json!([self::search_key(), "asc", "0xa", null])]
+    );
+}
+
+#[tokio::test]
+async fn get_transactions() {
+    let result = json!({
+        "objects": [{
+            "tx_hash": TX_HASH,
+            "block_number": "0x5b0",
+            "tx_index": "0x0",
+            "io_index": "0x0",
+            "io_type": "output",
+        }],
+        "last_cursor": "0x809bd7e06f3ecf4be0f2fcd2188b23f1b9fcc88e5d4b65a8637b17723bbda3cce801",
+    });
+    let server = MockServer::start(vec![("get_transactions", result.clone())]).await;
+    let jsonrpc = AsyncJsonrpc::connect(&[&server.uri]);
+
+    let search_key: SearchKey = serde_json::from_value(search_key()).unwrap();
+    let txs = jsonrpc
+        .get_transactions(search_key, Order::Desc, 1, None)
+        .await
+        .unwrap();
+    assert_decoded(&txs, &result);
+    assert_eq!(
+        server.params_of("get_transactions"),
+        vec![json!([self::search_key(), "desc", "0x1", null])]
+    );
+}
+
+#[tokio::test]
+async fn get_cells_capacity() {
+    let result = json!({
+        "capacity": "0x2363e7f00",
+        "block_hash": BLOCK_HASH,
+        "block_number": "0x5b0",
+    });
+    let server = MockServer::start(vec![("get_cells_capacity", result.clone())]).await;
+    let jsonrpc = AsyncJsonrpc::connect(&[&server.uri]);
+
+    let search_key: SearchKey = serde_json::from_value(search_key()).unwrap();
+    let capacity = jsonrpc.get_cells_capacity(search_key).await.unwrap();
+    assert_decoded(&capacity, &result);
+}
@@ -0,0 +1,158 @@
+//! Encode lock scripts into CKB addresses, as specified by
+//! [RFC 0021](https://github.com/nervosnetwork/rfcs/blob/master/rfcs/0021-ckb-address-format/0021-ckb-address-format.md).
+//!
+//! The locks of the default secp256k1 single-sig and multi-sig scripts are encoded in the short
+//! format; the other locks are encoded in the full format.
+
+use ckb_types::core::ScriptHashType;
+use ckb_types::packed::Script;
+use ckb_types::prelude::*;
+use ckb_types::{h256, H256};
+use std::convert::TryFrom;
+
+const SHORT_FORMAT: u8 = 0x01;
+const FULL_DATA_FORMAT: u8 = 0x02;
+const FULL_TYPE_FORMAT: u8 = 0x04;
+
+pub(crate) const SECP256K1_BLAKE160_CODE_HASH: H256 =
+    h256!("0x9bd7e06f3ecf4be0f2fcd2188b23f1b9fcc88e5d4b65a8637b17723bbda3cce8");
+pub(crate) const SECP256K1_MULTISIG_CODE_HASH: H256 =
+    h256!("0x5c5069eb0857efc65e1bca0c07df34c31663b3622fd3876c876320fc9634e2a8");
+
+const BECH32_CHARSET: &[u8; 32] = b"qpzry9x8gf2tvdw0s3jn54khce6mua7l";
+
+/// The human-readable prefix of the addresses
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum AddressPrefix {
+    Mainnet,
+    Testnet,
+}
+
+impl AddressPrefix {
+    /// Mainnet is the chain whose chain spec name is "ckb"; the others use the testnet prefix
+    pub(crate) fn from_chain_id(chain_id: &str) -> Self {
+        if chain_id == "ckb" {
+            AddressPrefix::Mainnet
+        } else {
+            AddressPrefix::Testnet
+        }
+    }
+
+    fn hrp(self) -> &'static str {
+        match self {
+            AddressPrefix::Mainnet => "ckb",
+            AddressPrefix::Testnet => "ckt",
+        }
+    }
+}
+
+/// Encode the lock into an address, in the short format if possible, otherwise in the full format.
+/// None if the hash type is unknown, e.g. of a cellbase witness lock, which is not verified.
+pub(crate) fn encode_address(prefix: AddressPrefix, lock: &Script) -> Option<String> {
+    let code_hash: H256 = lock.code_hash().unpack();
+    let hash_type = ScriptHashType::try_from(lock.hash_type()).ok()?;
+    let args = lock.args().raw_data();
+    let code_hash_index = match (&code_hash, hash_type) {
+        (hash, ScriptHashType::Type) if hash == &SECP256K1_BLAKE160_CODE_HASH => Some(0x00),
+        (hash, ScriptHashType::Type) if hash == &SECP256K1_MULTISIG_CODE_HASH => Some(0x01),
+        _ => None,
+    };
+    let payload = match code_hash_index {
+        Some(code_hash_index) if args.len() == 20 => {
+            [&[SHORT_FORMAT, code_hash_index][..], &args[..]].concat()
+        }
+        _ => {
+            let format = match hash_type {
+                ScriptHashType::Data => FULL_DATA_FORMAT,
+                ScriptHashType::Type => FULL_TYPE_FORMAT,
+            };
+            [&[format][..], code_hash.as_bytes(), &args[..]].concat()
+        }
+    };
+    Some(bech32_encode(prefix.hrp(), &payload))
+}
+
+/// Encode the lock's script components, as hex code hash, "data", "type" or "unknown", and hex
+/// args
+pub(crate) fn script_components(lock: &Script) -> (String, String, String) {
+    let code_hash: H256 = lock.code_hash().unpack();
+    let hash_type = match ScriptHashType::try_from(lock.hash_type()) {
+        Ok(ScriptHashType::Data) => "data",
+        Ok(ScriptHashType::Type) => "type",
+        Err(_) => "unknown",
+    };
+    let args = lock
+        .args()
+        .raw_data()
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect::<String>();
+    (
+        format!("{:#x}", code_hash),
+        hash_type.to_string(),
+        format!("0x{}", args),
+    )
+}
+
+// Bech32 as BIP-0173, without the 90 characters limit, which the full format exceeds
+fn bech32_encode(hrp: &str, payload: &[u8]) -> String {
+    let data = convert_bits(payload);
+    let mut values = hrp_expand(hrp);
+    values.extend_from_slice(&data);
+    values.extend_from_slice(&[0; 6]);
+    let polymod = bech32_polymod(&values) ^ 1;
+    let checksum = (0..6u32).map(|i| ((polymod >> (5 * (5 - i))) & 31) as u8);
+
+    let mut address = format!("{}1", hrp);
+    for value in data.into_iter().chain(checksum) {
+        address.push(BECH32_CHARSET[value as usize] as char);
+    }
+    address
+}
+
+fn hrp_expand(hrp: &str) -> Vec<u8> {
+    let mut values = hrp.bytes().map(|c| c >> 5).collect::<Vec<_>>();
+    values.push(0);
+    values.extend(hrp.bytes().map(|c| c & 31));
+    values
+}
+
+fn bech32_polymod(values: &[u8]) -> u32 {
+    const GENERATORS: [u32; 5] = [
+        0x3b6a_57b2,
+        0x2650_8e6d,
+        0x1ea1_19fa,
+        0x3d42_33dd,
+        0x2a14_62b3,
+    ];
+    let mut checksum = 1u32;
+    for value in values {
+        let top = checksum >> 25;
+        checksum = ((checksum & 0x01ff_ffff) << 5) ^ u32::from(*value);
+        for (i, generator) in GENERATORS.iter().enumerate() {
+            if (top >> i) & 1 == 1 {
+                checksum ^= generator;
+            }
+        }
+    }
+    checksum
+}
+
+// Regroup the 8-bit bytes into 5-bit values, padding the last one with zeros
+fn convert_bits(payload: &[u8]) -> Vec<u8> {
+    let mut values = Vec::with_capacity((payload.len() * 8 + 4) / 5);
+    let (mut accumulator, mut bits) = (0u32, 0u32);
+    for byte in payload {
+        // Only the bits not regrouped yet are kept
+        accumulator = ((accumulator << 8) | u32::from(*byte)) & 0xfff;
+        bits += 8;
+        while bits >= 5 {
+            bits -= 5;
+            values.push(((accumulator >> bits) & 31) as u8);
+        }
+    }
+    if bits > 0 {
+        values.push(((accumulator << (5 - bits)) & 31) as u8);
+    }
+    values
+}
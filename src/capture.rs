@@ -0,0 +1,217 @@
+//! Capture the subscription notifications and RPC exchanges into a gzip-compressed file of JSON
+//! lines, and replay them into the topics later, so that a field issue can be reproduced with the
+//! same inputs.
+//!
+//! Replayed notifications are delivered with their original intervals divided by the replay speed.
+//! Replayed RPC calls are answered immediately, with the responses recorded for the same method
+//! and params in the recorded order; the last one is repeated once they run out.
+
+use crate::config::Config;
+use crate::subscribe::Topic;
+use ckb_suite_rpc::{Recorder, Responder};
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::{HashMap, VecDeque};
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, Write};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+/// Where the topics' inputs come from
+#[derive(Clone)]
+pub(crate) enum CaptureMode {
+    /// From the node
+    Live,
+    /// From the node, and captured into a file
+    Capture(Arc<CaptureWriter>),
+    /// From a captured file
+    Replay(Arc<Replay>),
+}
+
+impl CaptureMode {
+    pub(crate) fn from_config(config: &Config) -> Self {
+        match (&config.capture, &config.replay) {
+            (Some(_), Some(_)) => panic!("capture and replay cannot be configured together"),
+            (Some(path), None) => {
+                let writer = CaptureWriter::create(path).unwrap_or_else(|err| {
+                    panic!("failed to create capture \"{}\", error: {}", path, err)
+                });
+                log::info!("capture into \"{}\"", path);
+                CaptureMode::Capture(Arc::new(writer))
+            }
+            (None, Some(replay)) => {
+                let reader = Replay::open(&replay.path, replay.speed).unwrap_or_else(|err| {
+                    panic!("failed to open capture \"{}\", error: {}", replay.path, err)
+                });
+                log::info!("replay \"{}\" at speed {}", replay.path, replay.speed);
+                CaptureMode::Replay(Arc::new(reader))
+            }
+            (None, None) => CaptureMode::Live,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+struct Record {
+    /// Unix timestamp in milliseconds
+    time: u64,
+    #[serde(flatten)]
+    event: Event,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+enum Event {
+    Notification {
+        topic: Topic,
+        message: String,
+    },
+    Rpc {
+        method: String,
+        params: Value,
+        response: Value,
+    },
+}
+
+pub(crate) struct CaptureWriter {
+    // None once finished
+    encoder: Mutex<Option<GzEncoder<File>>>,
+}
+
+impl CaptureWriter {
+    pub(crate) fn create(path: &str) -> io::Result<Self> {
+        let file = File::create(path)?;
+        Ok(Self {
+            encoder: Mutex::new(Some(GzEncoder::new(file, Compression::default()))),
+        })
+    }
+
+    pub(crate) fn record_notification(&self, topic: Topic, message: &str) {
+        self.write(Event::Notification {
+            topic,
+            message: message.to_string(),
+        })
+    }
+
+    /// Write the gzip trailer, ending the capture. The records after it are dropped.
+    pub(crate) fn finish(&self) {
+        if let Some(encoder) = self.encoder.lock().unwrap().take() {
+            if let Err(err) = encoder.finish() {
+                log::error!("failed to finish capture, error: {}", err);
+            }
+        }
+    }
+
+    // Every record is flushed, so that the capture stays decodable when the analyzer is killed
+    fn write(&self, event: Event) {
+        let record = Record {
+            time: chrono::Utc::now().timestamp_millis() as u64,
+            event,
+        };
+        let mut line = serde_json::to_vec(&record).expect("serialize capture record");
+        line.push(b'\n');
+        let mut encoder = self.encoder.lock().unwrap();
+        let encoder = match encoder.as_mut() {
+            Some(encoder) => encoder,
+            None => return,
+        };
+        if let Err(err) = encoder.write_all(&line).and_then(|_| encoder.flush()) {
+            log::error!("failed to write capture, error: {}", err);
+        }
+    }
+}
+
+impl Recorder for CaptureWriter {
+    fn record(&self, method: &str, params: &Value, response: &Value) {
+        self.write(Event::Rpc {
+            method: method.to_string(),
+            params: params.clone(),
+            response: response.clone(),
+        })
+    }
+}
+
+pub(crate) struct Replay {
+    notifications: Vec<(u64, Topic, String)>,
+    // Keyed by method and the JSON text of params
+    responses: Mutex<HashMap<(String, String), VecDeque<Value>>>,
+    first_time: u64,
+    speed: f64,
+    started_at: Instant,
+}
+
+impl Replay {
+    /// Load the capture. `speed` scales the notification intervals, e.g. 10.0 replays 10 times
+    /// faster; non-positive means as fast as possible.
+    pub(crate) fn open(path: &str, speed: f64) -> io::Result<Self> {
+        let file = File::open(path)?;
+        let mut notifications = Vec::new();
+        let mut responses: HashMap<(String, String), VecDeque<Value>> = HashMap::new();
+        let mut first_time = None;
+        for line in BufReader::new(GzDecoder::new(file)).lines() {
+            // The tail is truncated if the capturing analyzer was killed
+            let line = match line {
+                Ok(line) => line,
+                Err(err) => {
+                    log::warn!("stop reading capture \"{}\", error: {}", path, err);
+                    break;
+                }
+            };
+            let record: Record = serde_json::from_str(&line)
+                .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))?;
+            first_time.get_or_insert(record.time);
+            match record.event {
+                Event::Notification { topic, message } => {
+                    notifications.push((record.time, topic, message))
+                }
+                Event::Rpc {
+                    method,
+                    params,
+                    response,
+                } => responses
+                    .entry((method, params.to_string()))
+                    .or_default()
+                    .push_back(response),
+            }
+        }
+        Ok(Self {
+            notifications,
+            responses: Mutex::new(responses),
+            first_time: first_time.unwrap_or_default(),
+            speed,
+            started_at: Instant::now(),
+        })
+    }
+
+    /// The captured notifications of the topic, with the instants to deliver them
+    pub(crate) fn notifications(&self, topic: Topic) -> Vec<(Instant, String)> {
+        self.notifications
+            .iter()
+            .filter(|(_, captured_topic, _)| *captured_topic == topic)
+            .map(|(time, _, message)| {
+                let elapsed = Duration::from_millis(time.saturating_sub(self.first_time));
+                let deliver_at = if self.speed > 0.0 {
+                    self.started_at + elapsed.div_f64(self.speed)
+                } else {
+                    self.started_at
+                };
+                (deliver_at, message.clone())
+            })
+            .collect()
+    }
+}
+
+impl Responder for Replay {
+    fn respond(&self, method: &str, params: &Value) -> Option<Value> {
+        let mut responses = self.responses.lock().unwrap();
+        let queue = responses.get_mut(&(method.to_string(), params.to_string()))?;
+        if queue.len() > 1 {
+            queue.pop_front()
+        } else {
+            queue.front().cloned()
+        }
+    }
+}
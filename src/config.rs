@@ -1,5 +1,7 @@
+use ckb_suite_rpc::ClientConfig;
 use serde::{Deserialize, Serialize};
 use std::str::FromStr;
+use std::time::Duration;
 use tentacle_multiaddr::Multiaddr;
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
@@ -10,6 +12,97 @@ pub struct Config {
     pub topics: Vec<Topic>,
     pub postgres: String,
     pub node: NodeConfig,
+    /// How many blocks below the tip CanonicalChainState stays; 0 follows the tip
+    #[serde(default = "default_canonical_chain_lag")]
+    pub canonical_chain_lag: u64,
+    /// How many block batches CanonicalChainState fetches ahead of the analysis
+    #[serde(default = "default_backfill_concurrency")]
+    pub backfill_concurrency: usize,
+    /// The sizes, in blocks, of the sliding windows over which the hash rate is estimated
+    #[serde(default = "default_hash_rate_windows")]
+    pub hash_rate_windows: Vec<u64>,
+    /// The windows over which the blocks and uncles are aggregated per miner
+    #[serde(default = "default_miner_share_windows")]
+    pub miner_share_windows: Vec<MinerShareWindow>,
+    /// The share of a window, above which a single miner is alerted
+    #[serde(default = "default_miner_share_alert_threshold")]
+    pub miner_share_alert_threshold: f64,
+    /// How many recent transactions' outputs are cached to resolve the inputs
+    #[serde(default = "default_recent_transactions_cache_size")]
+    pub recent_transactions_cache_size: usize,
+    /// Path of the label file which maps lock args and cellbase messages to pool names
+    #[serde(default)]
+    pub pool_labels: Option<String>,
+    /// The well-known scripts named in the script usages, besides the built-in genesis scripts
+    #[serde(default)]
+    pub scripts: Vec<ScriptConfig>,
+    /// How many blocks apart the chain state is reconciled with the indexer
+    #[serde(default = "default_chain_state_reconciliation_interval")]
+    pub chain_state_reconciliation_interval: u64,
+    /// Path of the file to capture the subscription notifications and RPC exchanges into
+    #[serde(default)]
+    pub capture: Option<String>,
+    /// Replay a capture instead of connecting to the node
+    #[serde(default)]
+    pub replay: Option<ReplayConfig>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ScriptConfig {
+    pub name: String,
+    pub code_hash: String,
+    /// "data" or "type"
+    pub hash_type: String,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ReplayConfig {
+    pub path: String,
+    /// Replay speed relative to the captured one, e.g. 10.0 replays 10 times faster; 0 replays
+    /// as fast as possible
+    #[serde(default = "default_replay_speed")]
+    pub speed: f64,
+}
+
+// The blocks up to `tip - 11` are analyzed
+fn default_canonical_chain_lag() -> u64 {
+    11
+}
+
+fn default_backfill_concurrency() -> usize {
+    4
+}
+
+fn default_hash_rate_windows() -> Vec<u64> {
+    vec![100, 1000]
+}
+
+fn default_miner_share_windows() -> Vec<MinerShareWindow> {
+    vec![MinerShareWindow::Day, MinerShareWindow::Epoch]
+}
+
+fn default_miner_share_alert_threshold() -> f64 {
+    0.5
+}
+
+fn default_recent_transactions_cache_size() -> usize {
+    100_000
+}
+
+fn default_chain_state_reconciliation_interval() -> u64 {
+    1000
+}
+
+fn default_replay_speed() -> f64 {
+    1.0
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum MinerShareWindow {
+    /// A UTC day
+    Day,
+    Epoch,
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone, Copy)]
@@ -22,6 +115,7 @@ pub enum Topic {
     SubscribeNewTipHeader,
     SubscribeNewTransaction,
     SubscribeProposedTransaction,
+    RpcCacheStats,
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
@@ -31,6 +125,73 @@ pub struct NodeConfig {
     pub subscription_port: u16,
     pub data_dir: String,
     pub bootnodes: Vec<Multiaddr>,
+
+    /// Timeout of RPC requests, in milliseconds
+    #[serde(default = "default_rpc_timeout_ms")]
+    pub rpc_timeout_ms: u64,
+    /// How many times an idempotent RPC request is retried after transport failures or timeouts
+    #[serde(default = "default_rpc_max_retries")]
+    pub rpc_max_retries: u32,
+    /// Backoff before the first retry, doubled on every following retry, in milliseconds
+    #[serde(default = "default_rpc_retry_backoff_ms")]
+    pub rpc_retry_backoff_ms: u64,
+    /// Limit of RPC requests per second; unlimited if absent
+    #[serde(default)]
+    pub rpc_rate_limit: Option<u32>,
+    /// How many RPC requests can be sent at once after idle
+    #[serde(default = "default_rpc_rate_limit_burst")]
+    pub rpc_rate_limit_burst: u32,
+    /// RPC urls of fallback nodes, tried in order when the target node is unavailable
+    #[serde(default)]
+    pub fallback_rpc_urls: Vec<String>,
+    /// How long an unavailable node is skipped before being probed again, in milliseconds
+    #[serde(default = "default_rpc_health_check_interval_ms")]
+    pub rpc_health_check_interval_ms: u64,
+    /// RPC url of the ckb-indexer which the chain state is reconciled with; not reconciled if
+    /// absent
+    #[serde(default)]
+    pub indexer_rpc_url: Option<String>,
+    /// Capacity of the RPC client's blocks cache
+    #[serde(default = "default_rpc_blocks_cache_size")]
+    pub rpc_blocks_cache_size: usize,
+    /// Capacity of the RPC client's headers cache
+    #[serde(default = "default_rpc_headers_cache_size")]
+    pub rpc_headers_cache_size: usize,
+    /// Capacity of the RPC client's canonical headers cache, keyed by block number
+    #[serde(default = "default_rpc_headers_by_number_cache_size")]
+    pub rpc_headers_by_number_cache_size: usize,
+}
+
+fn default_rpc_timeout_ms() -> u64 {
+    ClientConfig::default().timeout.as_millis() as u64
+}
+
+fn default_rpc_max_retries() -> u32 {
+    ClientConfig::default().max_retries
+}
+
+fn default_rpc_retry_backoff_ms() -> u64 {
+    ClientConfig::default().retry_backoff.as_millis() as u64
+}
+
+fn default_rpc_rate_limit_burst() -> u32 {
+    ClientConfig::default().rate_limit_burst
+}
+
+fn default_rpc_health_check_interval_ms() -> u64 {
+    ClientConfig::default().health_check_interval.as_millis() as u64
+}
+
+fn default_rpc_blocks_cache_size() -> usize {
+    ClientConfig::default().blocks_cache_size
+}
+
+fn default_rpc_headers_cache_size() -> usize {
+    ClientConfig::default().headers_cache_size
+}
+
+fn default_rpc_headers_by_number_cache_size() -> usize {
+    ClientConfig::default().headers_by_number_cache_size
 }
 
 impl Config {
@@ -46,6 +207,28 @@ impl Config {
         format!("http://{}:{}", self.node.host, self.node.rpc_port)
     }
 
+    /// The target node's RPC url followed by the fallback ones
+    pub fn rpc_urls(&self) -> Vec<String> {
+        let mut urls = vec![self.rpc_url()];
+        urls.extend(self.node.fallback_rpc_urls.iter().cloned());
+        urls
+    }
+
+    pub fn rpc_client_config(&self) -> ClientConfig {
+        ClientConfig {
+            timeout: Duration::from_millis(self.node.rpc_timeout_ms),
+            max_retries: self.node.rpc_max_retries,
+            retry_backoff: Duration::from_millis(self.node.rpc_retry_backoff_ms),
+            rate_limit: self.node.rpc_rate_limit,
+            rate_limit_burst: self.node.rpc_rate_limit_burst,
+            health_check_interval: Duration::from_millis(self.node.rpc_health_check_interval_ms),
+            blocks_cache_size: self.node.rpc_blocks_cache_size,
+            headers_cache_size: self.node.rpc_headers_cache_size,
+            headers_by_number_cache_size: self.node.rpc_headers_by_number_cache_size,
+            indexer_only: false,
+        }
+    }
+
     pub fn subscription_url(&self) -> String {
         format!("{}:{}", self.node.host, self.node.subscription_port)
     }
@@ -0,0 +1,86 @@
+//! Decode the NervosDAO cells and the header `dao` field, as specified by
+//! [RFC 0023](https://github.com/nervosnetwork/rfcs/blob/master/rfcs/0023-dao-deposit-withdraw/0023-dao-deposit-withdraw.md).
+//!
+//! A deposit cell has 8 zero bytes as data; phase 1 turns it into a withdrawing cell, whose data
+//! is the deposit block number, and phase 2 consumes the withdrawing cell.
+
+use ckb_types::bytes::Bytes;
+use ckb_types::core::{Capacity, ScriptHashType};
+use ckb_types::packed::{Byte32, CellOutput};
+use ckb_types::prelude::*;
+use ckb_types::{h256, H256};
+use std::convert::{TryFrom, TryInto};
+
+pub(crate) const NERVOS_DAO_CODE_HASH: H256 =
+    h256!("0x82d76d1b75fe2fd9a27dfbaa65a039221a380d76c926f378d3f81cf3e7e13f2e");
+
+/// The decoded header `dao` field
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct DaoData {
+    /// C, the total issued capacity
+    pub(crate) total_issuance: u64,
+    /// AR, the accumulated rate, from which the deposits' compensation is computed
+    pub(crate) accumulated_rate: u64,
+    /// S, the total unissued secondary issuance
+    pub(crate) unissued_secondary_issuance: u64,
+    /// U, the total occupied capacity
+    pub(crate) occupied_capacity: u64,
+}
+
+/// Decode the header `dao` field, the 4 little-endian u64 C, AR, S and U
+pub(crate) fn extract_dao_data(dao: &Byte32) -> DaoData {
+    let data = dao.raw_data();
+    let field = |i: usize| u64::from_le_bytes(data[i * 8..(i + 1) * 8].try_into().unwrap());
+    DaoData {
+        total_issuance: field(0),
+        accumulated_rate: field(1),
+        unissued_secondary_issuance: field(2),
+        occupied_capacity: field(3),
+    }
+}
+
+pub(crate) fn is_dao_cell(output: &CellOutput) -> bool {
+    output.type_().to_opt().map_or(false, |type_| {
+        let code_hash: H256 = type_.code_hash().unpack();
+        code_hash == NERVOS_DAO_CODE_HASH
+            && matches!(
+                ScriptHashType::try_from(type_.hash_type()),
+                Ok(ScriptHashType::Type)
+            )
+    })
+}
+
+pub(crate) fn is_deposit_data(data: &Bytes) -> bool {
+    data.len() == 8 && data.iter().all(|byte| *byte == 0)
+}
+
+/// Return the deposit block number stored in the withdrawing cell data
+pub(crate) fn deposit_number(data: &Bytes) -> Option<u64> {
+    if is_deposit_data(data) {
+        return None;
+    }
+    let number = data[..].try_into().ok()?;
+    Some(u64::from_le_bytes(number))
+}
+
+/// The capacity of the DAO cell withdrawn in phase 2: the capacity not occupied grows as the
+/// accumulated rate from the deposit block to the withdrawing block
+pub(crate) fn maximum_withdraw(
+    output: &CellOutput,
+    data: &Bytes,
+    deposit_accumulated_rate: u64,
+    withdrawing_accumulated_rate: u64,
+) -> u64 {
+    let capacity: u64 = output.capacity().unpack();
+    if deposit_accumulated_rate == 0 {
+        return capacity;
+    }
+    let occupied = Capacity::bytes(data.len())
+        .and_then(|data_capacity| output.occupied_capacity(data_capacity))
+        .map(|occupied| occupied.as_u64())
+        .unwrap_or(capacity);
+    let counted = u128::from(capacity.saturating_sub(occupied));
+    let withdraw =
+        counted * u128::from(withdrawing_accumulated_rate) / u128::from(deposit_accumulated_rate);
+    withdraw as u64 + occupied
+}
@@ -35,7 +35,14 @@
 //!   - [x] block time interval
 //!   - [x] epoch uncles count and uncles rate
 //!   - [x] epoch duration and length
-//!   - [ ] epoch adjustment
+//!   - [x] epoch adjustment
+//!   - [x] network hash rate
+//!   - [x] committed transactions, their fees and fee rates
+//!   - [x] cells created and consumed per script
+//!   - [x] NervosDAO deposits, withdrawals and the header dao field
+//!   - [x] block rewards and the issuance per epoch
+//!   - [x] live cells, capacity and occupied capacity, reconciled with the indexer
+//!   - [x] proposals never committed, including the uncles' ones
 //!
 //! * [ ] network distribution
 //!   * [ ] tip distribution accross the network
@@ -52,6 +59,9 @@
 //!
 //! * [ ] miner
 //!   - [ ] the miner node of a specified block (ip or lock args)
+//!   - [x] miner shares and mining concentration
+//!   - [x] the mining pool of a block, labelled by lock args or cellbase message
+//!   - [x] the miner address of a block
 //!
 //! * [ ] transaction transition (mainly focus the transaction traffic and state transition latency)
 //!   - [x] pending
@@ -82,7 +92,7 @@
 //!
 //! * [ ] persist recent transactions (debug suite)
 //!
-//! * [ ] reproduce context
+//! * [x] reproduce context
 //!
 //! # Monitoring alerts
 //!
@@ -94,6 +104,7 @@
 //! * [ ] miner issues
 //!   - [ ] chain does not grow up for too long
 //!   - [ ] node receives too many uncle blocks
+//!   - [x] a single miner mines too large a share
 //!
 //! * [ ] chain growth issues
 //!   - [ ] block time interval is shorter/longer then threshold
@@ -121,22 +132,36 @@
 //!
 //!   Pushing metrics actively via HTTP to InfluxDB is much useful!
 
+use crate::capture::CaptureMode;
 use crate::config::{Config, Topic};
+use crate::subscribe::Subscription;
 use crate::topic::{
-    CanonicalChainState, NetworkPropagation, NetworkTopology, Reorganization,
-    SubscribeNewTipHeader, SubscribeNewTransaction, SubscribeProposedTransaction, TxTransition,
+    AnalyzedTable, Backfill, CanonicalChainState, ChainOccupancy, NetworkPropagation,
+    NetworkTopology, Reorganization, RpcCacheStats, SubscribeNewTipHeader, SubscribeNewTransaction,
+    SubscribeProposedTransaction, TxTransition,
 };
-use crate::util::get_last_updated_block_number;
-use ckb_suite_rpc::Jsonrpc;
+use crate::util::{get_last_chain_state, get_last_updated_block_number};
+use ckb_suite_rpc::{AsyncJsonrpc, Jsonrpc};
 use crossbeam::channel::bounded;
 use jsonrpc_server_utils::tokio as tokio01;
 use std::env::var;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
 use std::time::{Duration, Instant};
 
+mod address;
+mod capture;
 mod config;
+mod dao;
 mod dashboard;
+#[cfg(test)]
+mod mock_node;
+mod pool;
+mod script_registry;
 mod subscribe;
 mod table;
+#[cfg(test)]
+mod tests;
 mod topic;
 mod util;
 
@@ -157,135 +182,211 @@ async fn run(async_handle02: ckb_async_runtime::Handle) {
     let (query_sender, query_receiver) = bounded::<String>(5000);
     let pg = create_pg(config.postgres()).await;
 
-    for topic in config.topics.iter() {
-        let topic = *topic;
-        log::info!("Start topic {:?}", topic);
-        match topic {
-            Topic::CanonicalChainState => {
-                let jsonrpc = Jsonrpc::connect(&config.rpc_url());
-                let last_number = get_last_updated_block_number(&pg, &config.network).await;
-                let mut handler = CanonicalChainState::new(
-                    config.clone(),
-                    jsonrpc,
-                    query_sender.clone(),
-                    last_number,
-                );
-                tokio::spawn(async move {
-                    handler.run().await;
-                    log::info!("End topic {:?}", topic);
-                });
+    let capture_mode = CaptureMode::from_config(&config);
+    // Set on Ctrl-C when capturing, to write the queries sent and end the capture, so that its
+    // gzip stream is complete
+    let interrupted = Arc::new(AtomicBool::new(false));
+    if let CaptureMode::Capture(_) = &capture_mode {
+        let interrupted = Arc::clone(&interrupted);
+        tokio::spawn(async move {
+            if tokio::signal::ctrl_c().await.is_ok() {
+                interrupted.store(true, Ordering::SeqCst);
             }
-            Topic::Reorganization => {
-                let jsonrpc = Jsonrpc::connect(&config.rpc_url());
-                let (handler, subscription) =
-                    Reorganization::new(config.clone(), jsonrpc, query_sender.clone());
+        });
+    }
+    // Shared by the async topics, so that they share the caches, and the cache invalidation on
+    // reorganization applies to all of them
+    let jsonrpc = {
+        let jsonrpc =
+            AsyncJsonrpc::connect_with_config(&config.rpc_urls(), config.rpc_client_config());
+        match &capture_mode {
+            CaptureMode::Live => jsonrpc,
+            CaptureMode::Capture(writer) => jsonrpc.with_recorder(writer.clone()),
+            CaptureMode::Replay(replay) => jsonrpc.with_responder(replay.clone()),
+        }
+    };
+    if let Some(backfill) = init_backfill() {
+        // Exit once the range is analyzed and the queries are written
+        log::info!("Start backfill {:?}", backfill);
+        let last_chain_state = get_last_chain_state(&pg, &config.network, backfill.from)
+            .await
+            .map(chain_occupancy);
+        let mut handler = CanonicalChainState::new(config.clone(), jsonrpc, query_sender, 0)
+            .with_backfill(backfill);
+        if let Some((number, occupancy)) = last_chain_state {
+            handler = handler.with_chain_state(number, occupancy);
+        }
+        tokio::spawn(async move {
+            handler.run().await;
+            log::info!("End backfill");
+        });
+    } else {
+        for topic in config.topics.iter() {
+            let topic = *topic;
+            log::info!("Start topic {:?}", topic);
+            match topic {
+                Topic::CanonicalChainState => {
+                    let jsonrpc = jsonrpc.clone();
+                    let last_number = get_last_updated_block_number(&pg, &config.network).await;
+                    let mut handler = CanonicalChainState::new(
+                        config.clone(),
+                        jsonrpc,
+                        query_sender.clone(),
+                        last_number,
+                    );
+                    if let Some((number, occupancy)) =
+                        get_last_chain_state(&pg, &config.network, u64::MAX)
+                            .await
+                            .map(chain_occupancy)
+                    {
+                        handler = handler.with_chain_state(number, occupancy);
+                    }
+                    tokio::spawn(async move {
+                        handler.run().await;
+                        log::info!("End topic {:?}", topic);
+                    });
+                }
+                Topic::Reorganization => {
+                    let jsonrpc = jsonrpc.clone();
+                    let (handler, subscription) =
+                        Reorganization::new(config.clone(), jsonrpc, query_sender.clone());
 
-                ::std::thread::spawn(move || {
-                    let mut runtime01 = tokio01::runtime::Builder::new().build().unwrap();
-                    runtime01.block_on(subscription.run()).unwrap();
-                    log::info!("Runtime for subscription on topic {:?} exit", topic);
-                });
+                    spawn_subscription(topic, subscription, capture_mode.clone());
 
-                tokio::spawn(async move {
-                    handler.run().await;
-                    log::info!("End topic {:?}", topic);
-                });
-            }
-            Topic::TxTransition => {
-                let jsonrpc = Jsonrpc::connect(&config.rpc_url());
-                let (handler, subscription) =
-                    TxTransition::new(config.clone(), jsonrpc, query_sender.clone());
+                    tokio::spawn(async move {
+                        handler.run().await;
+                        log::info!("End topic {:?}", topic);
+                    });
+                }
+                Topic::TxTransition => {
+                    let jsonrpc = jsonrpc.clone();
+                    let (handler, subscription) =
+                        TxTransition::new(config.clone(), jsonrpc, query_sender.clone());
 
-                ::std::thread::spawn(move || {
-                    let mut runtime01 = tokio01::runtime::Builder::new().build().unwrap();
-                    runtime01.block_on(subscription.run()).unwrap();
-                    log::info!("Runtime for subscription on topic {:?} exit", topic);
-                });
+                    spawn_subscription(topic, subscription, capture_mode.clone());
 
-                tokio::spawn(async move {
-                    handler.run().await;
-                    log::info!("End topic {:?}", topic);
-                });
-            }
-            Topic::NetworkPropagation => {
-                let jsonrpc = Jsonrpc::connect(&config.rpc_url());
-                let mut handler = NetworkPropagation::new(
-                    config.clone(),
-                    jsonrpc,
-                    query_sender.clone(),
-                    async_handle02.clone(),
-                );
-                ::std::thread::spawn(move || handler.run());
-            }
-            Topic::NetworkTopology => {
-                // TODO NetworkTopology
-                let handler = NetworkTopology::new(vec![]);
-                tokio::spawn(async move {
-                    handler.run().await;
-                    log::info!("End topic {:?}", topic);
-                });
-            }
-            Topic::SubscribeNewTipHeader => {
-                let (handler, subscription) =
-                    SubscribeNewTipHeader::new(config.clone(), query_sender.clone());
+                    tokio::spawn(async move {
+                        handler.run().await;
+                        log::info!("End topic {:?}", topic);
+                    });
+                }
+                Topic::NetworkPropagation => {
+                    let jsonrpc = Jsonrpc::connect_with_config(
+                        &config.rpc_urls(),
+                        config.rpc_client_config(),
+                    );
+                    let mut handler = NetworkPropagation::new(
+                        config.clone(),
+                        jsonrpc,
+                        query_sender.clone(),
+                        async_handle02.clone(),
+                    );
+                    ::std::thread::spawn(move || handler.run());
+                }
+                Topic::NetworkTopology => {
+                    // TODO NetworkTopology
+                    let handler = NetworkTopology::new(vec![]);
+                    tokio::spawn(async move {
+                        handler.run().await;
+                        log::info!("End topic {:?}", topic);
+                    });
+                }
+                Topic::SubscribeNewTipHeader => {
+                    let (handler, subscription) =
+                        SubscribeNewTipHeader::new(config.clone(), query_sender.clone());
 
-                ::std::thread::spawn(move || {
-                    let mut runtime01 = tokio01::runtime::Builder::new().build().unwrap();
-                    runtime01.block_on(subscription.run()).unwrap();
-                    log::info!("Runtime for subscription on topic {:?} exit", topic);
-                });
+                    spawn_subscription(topic, subscription, capture_mode.clone());
 
-                tokio::spawn(async move {
-                    handler.run().await;
-                    log::info!("End topic {:?}", topic);
-                });
-            }
-            Topic::SubscribeNewTransaction => {
-                let (handler, subscription) =
-                    SubscribeNewTransaction::new(config.clone(), query_sender.clone());
+                    tokio::spawn(async move {
+                        handler.run().await;
+                        log::info!("End topic {:?}", topic);
+                    });
+                }
+                Topic::SubscribeNewTransaction => {
+                    let (handler, subscription) =
+                        SubscribeNewTransaction::new(config.clone(), query_sender.clone());
 
-                ::std::thread::spawn(move || {
-                    let mut runtime01 = tokio01::runtime::Builder::new().build().unwrap();
-                    runtime01.block_on(subscription.run()).unwrap();
-                    log::info!("Runtime for subscription on topic {:?} exit", topic);
-                });
+                    spawn_subscription(topic, subscription, capture_mode.clone());
 
-                tokio::spawn(async move {
-                    handler.run().await;
-                    log::info!("End topic {:?}", topic);
-                });
-            }
-            Topic::SubscribeProposedTransaction => {
-                let (handler, subscription) =
-                    SubscribeProposedTransaction::new(config.clone(), query_sender.clone());
+                    tokio::spawn(async move {
+                        handler.run().await;
+                        log::info!("End topic {:?}", topic);
+                    });
+                }
+                Topic::SubscribeProposedTransaction => {
+                    let (handler, subscription) =
+                        SubscribeProposedTransaction::new(config.clone(), query_sender.clone());
 
-                ::std::thread::spawn(move || {
-                    let mut runtime01 = tokio01::runtime::Builder::new().build().unwrap();
-                    runtime01.block_on(subscription.run()).unwrap();
-                    log::info!("Runtime for subscription on topic {:?} exit", topic);
-                });
+                    spawn_subscription(topic, subscription, capture_mode.clone());
 
-                tokio::spawn(async move {
-                    handler.run().await;
-                    log::info!("End topic {:?}", topic);
-                });
+                    tokio::spawn(async move {
+                        handler.run().await;
+                        log::info!("End topic {:?}", topic);
+                    });
+                }
+                Topic::RpcCacheStats => {
+                    let handler =
+                        RpcCacheStats::new(config.clone(), jsonrpc.clone(), query_sender.clone());
+                    tokio::spawn(async move {
+                        handler.run().await;
+                        log::info!("End topic {:?}", topic);
+                    });
+                }
             }
         }
     }
 
     let mut batch: Vec<String> = Vec::new();
     let mut last_batch_instant: Instant = Instant::now();
-    loop {
-        handle_message(&pg, &query_receiver, &mut batch, &mut last_batch_instant).await;
+    while !interrupted.load(Ordering::SeqCst)
+        && handle_message(&pg, &query_receiver, &mut batch, &mut last_batch_instant).await
+    {}
+    if interrupted.load(Ordering::SeqCst) {
+        // Write the pending batch and the queries already in the channel
+        batch.extend(query_receiver.try_iter());
+        if !batch.is_empty() {
+            let batch_query: String = batch.join(";");
+            pg.batch_execute(&batch_query).await.unwrap_or_else(|err| {
+                panic!("pg.batch_execute(\"{}\"), error: {}", batch_query, err)
+            });
+        }
     }
+
+    // The queries end once the backfill is analyzed, or are interrupted
+    if let CaptureMode::Capture(writer) = &capture_mode {
+        writer.finish();
+    }
+    log::info!("exit ckb-analyzer");
+    ::std::process::exit(0);
 }
 
+// Subscriptions run on tokio 0.1, see `Subscription::run`
+fn spawn_subscription(topic: Topic, subscription: Subscription, capture_mode: CaptureMode) {
+    ::std::thread::spawn(move || {
+        match capture_mode {
+            CaptureMode::Live => {
+                let mut runtime01 = tokio01::runtime::Builder::new().build().unwrap();
+                runtime01.block_on(subscription.run()).unwrap();
+            }
+            CaptureMode::Capture(writer) => {
+                let mut runtime01 = tokio01::runtime::Builder::new().build().unwrap();
+                runtime01
+                    .block_on(subscription.with_capture(writer).run())
+                    .unwrap();
+            }
+            CaptureMode::Replay(replay) => subscription.replay(replay),
+        }
+        log::info!("Runtime for subscription on topic {:?} exit", topic);
+    });
+}
+
+// Return false once every query is written and no more will come
 async fn handle_message(
     pg: &tokio_postgres::Client,
     query_receiver: &crossbeam::channel::Receiver<String>,
     batch: &mut Vec<String>,
     last_batch_instant: &mut Instant,
-) {
+) -> bool {
     let max_batch_size: usize = 100;
     let max_batch_timeout = Duration::from_secs(60);
     match query_receiver.try_recv() {
@@ -300,6 +401,7 @@ async fn handle_message(
                 *last_batch_instant = Instant::now();
                 *batch = Vec::new();
             }
+            true
         }
         Err(crossbeam::channel::TryRecvError::Empty) => {
             if !batch.is_empty() {
@@ -310,10 +412,16 @@ async fn handle_message(
                 *last_batch_instant = Instant::now();
                 *batch = Vec::new();
             }
+            true
         }
         Err(crossbeam::channel::TryRecvError::Disconnected) => {
-            log::info!("exit ckb-analyzer");
-            ::std::process::exit(0);
+            if !batch.is_empty() {
+                let batch_query: String = batch.join(";");
+                pg.batch_execute(&batch_query).await.unwrap_or_else(|err| {
+                    panic!("pg.batch_execute(\"{}\"), error: {}", batch_query, err)
+                });
+            }
+            false
         }
     }
 }
@@ -322,6 +430,66 @@ fn init_logger() {
     simple_logger::SimpleLogger::from_env().init().unwrap();
 }
 
+const BACKFILL_USAGE: &str =
+    "ckb-analyzer backfill --from <number> --to <number> [--tables <table>,...]";
+
+fn init_backfill() -> Option<Backfill> {
+    let args = ::std::env::args().skip(1).collect::<Vec<_>>();
+    parse_backfill(&args).unwrap_or_else(|err| {
+        eprintln!("error: {}\nusage: {}", err, BACKFILL_USAGE);
+        ::std::process::exit(2);
+    })
+}
+
+// None unless the command is `backfill`
+fn parse_backfill(args: &[String]) -> Result<Option<Backfill>, String> {
+    if args.first().map(String::as_str) != Some("backfill") {
+        return Ok(None);
+    }
+
+    let (mut from, mut to, mut tables) = (None, None, AnalyzedTable::all());
+    let mut args = args[1..].iter();
+    while let Some(arg) = args.next() {
+        let value = args
+            .next()
+            .ok_or_else(|| format!("missing value of backfill option {}", arg))?;
+        let parse_number = |value: &str| {
+            value
+                .parse::<u64>()
+                .map_err(|err| format!("invalid {} {}: {}", arg, value, err))
+        };
+        match arg.as_str() {
+            "--from" => from = Some(parse_number(value)?),
+            "--to" => to = Some(parse_number(value)?),
+            "--tables" => {
+                tables = value
+                    .split(',')
+                    .map(|table| table.trim().parse())
+                    .collect::<Result<_, _>>()?
+            }
+            _ => return Err(format!("unknown backfill option {}", arg)),
+        }
+    }
+    let from = from.ok_or("please specify --from for backfill")?;
+    let to = to.ok_or("please specify --to for backfill")?;
+    if from > to {
+        return Err(format!("--from {} is greater than --to {}", from, to));
+    }
+    Ok(Some(Backfill { from, to, tables }))
+}
+
+// The last chain state read by `get_last_chain_state`, to resume the analysis from
+fn chain_occupancy(
+    (number, live_cells, capacity, occupied_capacity): (u64, u64, u64, u64),
+) -> (u64, ChainOccupancy) {
+    let occupancy = ChainOccupancy {
+        live_cells,
+        capacity,
+        occupied_capacity,
+    };
+    (number, occupancy)
+}
+
 fn init_config() -> Config {
     let config_path = var("CKB_ANALYZER_CONFIG").unwrap_or_else(|_| {
         panic!("please specify config path via environment variable CKB_ANALYZER_CONFIG")
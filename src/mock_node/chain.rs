@@ -0,0 +1,404 @@
+use ckb_suite_rpc::ckb_jsonrpc_types::{
+    BlockEconomicState, BlockIssuance, BlockView as JsonBlockView, EpochView,
+    HeaderView as JsonHeaderView, MinerReward, PoolTransactionEntry, Status, TransactionWithStatus,
+    TxStatus, Uint64,
+};
+use ckb_suite_rpc::indexer::{CellsCapacity, ScriptType, SearchKey};
+use ckb_types::bytes::Bytes;
+use ckb_types::core::{
+    BlockBuilder, BlockNumber, BlockView, Capacity, EpochNumberWithFraction, ScriptHashType,
+    TransactionBuilder, TransactionView,
+};
+use ckb_types::packed::{Byte32, CellInput, CellOutput, CellbaseWitness, OutPoint, Script};
+use ckb_types::prelude::*;
+use ckb_types::H256;
+use serde_json::{json, Value};
+use std::collections::HashMap;
+
+const GENESIS_TIMESTAMP: u64 = 1_600_000_000_000;
+pub(crate) const BLOCK_INTERVAL: u64 = 8_000;
+pub(crate) const COMPACT_TARGET: u32 = 0x1e08_3126;
+// The mainnet rewards
+const INITIAL_PRIMARY_EPOCH_REWARD: u64 = 0x71af_d498_d000;
+const SECONDARY_EPOCH_REWARD: u64 = 0x37d0_c8e2_8542;
+// A block's reward is finalized by the block after the farthest proposal window
+const FINALIZATION_DELAY: u64 = 11;
+
+/// Synthetic chain served by the mock node. The canonical chain starts from a genesis block;
+/// blocks mined on other parents are fork blocks until the chain is reorganized onto them.
+pub(crate) struct MockChain {
+    epoch_length: u64,
+    blocks: HashMap<Byte32, BlockView>,
+    canonical: Vec<Byte32>,
+    pool: HashMap<Byte32, TransactionView>,
+    // Increased for every built block, so that the blocks at the same height differ
+    nonce: u128,
+}
+
+impl MockChain {
+    pub(crate) fn new(epoch_length: u64) -> Self {
+        let mut chain = Self {
+            epoch_length,
+            blocks: HashMap::new(),
+            canonical: Vec::new(),
+            pool: HashMap::new(),
+            nonce: 0,
+        };
+        let genesis = chain.build_block(None, |builder| builder);
+        chain.canonical.push(genesis.hash());
+        chain.blocks.insert(genesis.hash(), genesis);
+        chain
+    }
+
+    pub(crate) fn miner_lock() -> Script {
+        Script::new_builder()
+            .code_hash(H256([1; 32]).pack())
+            .hash_type(ScriptHashType::Type.into())
+            .args(Bytes::from(vec![2; 20]).pack())
+            .build()
+    }
+
+    pub(crate) fn tip(&self) -> BlockView {
+        let tip_hash = self.canonical.last().expect("genesis exists");
+        self.blocks[tip_hash].clone()
+    }
+
+    pub(crate) fn canonical_block(&self, number: BlockNumber) -> Option<BlockView> {
+        self.canonical
+            .get(number as usize)
+            .map(|hash| self.blocks[hash].clone())
+    }
+
+    fn is_canonical(&self, block: &BlockView) -> bool {
+        self.canonical.get(block.number() as usize) == Some(&block.hash())
+    }
+
+    /// Mine a block on the canonical tip
+    pub(crate) fn mine(&mut self) -> BlockView {
+        self.mine_with(|builder| builder)
+    }
+
+    /// Mine a block on the canonical tip. `f` may add transactions, proposals and uncles to the
+    /// block, whose cellbase is already set.
+    pub(crate) fn mine_with<F: FnOnce(BlockBuilder) -> BlockBuilder>(&mut self, f: F) -> BlockView {
+        let tip = self.tip();
+        let block = self.build_block(Some(&tip), f);
+        for transaction in block.transactions() {
+            self.pool.remove(&transaction.hash());
+        }
+        self.canonical.push(block.hash());
+        self.blocks.insert(block.hash(), block.clone());
+        block
+    }
+
+    /// Mine a block on `parent` without changing the canonical chain
+    pub(crate) fn fork_with<F: FnOnce(BlockBuilder) -> BlockBuilder>(
+        &mut self,
+        parent: &BlockView,
+        f: F,
+    ) -> BlockView {
+        let block = self.build_block(Some(parent), f);
+        self.blocks.insert(block.hash(), block.clone());
+        block
+    }
+
+    /// Switch the canonical chain to the one ending at `tip`. The detached blocks become fork
+    /// blocks.
+    pub(crate) fn reorganize(&mut self, tip: &BlockView) {
+        let mut attached = vec![tip.clone()];
+        loop {
+            let parent = &self.blocks[&attached.last().unwrap().parent_hash()];
+            if self.is_canonical(parent) {
+                self.canonical.truncate(parent.number() as usize + 1);
+                break;
+            }
+            attached.push(parent.clone());
+        }
+        for block in attached.into_iter().rev() {
+            self.canonical.push(block.hash());
+        }
+    }
+
+    pub(crate) fn submit_transaction(&mut self, transaction: TransactionView) {
+        self.pool.insert(transaction.hash(), transaction);
+    }
+
+    /// Respond the JSON-RPC call from the chain state. Returns `None` if the method is not
+    /// supported.
+    pub(crate) fn respond(&self, method: &str, params: &Value) -> Option<Value> {
+        let result = match method {
+            "get_tip_block_number" => json!(Uint64::from(self.tip().number())),
+            "get_tip_header" => json!(JsonHeaderView::from(self.tip().header())),
+            "get_block_by_number" => {
+                let block = self.canonical_block(param_number(params));
+                json!(block.map(JsonBlockView::from))
+            }
+            "get_header_by_number" => {
+                let block = self.canonical_block(param_number(params));
+                json!(block.map(|block| JsonHeaderView::from(block.header())))
+            }
+            "get_block_hash" => {
+                let block = self.canonical_block(param_number(params));
+                json!(block.map(|block| -> H256 { block.hash().unpack() }))
+            }
+            "get_block" => json!(self
+                .blocks
+                .get(&param_hash(params))
+                .filter(|block| self.is_canonical(block))
+                .cloned()
+                .map(JsonBlockView::from)),
+            "get_header" => json!(self
+                .blocks
+                .get(&param_hash(params))
+                .filter(|block| self.is_canonical(block))
+                .map(|block| JsonHeaderView::from(block.header()))),
+            "get_fork_block" => json!(self
+                .blocks
+                .get(&param_hash(params))
+                .filter(|block| !self.is_canonical(block))
+                .cloned()
+                .map(JsonBlockView::from)),
+            "get_epoch_by_number" => json!(self.epoch(param_number(params))),
+            "get_consensus" => consensus(&self.blocks[&self.canonical[0]]),
+            "get_transaction" => json!(self.transaction(&param_hash(params))),
+            "get_block_economic_state" => json!(self.economic_state(&param_hash(params))),
+            // Served as if the node embedded an indexer synchronized to the tip
+            "get_cells_capacity" => {
+                let search_key: SearchKey =
+                    serde_json::from_value(params[0].clone()).expect("search key param");
+                json!(self.cells_capacity(&search_key))
+            }
+            _ => return None,
+        };
+        Some(result)
+    }
+
+    fn epoch(&self, number: u64) -> Option<EpochView> {
+        let start = self
+            .canonical
+            .iter()
+            .map(|hash| &self.blocks[hash])
+            .find(|block| block.epoch().number() == number)?;
+        Some(EpochView {
+            number: number.into(),
+            start_number: start.number().into(),
+            length: start.epoch().length().into(),
+            compact_target: start.compact_target().into(),
+        })
+    }
+
+    // The block's issuance and miner reward, of which the secondary half goes to the miner; not
+    // found until the block is finalized
+    fn economic_state(&self, hash: &Byte32) -> Option<BlockEconomicState> {
+        let block = self
+            .blocks
+            .get(hash)
+            .filter(|block| self.is_canonical(block))?;
+        let finalized_at = self.canonical_block(block.number() + FINALIZATION_DELAY)?;
+        let epoch = block.epoch();
+        let split = |reward: u64| {
+            reward / epoch.length() + u64::from(epoch.index() < reward % epoch.length())
+        };
+        let primary = split(INITIAL_PRIMARY_EPOCH_REWARD);
+        let secondary = split(SECONDARY_EPOCH_REWARD);
+        Some(BlockEconomicState {
+            issuance: BlockIssuance {
+                primary: primary.into(),
+                secondary: secondary.into(),
+            },
+            miner_reward: MinerReward {
+                primary: primary.into(),
+                secondary: (secondary / 2).into(),
+                committed: 0.into(),
+                proposal: 0.into(),
+            },
+            txs_fee: 0.into(),
+            finalized_at: finalized_at.hash().unpack(),
+        })
+    }
+
+    // The capacity of the live cells at the tip whose lock matches the search key, with its args
+    // as a prefix; not found if there is not any
+    fn cells_capacity(&self, search_key: &SearchKey) -> Option<CellsCapacity> {
+        let script: Script = search_key.script.clone().into();
+        let matches = |output: &CellOutput| {
+            let candidate = match search_key.script_type {
+                ScriptType::Lock => output.lock(),
+                ScriptType::Type => match output.type_().to_opt() {
+                    Some(type_) => type_,
+                    None => return false,
+                },
+            };
+            candidate.code_hash() == script.code_hash()
+                && candidate.hash_type().as_slice() == script.hash_type().as_slice()
+                && candidate
+                    .args()
+                    .raw_data()
+                    .starts_with(&script.args().raw_data())
+        };
+        let mut live_cells = HashMap::new();
+        for block_hash in self.canonical.iter() {
+            for transaction in self.blocks[block_hash].transactions() {
+                for input in transaction.inputs().into_iter() {
+                    live_cells.remove(&input.previous_output());
+                }
+                for (index, output) in transaction.outputs().into_iter().enumerate() {
+                    live_cells.insert(OutPoint::new(transaction.hash(), index as u32), output);
+                }
+            }
+        }
+        let capacities = live_cells
+            .values()
+            .filter(|output| matches(output))
+            .map(|output| -> u64 { output.capacity().unpack() })
+            .collect::<Vec<_>>();
+        if capacities.is_empty() {
+            return None;
+        }
+        let tip = self.tip();
+        Some(CellsCapacity {
+            capacity: capacities.iter().sum::<u64>().into(),
+            block_hash: tip.hash().unpack(),
+            block_number: tip.number().into(),
+        })
+    }
+
+    fn transaction(&self, hash: &Byte32) -> Option<TransactionWithStatus> {
+        for block_hash in self.canonical.iter() {
+            let block = &self.blocks[block_hash];
+            if let Some(transaction) = block
+                .transactions()
+                .into_iter()
+                .find(|tx| &tx.hash() == hash)
+            {
+                return Some(TransactionWithStatus {
+                    transaction: transaction.into(),
+                    tx_status: TxStatus {
+                        status: Status::Committed,
+                        block_hash: Some(block.hash().unpack()),
+                    },
+                });
+            }
+        }
+        self.pool
+            .get(hash)
+            .map(|transaction| TransactionWithStatus {
+                transaction: transaction.clone().into(),
+                tx_status: TxStatus {
+                    status: Status::Pending,
+                    block_hash: None,
+                },
+            })
+    }
+
+    fn build_block<F: FnOnce(BlockBuilder) -> BlockBuilder>(
+        &mut self,
+        parent: Option<&BlockView>,
+        f: F,
+    ) -> BlockView {
+        let (number, timestamp, parent_hash) = match parent {
+            Some(parent) => (
+                parent.number() + 1,
+                parent.timestamp() + BLOCK_INTERVAL,
+                parent.hash(),
+            ),
+            None => (0, GENESIS_TIMESTAMP, Byte32::zero()),
+        };
+        let epoch = EpochNumberWithFraction::new(
+            number / self.epoch_length,
+            number % self.epoch_length,
+            self.epoch_length,
+        );
+        self.nonce += 1;
+        let builder = BlockBuilder::default()
+            .number(number.pack())
+            .timestamp(timestamp.pack())
+            .parent_hash(parent_hash)
+            .epoch(epoch.pack())
+            .compact_target(COMPACT_TARGET.pack())
+            .nonce(self.nonce.pack())
+            .transaction(cellbase(number));
+        f(builder).build()
+    }
+}
+
+// The mainnet consensus, except the genesis
+fn consensus(genesis: &BlockView) -> Value {
+    let genesis_hash: H256 = genesis.hash().unpack();
+    json!({
+        "id": "mock",
+        "genesis_hash": genesis_hash,
+        "dao_type_hash": null,
+        "secp256k1_blake160_sighash_all_type_hash": null,
+        "secp256k1_blake160_multisig_all_type_hash": null,
+        "initial_primary_epoch_reward": Uint64::from(INITIAL_PRIMARY_EPOCH_REWARD),
+        "secondary_epoch_reward": Uint64::from(SECONDARY_EPOCH_REWARD),
+        "max_uncles_num": "0x2",
+        "orphan_rate_target": { "numer": "0x1", "denom": "0x28" },
+        "epoch_duration_target": "0x3840",
+        "tx_proposal_window": { "closest": "0x2", "farthest": "0xa" },
+        "proposer_reward_ratio": { "numer": "0x4", "denom": "0xa" },
+        "cellbase_maturity": "0x10000000000",
+        "median_time_block_count": "0x25",
+        "max_block_cycles": "0xd09dc300",
+        "max_block_bytes": "0x91c08",
+        "block_version": "0x0",
+        "tx_version": "0x0",
+        "type_id_code_hash": "0x00000000000000000000000000000000000000000000000000545950455f4944",
+        "max_block_proposals_limit": "0x5dc",
+        "primary_epoch_reward_halving_interval": "0x2238",
+        "permanent_difficulty_in_dummy": false
+    })
+}
+
+fn cellbase(number: BlockNumber) -> TransactionView {
+    let lock = MockChain::miner_lock();
+    let witness = CellbaseWitness::new_builder()
+        .lock(lock.clone())
+        .message(Bytes::from_static(b"mock").pack())
+        .build();
+    TransactionBuilder::default()
+        .input(CellInput::new_cellbase_input(number))
+        .output(
+            CellOutput::new_builder()
+                .capacity(Capacity::shannons(100_000_000_000).pack())
+                .lock(lock)
+                .build(),
+        )
+        .output_data(Bytes::new().pack())
+        .witness(witness.as_bytes().pack())
+        .build()
+}
+
+/// A transaction with a single output, distinguished by `seed`
+pub(crate) fn transaction(seed: u64) -> TransactionView {
+    TransactionBuilder::default()
+        .output(
+            CellOutput::new_builder()
+                .capacity(Capacity::shannons(seed).pack())
+                .lock(MockChain::miner_lock())
+                .build(),
+        )
+        .output_data(Bytes::new().pack())
+        .build()
+}
+
+/// The pool entry of the transaction, as pushed by the "new_transaction" subscription
+pub(crate) fn pool_entry(transaction: &TransactionView) -> PoolTransactionEntry {
+    PoolTransactionEntry {
+        transaction: transaction.clone().into(),
+        cycles: 0.into(),
+        size: (transaction.data().serialized_size_in_block() as u64).into(),
+        fee: Capacity::zero().into(),
+    }
+}
+
+fn param_number(params: &Value) -> u64 {
+    let number: Uint64 = serde_json::from_value(params[0].clone()).expect("number param");
+    number.value()
+}
+
+fn param_hash(params: &Value) -> Byte32 {
+    let hash: H256 = serde_json::from_value(params[0].clone()).expect("hash param");
+    hash.pack()
+}
@@ -0,0 +1,198 @@
+//! Test support: a local fake CKB node serving JSON-RPC from a synthetic chain and pushing
+//! notifications through the TCP subscription, plus an in-memory sink for the queries produced by
+//! the topics.
+
+mod chain;
+mod sink;
+mod subscription;
+
+pub(crate) use chain::{pool_entry, transaction, MockChain, BLOCK_INTERVAL, COMPACT_TARGET};
+pub(crate) use sink::memory_sink;
+
+use crate::config::Config;
+use crate::subscribe::{Notification, Subscription};
+use hyper::service::{make_service_fn, service_fn};
+use hyper::{Body, Request, Response, Server};
+use jsonrpc_server_utils::tokio as tokio01;
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::convert::Infallible;
+use std::net::SocketAddr;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use subscription::Notifications;
+
+pub(crate) const NETWORK: &str = "mock";
+
+pub(crate) struct MockNode {
+    state: Arc<State>,
+    notifications: Arc<Notifications>,
+    rpc_address: SocketAddr,
+    subscription_address: SocketAddr,
+}
+
+struct State {
+    chain: Mutex<MockChain>,
+    // Keyed by method and the JSON text of params, take precedence over the chain state
+    scripted: Mutex<HashMap<(String, String), Value>>,
+    // Delay of every response
+    latency: Mutex<Duration>,
+    // Keyed by method, the requests being served and the maximum of them
+    in_flight: Mutex<HashMap<String, (usize, usize)>>,
+}
+
+impl MockNode {
+    /// Start serving on random local ports. Must be called inside a tokio runtime.
+    pub(crate) async fn start(chain: MockChain) -> Self {
+        let state = Arc::new(State {
+            chain: Mutex::new(chain),
+            scripted: Mutex::new(HashMap::new()),
+            latency: Mutex::new(Duration::default()),
+            in_flight: Mutex::new(HashMap::new()),
+        });
+        let make_service = {
+            let state = Arc::clone(&state);
+            make_service_fn(move |_| {
+                let state = Arc::clone(&state);
+                async move {
+                    Ok::<_, Infallible>(service_fn(move |request| {
+                        handle(request, Arc::clone(&state))
+                    }))
+                }
+            })
+        };
+        let server = Server::bind(&SocketAddr::from(([127, 0, 0, 1], 0))).serve(make_service);
+        let rpc_address = server.local_addr();
+        tokio::spawn(server);
+
+        let notifications = Arc::new(Notifications::new());
+        let subscription_address = subscription::serve(Arc::clone(&notifications)).await;
+        Self {
+            state,
+            notifications,
+            rpc_address,
+            subscription_address,
+        }
+    }
+
+    /// Respond `result` to the calls of `method` with exactly `params`
+    pub(crate) fn script(&self, method: &str, params: Value, result: Value) {
+        self.state
+            .scripted
+            .lock()
+            .unwrap()
+            .insert((method.to_string(), params.to_string()), result);
+    }
+
+    /// Delay every response by `latency`, as a remote node
+    pub(crate) fn set_latency(&self, latency: Duration) {
+        *self.state.latency.lock().unwrap() = latency;
+    }
+
+    /// The maximum number of requests of `method` served at the same time. A batch request counts
+    /// once, by the method of its first call.
+    pub(crate) fn max_concurrent_requests(&self, method: &str) -> usize {
+        self.state
+            .in_flight
+            .lock()
+            .unwrap()
+            .get(method)
+            .map(|(_, max)| *max)
+            .unwrap_or_default()
+    }
+
+    /// Change the chain being served, e.g. mine or reorganize
+    pub(crate) fn update_chain<F: FnOnce(&mut MockChain)>(&self, f: F) {
+        f(&mut self.state.chain.lock().unwrap())
+    }
+
+    /// Push the notification to the subscribers of its topic, including the future ones
+    pub(crate) fn notify(&self, notification: Notification) {
+        self.notifications.push(notification);
+    }
+
+    /// Config pointing to this node
+    pub(crate) fn config(&self) -> Config {
+        let config = format!(
+            r#"
+            network = "{}"
+            topics = []
+            postgres = "postgresql://mock@127.0.0.1/mock"
+
+            [node]
+            host = "127.0.0.1"
+            rpc_port = {}
+            subscription_port = {}
+            data_dir = ""
+            bootnodes = []
+            "#,
+            NETWORK,
+            self.rpc_address.port(),
+            self.subscription_address.port(),
+        );
+        toml::from_str(&config).expect("parse mock config")
+    }
+}
+
+/// Run the subscription client the same way as main does
+pub(crate) fn spawn_subscription(subscription: Subscription) {
+    ::std::thread::spawn(move || {
+        let mut runtime01 = tokio01::runtime::Builder::new().build().unwrap();
+        runtime01.block_on(subscription.run()).unwrap();
+    });
+}
+
+async fn handle(request: Request<Body>, state: Arc<State>) -> Result<Response<Body>, Infallible> {
+    let bytes = hyper::body::to_bytes(request.into_body())
+        .await
+        .expect("read request body");
+    let body: Value = serde_json::from_slice(&bytes).expect("parse request body");
+    let method = match &body {
+        Value::Array(batch) => batch.first().map(|call| call["method"].clone()),
+        call => Some(call["method"].clone()),
+    }
+    .and_then(|method| method.as_str().map(ToString::to_string))
+    .unwrap_or_default();
+    {
+        let mut in_flight = state.in_flight.lock().unwrap();
+        let (current, max) = in_flight.entry(method.clone()).or_default();
+        *current += 1;
+        *max = (*max).max(*current);
+    }
+    let latency = *state.latency.lock().unwrap();
+    tokio::time::sleep(latency).await;
+
+    let response = match body {
+        Value::Array(batch) => {
+            Value::Array(batch.into_iter().map(|call| state.respond(&call)).collect())
+        }
+        call => state.respond(&call),
+    };
+    if let Some((current, _)) = state.in_flight.lock().unwrap().get_mut(&method) {
+        *current -= 1;
+    }
+    Ok(Response::new(Body::from(response.to_string())))
+}
+
+impl State {
+    fn respond(&self, call: &Value) -> Value {
+        let id = call["id"].clone();
+        let method = call["method"].as_str().unwrap_or_default();
+        let params = &call["params"];
+        let scripted = self
+            .scripted
+            .lock()
+            .unwrap()
+            .get(&(method.to_string(), params.to_string()))
+            .cloned();
+        let result = scripted.or_else(|| self.chain.lock().unwrap().respond(method, params));
+        match result {
+            Some(result) => json!({ "jsonrpc": "2.0", "id": id, "result": result }),
+            None => json!({
+                "jsonrpc": "2.0",
+                "id": id,
+                "error": { "code": -32601, "message": "Method not found" },
+            }),
+        }
+    }
+}
@@ -0,0 +1,64 @@
+use super::NETWORK;
+use std::time::{Duration, Instant};
+
+const WAIT_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Collects the queries which the topics send to Postgres
+pub(crate) struct MemorySink {
+    receiver: crossbeam::channel::Receiver<String>,
+    queries: Vec<String>,
+}
+
+pub(crate) fn memory_sink() -> (crossbeam::channel::Sender<String>, MemorySink) {
+    let (sender, receiver) = crossbeam::channel::bounded(5000);
+    let sink = MemorySink {
+        receiver,
+        queries: Vec::new(),
+    };
+    (sender, sink)
+}
+
+impl MemorySink {
+    /// Receive queries until `done` is satisfied. Panic on timeout.
+    pub(crate) async fn wait_until<F: Fn(&Self) -> bool>(&mut self, done: F) {
+        let start = Instant::now();
+        loop {
+            while let Ok(query) = self.receiver.try_recv() {
+                self.queries.push(query);
+            }
+            if done(self) {
+                return;
+            }
+            assert!(
+                start.elapsed() < WAIT_TIMEOUT,
+                "timeout, received queries: {:#?}",
+                self.queries
+            );
+            tokio::time::sleep(Duration::from_millis(50)).await;
+        }
+    }
+
+    pub(crate) fn queries(&self) -> &[String] {
+        &self.queries
+    }
+
+    /// The values of the rows inserted into the table, unquoted
+    pub(crate) fn inserts(&self, table: &str) -> Vec<Vec<String>> {
+        let prefix = format!("INSERT INTO {}_{}", NETWORK, table);
+        self.queries
+            .iter()
+            .filter(|query| {
+                query.starts_with(&prefix)
+                    && matches!(query[prefix.len()..].chars().next(), Some(' ') | Some('('))
+            })
+            .map(|query| {
+                let values = &query[query.find("VALUES (").expect("VALUES") + "VALUES (".len()
+                    ..query.rfind(')').expect(")")];
+                values
+                    .split(", ")
+                    .map(|value| value.trim_matches('\'').to_string())
+                    .collect()
+            })
+            .collect()
+    }
+}
@@ -0,0 +1,122 @@
+use crate::subscribe::{Notification, Topic};
+use serde_json::{json, Value};
+use std::net::SocketAddr;
+use std::sync::{Arc, Mutex};
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::watch;
+
+/// Pushed notifications. Every subscriber receives all the notifications of its topic, including
+/// the ones pushed before it subscribed, so tests need not wait for the subscription.
+pub(super) struct Notifications {
+    pushed: Mutex<Vec<(Topic, String)>>,
+    version: watch::Sender<usize>,
+    version_receiver: watch::Receiver<usize>,
+}
+
+impl Notifications {
+    pub(super) fn new() -> Self {
+        let (version, version_receiver) = watch::channel(0);
+        Self {
+            pushed: Mutex::new(Vec::new()),
+            version,
+            version_receiver,
+        }
+    }
+
+    pub(super) fn push(&self, notification: Notification) {
+        let (topic, message) = match notification {
+            Notification::NewTipHeader(header) => (Topic::NewTipHeader, json!(header)),
+            Notification::NewTipBlock(block) => (Topic::NewTipBlock, json!(block)),
+            Notification::NewTransaction(entry) => (Topic::NewTransaction, json!(entry)),
+            Notification::ProposedTransaction(entry) => (Topic::ProposedTransaction, json!(entry)),
+            Notification::RejectedTransaction(entry, reason) => {
+                (Topic::RejectedTransaction, json!([entry, reason]))
+            }
+        };
+        let len = {
+            let mut pushed = self.pushed.lock().unwrap();
+            pushed.push((topic, message.to_string()));
+            pushed.len()
+        };
+        let _ = self.version.send(len);
+    }
+
+    fn pushed_since(&self, topic: Topic, cursor: &mut usize) -> Vec<String> {
+        let pushed = self.pushed.lock().unwrap();
+        let messages = pushed[*cursor..]
+            .iter()
+            .filter(|(pushed_topic, _)| *pushed_topic == topic)
+            .map(|(_, message)| message.clone())
+            .collect();
+        *cursor = pushed.len();
+        messages
+    }
+}
+
+/// Serve the TCP subscription on a random local port
+pub(super) async fn serve(notifications: Arc<Notifications>) -> SocketAddr {
+    let listener = TcpListener::bind("127.0.0.1:0")
+        .await
+        .expect("bind subscription listener");
+    let address = listener.local_addr().unwrap();
+    tokio::spawn(async move {
+        let mut next_subscription_id = 0u64;
+        while let Ok((stream, _)) = listener.accept().await {
+            tokio::spawn(serve_subscriber(
+                stream,
+                Arc::clone(&notifications),
+                next_subscription_id,
+            ));
+            next_subscription_id += 1;
+        }
+    });
+    address
+}
+
+// The client sends newline-separated requests. Only the first one, "subscribe", is handled.
+async fn serve_subscriber(
+    stream: TcpStream,
+    notifications: Arc<Notifications>,
+    subscription_id: u64,
+) {
+    let (reader, mut writer) = stream.into_split();
+    let mut lines = BufReader::new(reader).lines();
+    let request: Value = match lines.next_line().await {
+        Ok(Some(line)) => serde_json::from_str(&line).expect("parse subscribe request"),
+        _ => return,
+    };
+    assert_eq!(request["method"], "subscribe", "request: {}", request);
+    let topic: Topic =
+        serde_json::from_value(request["params"][0].clone()).expect("parse subscribe topic");
+    let subscription_id = format!("{:#x}", subscription_id);
+    let response = json!({ "jsonrpc": "2.0", "id": request["id"], "result": subscription_id });
+    if write_line(&mut writer, &response).await.is_err() {
+        return;
+    }
+
+    let mut version = notifications.version_receiver.clone();
+    let mut cursor = 0;
+    loop {
+        for message in notifications.pushed_since(topic, &mut cursor) {
+            let notification = json!({
+                "jsonrpc": "2.0",
+                "method": "subscribe",
+                "params": { "result": message, "subscription": subscription_id },
+            });
+            if write_line(&mut writer, &notification).await.is_err() {
+                return;
+            }
+        }
+        if version.changed().await.is_err() {
+            return;
+        }
+    }
+}
+
+async fn write_line<W: AsyncWriteExt + Unpin>(
+    writer: &mut W,
+    value: &Value,
+) -> std::io::Result<()> {
+    writer.write_all(format!("{}\n", value).as_bytes()).await
+}
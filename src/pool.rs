@@ -0,0 +1,121 @@
+//! Identify the mining pools of blocks by their miner lock args, or by the cellbase witness
+//! message which pools often tag their blocks with. The labels are read from a TOML file:
+//!
+//! ```toml
+//! [[pools]]
+//! name = "ExamplePool"
+//! lock_args = ["0x0202020202020202020202020202020202020202"]
+//! message_patterns = ["(?i)examplepool"]
+//! ```
+//!
+//! A block is labelled by the first pool whose lock args match; otherwise, by the first pool
+//! whose message patterns match.
+
+use crate::config::Config;
+use regex::Regex;
+use serde::Deserialize;
+use std::collections::HashSet;
+
+#[derive(Deserialize, Debug)]
+struct PoolLabelsFile {
+    #[serde(default)]
+    pools: Vec<PoolLabelConfig>,
+}
+
+#[derive(Deserialize, Debug)]
+struct PoolLabelConfig {
+    name: String,
+    #[serde(default)]
+    lock_args: Vec<String>,
+    #[serde(default)]
+    message_patterns: Vec<String>,
+}
+
+#[derive(Debug)]
+struct PoolLabel {
+    name: String,
+    lock_args: HashSet<String>,
+    message_patterns: Vec<Regex>,
+}
+
+#[derive(Debug, Default)]
+pub(crate) struct PoolLabels {
+    pools: Vec<PoolLabel>,
+}
+
+impl PoolLabels {
+    /// Load the configured label file; no pool is identified if it is not configured
+    pub(crate) fn from_config(config: &Config) -> Self {
+        match &config.pool_labels {
+            Some(path) => {
+                let content = std::fs::read_to_string(path).unwrap_or_else(|err| {
+                    panic!("failed to read pool labels \"{}\", error: {}", path, err)
+                });
+                Self::parse(&content).unwrap_or_else(|err| {
+                    panic!("failed to parse pool labels \"{}\", error: {}", path, err)
+                })
+            }
+            None => Self::default(),
+        }
+    }
+
+    pub(crate) fn parse(content: &str) -> Result<Self, String> {
+        let file: PoolLabelsFile = toml::from_str(content).map_err(|err| err.to_string())?;
+        let mut pools = Vec::with_capacity(file.pools.len());
+        for pool in file.pools {
+            // The names are written into the queries as they are
+            if pool.name.contains('\'') {
+                return Err(format!("pool name {:?} contains a single quote", pool.name));
+            }
+            // The `pool` columns are `VARCHAR ( 50 )`
+            if pool.name.chars().count() > 50 {
+                return Err(format!(
+                    "pool name {:?} is longer than 50 characters",
+                    pool.name
+                ));
+            }
+            let message_patterns = pool
+                .message_patterns
+                .iter()
+                .map(|pattern| Regex::new(pattern).map_err(|err| err.to_string()))
+                .collect::<Result<Vec<_>, _>>()?;
+            pools.push(PoolLabel {
+                name: pool.name,
+                lock_args: pool
+                    .lock_args
+                    .iter()
+                    .map(|args| args.to_lowercase())
+                    .collect(),
+                message_patterns,
+            });
+        }
+        Ok(Self { pools })
+    }
+
+    /// Return the pool name of the block mined by the lock args, in hex prefixed by "0x", with the
+    /// decoded cellbase witness message
+    pub(crate) fn identify(&self, lock_args: &str, message: &str) -> Option<&str> {
+        let lock_args = lock_args.to_lowercase();
+        self.pools
+            .iter()
+            .find(|pool| pool.lock_args.contains(&lock_args))
+            .or_else(|| {
+                self.pools.iter().find(|pool| {
+                    pool.message_patterns
+                        .iter()
+                        .any(|pattern| pattern.is_match(message))
+                })
+            })
+            .map(|pool| pool.name.as_str())
+    }
+}
+
+/// Decode the cellbase witness message as text, dropping the control characters
+pub(crate) fn decode_message(message: &[u8]) -> String {
+    String::from_utf8_lossy(message)
+        .chars()
+        .filter(|c| !c.is_control())
+        .collect::<String>()
+        .trim()
+        .to_string()
+}
@@ -0,0 +1,82 @@
+//! Name the well-known scripts by their code hashes and hash types. The scripts deployed in the
+//! genesis block are built in; the others are configured per network:
+//!
+//! ```toml
+//! [[scripts]]
+//! name = "sudt"
+//! code_hash = "0x5e7a36a77e68eecc013dfa2fe6a23f3b6c344b04005808694ae6dd45eea4cfd5"
+//! hash_type = "type"
+//! ```
+
+use crate::address::{SECP256K1_BLAKE160_CODE_HASH, SECP256K1_MULTISIG_CODE_HASH};
+use crate::config::Config;
+use crate::dao::NERVOS_DAO_CODE_HASH;
+use ckb_types::H256;
+use std::collections::HashMap;
+use std::str::FromStr;
+
+#[derive(Debug, Default)]
+pub(crate) struct ScriptRegistry {
+    // (code hash, hash type) => name
+    names: HashMap<(String, String), String>,
+}
+
+impl ScriptRegistry {
+    /// The built-in genesis scripts, extended by the configured ones
+    pub(crate) fn from_config(config: &Config) -> Self {
+        let mut registry = Self::default();
+        let genesis_scripts = [
+            ("secp256k1_blake160", SECP256K1_BLAKE160_CODE_HASH),
+            ("secp256k1_multisig", SECP256K1_MULTISIG_CODE_HASH),
+            ("nervos_dao", NERVOS_DAO_CODE_HASH),
+        ];
+        for (name, code_hash) in genesis_scripts.iter() {
+            registry.register(name, &format!("{:#x}", code_hash), "type");
+        }
+        for script in config.scripts.iter() {
+            // The names are written into the queries as they are
+            assert!(
+                !script.name.contains('\''),
+                "script name {:?} contains a single quote",
+                script.name
+            );
+            assert!(
+                script.code_hash.len() == 66
+                    && script.code_hash.starts_with("0x")
+                    && H256::from_str(&script.code_hash[2..].to_lowercase()).is_ok(),
+                "script {:?} has an invalid code hash {:?}",
+                script.name,
+                script.code_hash
+            );
+            assert!(
+                script.hash_type == "data" || script.hash_type == "type",
+                "script {:?} has an unknown hash type {:?}",
+                script.name,
+                script.hash_type
+            );
+            registry.register(&script.name, &script.code_hash, &script.hash_type);
+        }
+        registry
+    }
+
+    fn register(&mut self, name: &str, code_hash: &str, hash_type: &str) {
+        self.names.insert(
+            (code_hash.to_lowercase(), hash_type.to_string()),
+            name.to_string(),
+        );
+    }
+
+    /// The registered scripts, as code hash, hash type and name
+    pub(crate) fn scripts(&self) -> impl Iterator<Item = (&str, &str, &str)> {
+        self.names.iter().map(|((code_hash, hash_type), name)| {
+            (code_hash.as_str(), hash_type.as_str(), name.as_str())
+        })
+    }
+
+    /// Return the name of the script, with the code hash in hex prefixed by "0x"
+    pub(crate) fn name(&self, code_hash: &str, hash_type: &str) -> Option<&str> {
+        self.names
+            .get(&(code_hash.to_lowercase(), hash_type.to_string()))
+            .map(|name| name.as_str())
+    }
+}
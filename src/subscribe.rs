@@ -1,4 +1,6 @@
+use crate::capture::{CaptureWriter, Replay};
 use crate::util::forward_tokio1_channel;
+use ckb_suite_rpc::ckb_jsonrpc_types::{BlockView, HeaderView, PoolTransactionEntry};
 use jsonrpc_client_transports::RpcError;
 use jsonrpc_core::{futures::prelude::*, Result};
 use jsonrpc_core_client::{
@@ -10,6 +12,8 @@ use jsonrpc_pubsub::{typed::Subscriber, SubscriptionId};
 use jsonrpc_server_utils::{codecs::StreamCodec, tokio::codec::Decoder, tokio::net::TcpStream};
 use serde::{Deserialize, Serialize};
 use std::net::SocketAddr;
+use std::sync::Arc;
+use std::time::Instant;
 
 #[derive(Serialize, Deserialize, PartialEq, Eq, Hash, Debug, Clone, Copy)]
 #[serde(rename_all = "snake_case")]
@@ -26,6 +30,42 @@ pub enum Topic {
     RejectedTransaction,
 }
 
+/// Typed notification decoded from the raw subscription message.
+#[derive(Debug, Clone)]
+pub enum Notification {
+    NewTipHeader(HeaderView),
+    NewTipBlock(BlockView),
+    NewTransaction(PoolTransactionEntry),
+    ProposedTransaction(PoolTransactionEntry),
+    /// The rejected transaction entry and the reject reason
+    RejectedTransaction(PoolTransactionEntry, String),
+}
+
+impl Notification {
+    pub fn decode(topic: Topic, message: &str) -> serde_json::Result<Self> {
+        let notification = match topic {
+            Topic::NewTipHeader => Notification::NewTipHeader(serde_json::from_str(message)?),
+            Topic::NewTipBlock => Notification::NewTipBlock(serde_json::from_str(message)?),
+            Topic::NewTransaction => Notification::NewTransaction(serde_json::from_str(message)?),
+            Topic::ProposedTransaction => {
+                Notification::ProposedTransaction(serde_json::from_str(message)?)
+            }
+            Topic::RejectedTransaction => {
+                // The reason is a plain string on older nodes and a structured object on newer
+                // ones; keep the JSON text of the latter.
+                let (entry, reason): (PoolTransactionEntry, serde_json::Value) =
+                    serde_json::from_str(message)?;
+                let reason = match reason {
+                    serde_json::Value::String(reason) => reason,
+                    reason => reason.to_string(),
+                };
+                Notification::RejectedTransaction(entry, reason)
+            }
+        };
+        Ok(notification)
+    }
+}
+
 #[allow(clippy::needless_return)]
 #[rpc]
 pub trait SubscriptionRpc {
@@ -38,18 +78,19 @@ pub trait SubscriptionRpc {
     fn unsubscribe(&self, meta: Option<Self::Metadata>, id: SubscriptionId) -> Result<bool>;
 }
 
-#[derive(Debug, Clone)]
+#[derive(Clone)]
 pub struct Subscription {
     address: SocketAddr,
     topic: Topic,
-    publisher: crate::tokio01::sync::mpsc::Sender<(Topic, String)>,
+    publisher: crate::tokio01::sync::mpsc::Sender<Notification>,
+    capture: Option<Arc<CaptureWriter>>,
 }
 
 impl Subscription {
     pub fn new(
         ckb_subscription_url: String,
         topic: Topic,
-    ) -> (Self, crossbeam::channel::Receiver<(Topic, String)>) {
+    ) -> (Self, crossbeam::channel::Receiver<Notification>) {
         let (publisher, subscriber) = {
             let (publisher, subscriber) = crate::tokio01::sync::mpsc::channel(100);
             let subscriber = forward_tokio1_channel(subscriber);
@@ -65,11 +106,36 @@ impl Subscription {
                 address,
                 topic,
                 publisher,
+                capture: None,
             },
             subscriber,
         )
     }
 
+    /// Write the received messages into the capture as well
+    pub(crate) fn with_capture(mut self, capture: Arc<CaptureWriter>) -> Self {
+        self.capture = Some(capture);
+        self
+    }
+
+    /// Publish the captured messages of the topic at their scheduled instants, instead of
+    /// subscribing from the node. Blocks until all the messages are published.
+    pub(crate) fn replay(self, replay: Arc<Replay>) {
+        let notifications = replay.notifications(self.topic);
+        log::info!(
+            "replay {} messages of topic \"{:?}\"",
+            notifications.len(),
+            self.topic
+        );
+        for (deliver_at, message) in notifications {
+            let now = Instant::now();
+            if deliver_at > now {
+                std::thread::sleep(deliver_at - now);
+            }
+            publish(&self.publisher, self.topic, &message);
+        }
+    }
+
     // IMPORTANT: This task use `jsonrpc_server_utils::tokio`, which version is 0.1.x. It is
     // incompatible with tokio 0.2! So use jsonrpc_server_utils::tokio as the runtime!
     pub fn run(self) -> impl jsonrpc_core::futures::Future<Item = (), Error = ()> {
@@ -94,14 +160,14 @@ impl Subscription {
         let requester = gen_client::Client::from(sender_channel);
         let publisher = self.publisher.clone();
         let topic = self.topic;
+        let capture = self.capture.clone();
         let subscription = requester.subscribe(topic).and_then(
             move |subscriber: TypedSubscriptionStream<String>| {
                 subscriber.for_each(move |message| {
-                    publisher
-                        .clone()
-                        .send((topic, message))
-                        .wait()
-                        .unwrap_or_else(|err| panic!("channel error: {:?}", err));
+                    if let Some(capture) = capture.as_ref() {
+                        capture.record_notification(topic, &message);
+                    }
+                    publish(&publisher, topic, &message);
                     Ok(())
                 })
             },
@@ -117,3 +183,25 @@ impl Subscription {
             .map_err(|err| panic!("map_err error {:?}", err))
     }
 }
+
+fn publish(
+    publisher: &crate::tokio01::sync::mpsc::Sender<Notification>,
+    topic: Topic,
+    message: &str,
+) {
+    match Notification::decode(topic, message) {
+        Ok(notification) => {
+            publisher
+                .clone()
+                .send(notification)
+                .wait()
+                .unwrap_or_else(|err| panic!("channel error: {:?}", err));
+        }
+        Err(err) => log::error!(
+            "failed to decode notification of topic \"{:?}\", message: {}, error: {}",
+            topic,
+            message,
+            err
+        ),
+    }
+}
@@ -15,10 +15,25 @@ use std::fmt::Debug;
 ///     n_uncles        INT             NOT NULL,
 ///     hash            CHAR ( 66 )     NOT NULL,
 ///     miner           CHAR ( 66 )     NOT NULL,
-///     version         INT             NOT NULL
+///     version         INT             NOT NULL,
+///     pool            VARCHAR ( 50 )  NOT NULL,
+///     miner_address   TEXT            NOT NULL,
+///     miner_code_hash CHAR ( 66 )     NOT NULL,
+///     miner_hash_type VARCHAR ( 10 )  NOT NULL,
+///     miner_args      TEXT            NOT NULL,
+///     message         TEXT            NOT NULL
 /// );
 ///
 /// SELECT create_hypertable('$network_block', 'time');
+///
+/// -- Upgrade an existing table, then re-derive the rows via `ckb-analyzer backfill --tables block`
+/// ALTER TABLE $network_block
+///     ADD COLUMN IF NOT EXISTS pool               VARCHAR ( 50 )  NOT NULL DEFAULT '',
+///     ADD COLUMN IF NOT EXISTS miner_address      TEXT            NOT NULL DEFAULT '',
+///     ADD COLUMN IF NOT EXISTS miner_code_hash    CHAR ( 66 )     NOT NULL DEFAULT '',
+///     ADD COLUMN IF NOT EXISTS miner_hash_type    VARCHAR ( 10 )  NOT NULL DEFAULT '',
+///     ADD COLUMN IF NOT EXISTS miner_args         TEXT            NOT NULL DEFAULT '',
+///     ADD COLUMN IF NOT EXISTS message            TEXT            NOT NULL DEFAULT '';
 /// ```
 #[derive(Clone, Debug)]
 pub struct Block {
@@ -32,13 +47,19 @@ pub struct Block {
     pub hash: String,  // hex hash
     pub miner: String, // hex hash
     pub version: i32,
+    pub pool: String,            // empty if unidentified
+    pub miner_address: String,   // "ckb1..." on mainnet, "ckt1..." on the others, empty if unknown
+    pub miner_code_hash: String, // hex hash
+    pub miner_hash_type: String, // "data", "type" or "unknown"
+    pub miner_args: String,      // hex
+    pub message: String,         // the decoded cellbase witness message
 }
 
 impl Block {
     pub fn insert_query(&self) -> String {
         format!(
-            "INSERT INTO {}_block (network, time, number, interval, n_transactions, n_proposals, n_uncles, hash, miner, version)\
-            VALUES ('{}', '{}', {}, {}, {}, {}, {}, '{}', '{}', {})",
+            "INSERT INTO {}_block (network, time, number, interval, n_transactions, n_proposals, n_uncles, hash, miner, version, pool, miner_address, miner_code_hash, miner_hash_type, miner_args, message)\
+            VALUES ('{}', '{}', {}, {}, {}, {}, {}, '{}', '{}', {}, '{}', '{}', '{}', '{}', '{}', '{}')",
             &self.network,
             &self.network,
             &self.time,
@@ -50,6 +71,29 @@ impl Block {
             &self.hash,
             &self.miner,
             &self.version,
+            &self.pool,
+            &self.miner_address,
+            &self.miner_code_hash,
+            &self.miner_hash_type,
+            &self.miner_args,
+            // The message is arbitrary text chosen by the miner
+            self.message.replace('\'', "''"),
+        )
+    }
+
+    /// Delete the rows at the same height, before re-inserting it
+    pub fn delete_query(&self) -> String {
+        format!(
+            "DELETE FROM {}_block WHERE network = '{}' AND number = {}",
+            &self.network, &self.network, &self.number,
+        )
+    }
+
+    /// Delete the rows since the given height, which are rolled back
+    pub fn delete_since_query(network: &str, number: i64) -> String {
+        format!(
+            "DELETE FROM {}_block WHERE network = '{}' AND number >= {}",
+            network, network, number,
         )
     }
 }
@@ -64,10 +108,25 @@ impl Block {
 ///     n_proposals         INT         NOT NULL,
 ///     hash                CHAR ( 66 ) NOT NULL,
 ///     miner               CHAR ( 66 ) NOT NULL,
-///     version             INT         NOT NULL
+///     version             INT         NOT NULL,
+///     pool                VARCHAR ( 50 )  NOT NULL,
+///     miner_address       TEXT            NOT NULL,
+///     miner_code_hash     CHAR ( 66 )     NOT NULL,
+///     miner_hash_type     VARCHAR ( 10 )  NOT NULL,
+///     miner_args          TEXT            NOT NULL,
+///     message             TEXT            NOT NULL
 /// );
 ///
 /// SELECT create_hypertable('$network_uncle', 'time');
+///
+/// -- Upgrade an existing table, then re-derive the rows via `ckb-analyzer backfill --tables uncle`
+/// ALTER TABLE $network_uncle
+///     ADD COLUMN IF NOT EXISTS pool               VARCHAR ( 50 )  NOT NULL DEFAULT '',
+///     ADD COLUMN IF NOT EXISTS miner_address      TEXT            NOT NULL DEFAULT '',
+///     ADD COLUMN IF NOT EXISTS miner_code_hash    CHAR ( 66 )     NOT NULL DEFAULT '',
+///     ADD COLUMN IF NOT EXISTS miner_hash_type    VARCHAR ( 10 )  NOT NULL DEFAULT '',
+///     ADD COLUMN IF NOT EXISTS miner_args         TEXT            NOT NULL DEFAULT '',
+///     ADD COLUMN IF NOT EXISTS message            TEXT            NOT NULL DEFAULT '';
 /// ```
 #[derive(Clone, Debug)]
 pub struct Uncle {
@@ -80,13 +139,19 @@ pub struct Uncle {
     pub hash: String,  // hex hash
     pub miner: String, // hex hash
     pub version: i32,
+    pub pool: String,            // empty if unidentified
+    pub miner_address: String,   // "ckb1..." on mainnet, "ckt1..." on the others, empty if unknown
+    pub miner_code_hash: String, // hex hash
+    pub miner_hash_type: String, // "data", "type" or "unknown"
+    pub miner_args: String,      // hex
+    pub message: String,         // the decoded cellbase witness message
 }
 
 impl Uncle {
     pub fn insert_query(&self) -> String {
         format!(
-            "INSERT INTO {}_uncle (network, time, number, lag_to_canonical, n_transactions, n_proposals, hash, miner, version)\
-            VALUES ('{}', '{}', {}, {}, {}, {}, '{}', '{}', {})",
+            "INSERT INTO {}_uncle (network, time, number, lag_to_canonical, n_transactions, n_proposals, hash, miner, version, pool, miner_address, miner_code_hash, miner_hash_type, miner_args, message)\
+            VALUES ('{}', '{}', {}, {}, {}, {}, '{}', '{}', {}, '{}', '{}', '{}', '{}', '{}', '{}')",
             &self.network,
             &self.network,
             &self.time,
@@ -97,6 +162,34 @@ impl Uncle {
             &self.hash,
             &self.miner,
             &self.version,
+            &self.pool,
+            &self.miner_address,
+            &self.miner_code_hash,
+            &self.miner_hash_type,
+            &self.miner_args,
+            // The message is arbitrary text chosen by the miner
+            self.message.replace('\'', "''"),
+        )
+    }
+
+    /// Delete the rows of the same uncle, before re-inserting it
+    pub fn delete_query(&self) -> String {
+        format!(
+            "DELETE FROM {}_uncle WHERE network = '{}' AND hash = '{}'",
+            &self.network, &self.network, &self.hash,
+        )
+    }
+
+    /// Delete the rows of the given uncles, which are rolled back
+    pub fn delete_by_hashes_query(network: &str, hashes: &[String]) -> String {
+        let hashes = hashes
+            .iter()
+            .map(|hash| format!("'{}'", hash))
+            .collect::<Vec<_>>()
+            .join(", ");
+        format!(
+            "DELETE FROM {}_uncle WHERE network = '{}' AND hash IN ({})",
+            network, network, hashes,
         )
     }
 }
@@ -127,6 +220,699 @@ impl TwoPCCommitment {
             &self.network, self.network, &self.time, &self.number, &self.delay
         )
     }
+
+    /// Delete the commitments of the given committed block, before re-inserting them
+    pub fn delete_query(network: &str, number: i64) -> String {
+        format!(
+            "DELETE FROM {}_two_pc_commitment WHERE network = '{}' AND number = {}",
+            network, network, number,
+        )
+    }
+
+    /// Delete the commitments since the given committed block, which are rolled back
+    pub fn delete_since_query(network: &str, number: i64) -> String {
+        format!(
+            "DELETE FROM {}_two_pc_commitment WHERE network = '{}' AND number >= {}",
+            network, network, number,
+        )
+    }
+}
+
+/// ```
+/// CREATE TABLE IF NOT EXISTS $network_expired_proposal (
+///     network         VARCHAR ( 10 )      NOT NULL,
+///     time            TIMESTAMP           NOT NULL,
+///     number          BIGINT              NOT NULL,
+///     proposal_id     CHAR ( 22 )         NOT NULL,
+///     proposed_number BIGINT              NOT NULL,
+///     proposer_hash   CHAR ( 66 )         NOT NULL,
+///     miner           VARCHAR ( 200 ),
+///     is_uncle        BOOLEAN             NOT NULL
+/// );
+///
+/// SELECT create_hypertable('$network_expired_proposal', 'time');
+/// ```
+#[derive(Clone, Debug)]
+pub struct ExpiredProposal {
+    pub network: String,
+    pub time: chrono::NaiveDateTime, // the timestamp of the block closing the proposal window
+    pub number: i64,                 // the number of the block closing the proposal window
+    pub proposal_id: String,         // hex proposal short id
+    // The number of the block proposing it, or including the proposing uncle
+    pub proposed_number: i64,
+    pub proposer_hash: String, // hex hash of the proposing block or uncle
+    // The proposer's miner lock args, NULL if the proposing uncle is not found
+    pub miner: Option<String>,
+    pub is_uncle: bool,
+}
+
+impl ExpiredProposal {
+    pub fn insert_query(&self) -> String {
+        format!(
+            "INSERT INTO {}_expired_proposal (network, time, number, proposal_id, proposed_number, proposer_hash, miner, is_uncle) \
+            VALUES ('{}', '{}', {}, '{}', {}, '{}', {}, {})",
+            &self.network,
+            &self.network,
+            &self.time,
+            &self.number,
+            &self.proposal_id,
+            &self.proposed_number,
+            &self.proposer_hash,
+            self.miner
+                .as_ref()
+                .map_or_else(|| "NULL".to_string(), |miner| format!("'{}'", miner)),
+            &self.is_uncle,
+        )
+    }
+
+    /// Delete the proposals expired at the given block, before re-inserting them
+    pub fn delete_query(network: &str, number: i64) -> String {
+        format!(
+            "DELETE FROM {}_expired_proposal WHERE network = '{}' AND number = {}",
+            network, network, number,
+        )
+    }
+
+    /// Delete the proposals expired since the given block, which are rolled back
+    pub fn delete_since_query(network: &str, number: i64) -> String {
+        format!(
+            "DELETE FROM {}_expired_proposal WHERE network = '{}' AND number >= {}",
+            network, network, number,
+        )
+    }
+}
+
+/// ```
+/// CREATE TABLE IF NOT EXISTS $network_committed_transaction (
+///     network             VARCHAR ( 10 )  NOT NULL,
+///     time                TIMESTAMP       NOT NULL,
+///     number              BIGINT          NOT NULL,
+///     index               INT             NOT NULL,
+///     hash                CHAR ( 66 )     NOT NULL,
+///     n_inputs            INT             NOT NULL,
+///     n_outputs           INT             NOT NULL,
+///     n_cell_deps         INT             NOT NULL,
+///     n_header_deps       INT             NOT NULL,
+///     size                INT             NOT NULL,
+///     output_capacity     BIGINT          NOT NULL,
+///     witness_size        INT             NOT NULL,
+///     lock_code_hashes    TEXT[]          NOT NULL,
+///     type_code_hashes    TEXT[]          NOT NULL
+/// );
+///
+/// SELECT create_hypertable('$network_committed_transaction', 'time');
+/// ```
+#[derive(Clone, Debug)]
+pub struct CommittedTransaction {
+    pub network: String,
+    pub time: chrono::NaiveDateTime, // committed block timestamp
+    pub number: i64,                 // committed block number
+    pub index: i32,                  // index within the committed block
+    pub hash: String,                // hex hash
+    pub n_inputs: i32,
+    pub n_outputs: i32,
+    pub n_cell_deps: i32,
+    pub n_header_deps: i32,
+    pub size: i32,                     // serialized size in the block, in bytes
+    pub output_capacity: i64,          // shannons
+    pub witness_size: i32,             // bytes
+    pub lock_code_hashes: Vec<String>, // distinct hex hashes of the inputs' and outputs' locks
+    pub type_code_hashes: Vec<String>, // distinct hex hashes of the inputs' and outputs' types
+}
+
+impl CommittedTransaction {
+    pub fn insert_query(&self) -> String {
+        format!(
+            "INSERT INTO {}_committed_transaction (network, time, number, index, hash, n_inputs, n_outputs, n_cell_deps, n_header_deps, size, output_capacity, witness_size, lock_code_hashes, type_code_hashes) \
+            VALUES ('{}', '{}', {}, {}, '{}', {}, {}, {}, {}, {}, {}, {}, '{{{}}}', '{{{}}}')",
+            &self.network,
+            &self.network,
+            &self.time,
+            &self.number,
+            &self.index,
+            &self.hash,
+            &self.n_inputs,
+            &self.n_outputs,
+            &self.n_cell_deps,
+            &self.n_header_deps,
+            &self.size,
+            &self.output_capacity,
+            &self.witness_size,
+            self.lock_code_hashes.join(","),
+            self.type_code_hashes.join(","),
+        )
+    }
+
+    /// Delete the transactions of the given committed block, before re-inserting them
+    pub fn delete_query(network: &str, number: i64) -> String {
+        format!(
+            "DELETE FROM {}_committed_transaction WHERE network = '{}' AND number = {}",
+            network, network, number,
+        )
+    }
+
+    /// Delete the transactions since the given committed block, which are rolled back
+    pub fn delete_since_query(network: &str, number: i64) -> String {
+        format!(
+            "DELETE FROM {}_committed_transaction WHERE network = '{}' AND number >= {}",
+            network, network, number,
+        )
+    }
+}
+
+/// ```
+/// CREATE TABLE IF NOT EXISTS $network_block_fee (
+///     network         VARCHAR ( 10 )  NOT NULL,
+///     time            TIMESTAMP       NOT NULL,
+///     number          BIGINT          NOT NULL,
+///     n_transactions  INT             NOT NULL,
+///     total_fee       BIGINT          NOT NULL,
+///     min_fee_rate    BIGINT          NOT NULL,
+///     median_fee_rate BIGINT          NOT NULL,
+///     p90_fee_rate    BIGINT          NOT NULL
+/// );
+///
+/// SELECT create_hypertable('$network_block_fee', 'time');
+/// ```
+#[derive(Clone, Debug)]
+pub struct BlockFee {
+    pub network: String,
+    pub time: chrono::NaiveDateTime,
+    pub number: i64,
+    pub n_transactions: i32, // excluding the cellbase
+    pub total_fee: i64,      // shannons
+    pub min_fee_rate: i64,   // shannons per KB
+    pub median_fee_rate: i64,
+    pub p90_fee_rate: i64,
+}
+
+impl BlockFee {
+    pub fn insert_query(&self) -> String {
+        format!(
+            "INSERT INTO {}_block_fee (network, time, number, n_transactions, total_fee, min_fee_rate, median_fee_rate, p90_fee_rate) \
+            VALUES ('{}', '{}', {}, {}, {}, {}, {}, {})",
+            &self.network,
+            &self.network,
+            &self.time,
+            &self.number,
+            &self.n_transactions,
+            &self.total_fee,
+            &self.min_fee_rate,
+            &self.median_fee_rate,
+            &self.p90_fee_rate,
+        )
+    }
+
+    /// Delete the rows of the given block, before re-inserting them
+    pub fn delete_query(network: &str, number: i64) -> String {
+        format!(
+            "DELETE FROM {}_block_fee WHERE network = '{}' AND number = {}",
+            network, network, number,
+        )
+    }
+
+    /// Delete the rows since the given block, which are rolled back
+    pub fn delete_since_query(network: &str, number: i64) -> String {
+        format!(
+            "DELETE FROM {}_block_fee WHERE network = '{}' AND number >= {}",
+            network, network, number,
+        )
+    }
+}
+
+/// ```
+/// CREATE TABLE IF NOT EXISTS $network_fee_rate_histogram (
+///     network         VARCHAR ( 10 )  NOT NULL,
+///     time            TIMESTAMP       NOT NULL,
+///     number          BIGINT          NOT NULL,
+///     bucket          BIGINT          NOT NULL,
+///     n_transactions  INT             NOT NULL,
+///     size            BIGINT          NOT NULL
+/// );
+///
+/// SELECT create_hypertable('$network_fee_rate_histogram', 'time');
+/// ```
+#[derive(Clone, Debug)]
+pub struct FeeRateHistogram {
+    pub network: String,
+    pub time: chrono::NaiveDateTime,
+    pub number: i64,
+    pub bucket: i64, // the lower bound of the fee rates, 0 or a power of 2, in shannons per KB
+    pub n_transactions: i32,
+    pub size: i64, // the total serialized size of the transactions, in bytes
+}
+
+impl FeeRateHistogram {
+    pub fn insert_query(&self) -> String {
+        format!(
+            "INSERT INTO {}_fee_rate_histogram (network, time, number, bucket, n_transactions, size) \
+            VALUES ('{}', '{}', {}, {}, {}, {})",
+            &self.network,
+            &self.network,
+            &self.time,
+            &self.number,
+            &self.bucket,
+            &self.n_transactions,
+            &self.size,
+        )
+    }
+
+    /// Delete the rows of the given block, before re-inserting them
+    pub fn delete_query(network: &str, number: i64) -> String {
+        format!(
+            "DELETE FROM {}_fee_rate_histogram WHERE network = '{}' AND number = {}",
+            network, network, number,
+        )
+    }
+
+    /// Delete the rows since the given block, which are rolled back
+    pub fn delete_since_query(network: &str, number: i64) -> String {
+        format!(
+            "DELETE FROM {}_fee_rate_histogram WHERE network = '{}' AND number >= {}",
+            network, network, number,
+        )
+    }
+}
+
+/// ```
+/// CREATE TABLE IF NOT EXISTS $network_script_usage (
+///     network         VARCHAR ( 10 )  NOT NULL,
+///     time            TIMESTAMP       NOT NULL,
+///     number          BIGINT          NOT NULL,
+///     script_type     VARCHAR ( 10 )  NOT NULL,
+///     code_hash       CHAR ( 66 )     NOT NULL,
+///     hash_type       VARCHAR ( 10 )  NOT NULL,
+///     name            VARCHAR ( 50 )  NOT NULL,
+///     n_created       INT             NOT NULL,
+///     n_consumed      INT             NOT NULL
+/// );
+///
+/// SELECT create_hypertable('$network_script_usage', 'time');
+///
+/// -- The daily usages
+/// CREATE MATERIALIZED VIEW IF NOT EXISTS $network_script_usage_daily
+/// WITH (timescaledb.continuous) AS
+/// SELECT network, time_bucket('1 day', time) AS day, script_type, code_hash, hash_type, name,
+///     SUM(n_created) AS n_created, SUM(n_consumed) AS n_consumed
+/// FROM $network_script_usage
+/// GROUP BY network, day, script_type, code_hash, hash_type, name;
+/// ```
+#[derive(Clone, Debug)]
+pub struct ScriptUsage {
+    pub network: String,
+    pub time: chrono::NaiveDateTime,
+    pub number: i64,
+    pub script_type: String, // "lock" or "type"
+    pub code_hash: String,
+    pub hash_type: String,
+    pub name: String, // the registered name of the script, empty if unknown
+    pub n_created: i32,
+    pub n_consumed: i32,
+}
+
+impl ScriptUsage {
+    pub fn insert_query(&self) -> String {
+        format!(
+            "INSERT INTO {}_script_usage (network, time, number, script_type, code_hash, hash_type, name, n_created, n_consumed) \
+            VALUES ('{}', '{}', {}, '{}', '{}', '{}', '{}', {}, {})",
+            &self.network,
+            &self.network,
+            &self.time,
+            &self.number,
+            &self.script_type,
+            &self.code_hash,
+            &self.hash_type,
+            &self.name,
+            &self.n_created,
+            &self.n_consumed,
+        )
+    }
+
+    /// Delete the rows of the given block, before re-inserting them
+    pub fn delete_query(network: &str, number: i64) -> String {
+        format!(
+            "DELETE FROM {}_script_usage WHERE network = '{}' AND number = {}",
+            network, network, number,
+        )
+    }
+
+    /// Delete the rows since the given block, which are rolled back
+    pub fn delete_since_query(network: &str, number: i64) -> String {
+        format!(
+            "DELETE FROM {}_script_usage WHERE network = '{}' AND number >= {}",
+            network, network, number,
+        )
+    }
+}
+
+/// ```
+/// CREATE TABLE IF NOT EXISTS $network_dao_state (
+///     network         VARCHAR ( 10 )  NOT NULL,
+///     time            TIMESTAMP       NOT NULL,
+///     number          BIGINT          NOT NULL,
+///     total_issuance                  BIGINT  NOT NULL,
+///     accumulated_rate                BIGINT  NOT NULL,
+///     unissued_secondary_issuance     BIGINT  NOT NULL,
+///     occupied_capacity               BIGINT  NOT NULL
+/// );
+///
+/// SELECT create_hypertable('$network_dao_state', 'time');
+/// ```
+#[derive(Clone, Debug)]
+pub struct DaoState {
+    pub network: String,
+    pub time: chrono::NaiveDateTime,
+    pub number: i64,
+    pub total_issuance: i64,              // C, in shannons
+    pub accumulated_rate: i64,            // AR, 10^16 at the genesis
+    pub unissued_secondary_issuance: i64, // S, in shannons
+    pub occupied_capacity: i64,           // U, in shannons
+}
+
+impl DaoState {
+    pub fn insert_query(&self) -> String {
+        format!(
+            "INSERT INTO {}_dao_state (network, time, number, total_issuance, accumulated_rate, unissued_secondary_issuance, occupied_capacity) \
+            VALUES ('{}', '{}', {}, {}, {}, {}, {})",
+            &self.network,
+            &self.network,
+            &self.time,
+            &self.number,
+            &self.total_issuance,
+            &self.accumulated_rate,
+            &self.unissued_secondary_issuance,
+            &self.occupied_capacity,
+        )
+    }
+
+    /// Delete the row of the given block, before re-inserting it
+    pub fn delete_query(network: &str, number: i64) -> String {
+        format!(
+            "DELETE FROM {}_dao_state WHERE network = '{}' AND number = {}",
+            network, network, number,
+        )
+    }
+
+    /// Delete the rows since the given block, which are rolled back
+    pub fn delete_since_query(network: &str, number: i64) -> String {
+        format!(
+            "DELETE FROM {}_dao_state WHERE network = '{}' AND number >= {}",
+            network, network, number,
+        )
+    }
+}
+
+/// ```
+/// CREATE TABLE IF NOT EXISTS $network_dao_event (
+///     network             VARCHAR ( 10 )  NOT NULL,
+///     time                TIMESTAMP       NOT NULL,
+///     number              BIGINT          NOT NULL,
+///     transaction_hash    CHAR ( 66 )     NOT NULL,
+///     kind                VARCHAR ( 10 )  NOT NULL,
+///     capacity            BIGINT          NOT NULL,
+///     deposit_number      BIGINT          NOT NULL,
+///     withdrawing_number  BIGINT          NOT NULL,
+///     duration            BIGINT          NOT NULL,
+///     compensation        BIGINT          NOT NULL
+/// );
+///
+/// SELECT create_hypertable('$network_dao_event', 'time');
+///
+/// -- The total deposits, since the first analyzed block
+/// SELECT time, SUM(CASE kind WHEN 'deposit' THEN capacity WHEN 'phase2' THEN -capacity ELSE 0 END)
+///     OVER (ORDER BY number) AS total_deposits
+/// FROM $network_dao_event;
+/// ```
+#[derive(Clone, Debug)]
+pub struct DaoEvent {
+    pub network: String,
+    pub time: chrono::NaiveDateTime,
+    pub number: i64,
+    pub transaction_hash: String,
+    pub kind: String,  // "deposit", "phase1" or "phase2"
+    pub capacity: i64, // the capacity of the deposited cell, in shannons
+    pub deposit_number: i64,
+    pub withdrawing_number: i64, // the block of the phase 1 transaction, 0 for deposits
+    // From the deposit block to the withdrawing block, in milliseconds, 0 for deposits
+    pub duration: i64,
+    // The compensation from the deposit block to the withdrawing block, in shannons, 0 for
+    // deposits. Phase 2 claims the compensation computed in phase 1.
+    pub compensation: i64,
+}
+
+impl DaoEvent {
+    pub fn insert_query(&self) -> String {
+        format!(
+            "INSERT INTO {}_dao_event (network, time, number, transaction_hash, kind, capacity, deposit_number, withdrawing_number, duration, compensation) \
+            VALUES ('{}', '{}', {}, '{}', '{}', {}, {}, {}, {}, {})",
+            &self.network,
+            &self.network,
+            &self.time,
+            &self.number,
+            &self.transaction_hash,
+            &self.kind,
+            &self.capacity,
+            &self.deposit_number,
+            &self.withdrawing_number,
+            &self.duration,
+            &self.compensation,
+        )
+    }
+
+    /// Delete the rows of the given block, before re-inserting them
+    pub fn delete_query(network: &str, number: i64) -> String {
+        format!(
+            "DELETE FROM {}_dao_event WHERE network = '{}' AND number = {}",
+            network, network, number,
+        )
+    }
+
+    /// Delete the rows since the given block, which are rolled back
+    pub fn delete_since_query(network: &str, number: i64) -> String {
+        format!(
+            "DELETE FROM {}_dao_event WHERE network = '{}' AND number >= {}",
+            network, network, number,
+        )
+    }
+}
+
+/// ```
+/// CREATE TABLE IF NOT EXISTS $network_block_reward (
+///     network             VARCHAR ( 10 )  NOT NULL,
+///     time                TIMESTAMP       NOT NULL,
+///     number              BIGINT          NOT NULL,
+///     epoch               BIGINT          NOT NULL,
+///     finalized_number    BIGINT          NOT NULL,
+///     primary_issuance    BIGINT          NOT NULL,
+///     secondary_issuance  BIGINT          NOT NULL,
+///     expected_primary_issuance   BIGINT  NOT NULL,
+///     expected_secondary_issuance BIGINT  NOT NULL,
+///     primary_reward      BIGINT          NOT NULL,
+///     secondary_reward    BIGINT          NOT NULL,
+///     commit_reward       BIGINT          NOT NULL,
+///     proposal_reward     BIGINT          NOT NULL,
+///     total_fee           BIGINT          NOT NULL
+/// );
+///
+/// SELECT create_hypertable('$network_block_reward', 'time');
+///
+/// -- The issuance per epoch, to reconcile with the emission schedule
+/// CREATE VIEW $network_epoch_issuance AS
+/// SELECT network, epoch, MIN(time) AS time, COUNT(*) AS n_blocks,
+///     SUM(primary_issuance) AS primary_issuance,
+///     SUM(secondary_issuance) AS secondary_issuance,
+///     SUM(expected_primary_issuance) AS expected_primary_issuance,
+///     SUM(expected_secondary_issuance) AS expected_secondary_issuance,
+///     SUM(primary_reward + secondary_reward + commit_reward + proposal_reward) AS miner_reward,
+///     SUM(total_fee) AS total_fee
+/// FROM $network_block_reward
+/// GROUP BY network, epoch;
+/// ```
+#[derive(Clone, Debug)]
+pub struct BlockReward {
+    pub network: String,
+    pub time: chrono::NaiveDateTime,
+    pub number: i64,
+    pub epoch: i64,
+    pub finalized_number: i64, // the block whose cellbase pays the reward
+    pub primary_issuance: i64,
+    pub secondary_issuance: i64,
+    // The issuance of the consensus emission schedule
+    pub expected_primary_issuance: i64,
+    pub expected_secondary_issuance: i64,
+    pub primary_reward: i64,
+    pub secondary_reward: i64,
+    pub commit_reward: i64,
+    pub proposal_reward: i64,
+    pub total_fee: i64,
+}
+
+impl BlockReward {
+    pub fn insert_query(&self) -> String {
+        format!(
+            "INSERT INTO {}_block_reward (network, time, number, epoch, finalized_number, primary_issuance, secondary_issuance, expected_primary_issuance, expected_secondary_issuance, primary_reward, secondary_reward, commit_reward, proposal_reward, total_fee) \
+            VALUES ('{}', '{}', {}, {}, {}, {}, {}, {}, {}, {}, {}, {}, {}, {})",
+            &self.network,
+            &self.network,
+            &self.time,
+            &self.number,
+            &self.epoch,
+            &self.finalized_number,
+            &self.primary_issuance,
+            &self.secondary_issuance,
+            &self.expected_primary_issuance,
+            &self.expected_secondary_issuance,
+            &self.primary_reward,
+            &self.secondary_reward,
+            &self.commit_reward,
+            &self.proposal_reward,
+            &self.total_fee,
+        )
+    }
+
+    /// Delete the row of the given block, before re-inserting it
+    pub fn delete_query(network: &str, number: i64) -> String {
+        format!(
+            "DELETE FROM {}_block_reward WHERE network = '{}' AND number = {}",
+            network, network, number,
+        )
+    }
+
+    /// Delete the rows finalized since the given block, which are rolled back
+    pub fn delete_since_query(network: &str, finalized_number: i64) -> String {
+        format!(
+            "DELETE FROM {}_block_reward WHERE network = '{}' AND finalized_number >= {}",
+            network, network, finalized_number,
+        )
+    }
+}
+
+/// ```
+/// CREATE TABLE IF NOT EXISTS $network_chain_state (
+///     network             VARCHAR ( 10 )  NOT NULL,
+///     time                TIMESTAMP       NOT NULL,
+///     number              BIGINT          NOT NULL,
+///     n_created_cells     INT             NOT NULL,
+///     n_consumed_cells    INT             NOT NULL,
+///     live_cells          BIGINT          NOT NULL,
+///     capacity            BIGINT          NOT NULL,
+///     occupied_capacity   BIGINT          NOT NULL,
+///     free_capacity       BIGINT          NOT NULL
+/// );
+///
+/// SELECT create_hypertable('$network_chain_state', 'time');
+/// ```
+#[derive(Clone, Debug)]
+pub struct ChainState {
+    pub network: String,
+    pub time: chrono::NaiveDateTime,
+    pub number: i64,
+    pub n_created_cells: i32,
+    pub n_consumed_cells: i32,
+    // The live cells after the block
+    pub live_cells: i64,
+    pub capacity: i64,          // the total capacity of the live cells, in shannons
+    pub occupied_capacity: i64, // in shannons
+    pub free_capacity: i64,     // in shannons
+}
+
+impl ChainState {
+    pub fn insert_query(&self) -> String {
+        format!(
+            "INSERT INTO {}_chain_state (network, time, number, n_created_cells, n_consumed_cells, live_cells, capacity, occupied_capacity, free_capacity) \
+            VALUES ('{}', '{}', {}, {}, {}, {}, {}, {}, {})",
+            &self.network,
+            &self.network,
+            &self.time,
+            &self.number,
+            &self.n_created_cells,
+            &self.n_consumed_cells,
+            &self.live_cells,
+            &self.capacity,
+            &self.occupied_capacity,
+            &self.free_capacity,
+        )
+    }
+
+    /// Delete the row of the given block, before re-inserting it
+    pub fn delete_query(network: &str, number: i64) -> String {
+        format!(
+            "DELETE FROM {}_chain_state WHERE network = '{}' AND number = {}",
+            network, network, number,
+        )
+    }
+
+    /// Delete the rows since the given block, which are rolled back
+    pub fn delete_since_query(network: &str, number: i64) -> String {
+        format!(
+            "DELETE FROM {}_chain_state WHERE network = '{}' AND number >= {}",
+            network, network, number,
+        )
+    }
+}
+
+/// ```
+/// CREATE TABLE IF NOT EXISTS $network_chain_state_reconciliation (
+///     network             VARCHAR ( 10 )  NOT NULL,
+///     time                TIMESTAMP       NOT NULL,
+///     number              BIGINT          NOT NULL,
+///     code_hash           CHAR ( 66 )     NOT NULL,
+///     hash_type           VARCHAR ( 10 )  NOT NULL,
+///     name                VARCHAR ( 50 )  NOT NULL,
+///     capacity            BIGINT          NOT NULL,
+///     indexer_capacity    BIGINT          NOT NULL,
+///     drift               BIGINT          NOT NULL
+/// );
+///
+/// SELECT create_hypertable('$network_chain_state_reconciliation', 'time');
+/// ```
+#[derive(Clone, Debug)]
+pub struct ChainStateReconciliation {
+    pub network: String,
+    pub time: chrono::NaiveDateTime,
+    pub number: i64,
+    // The lock script of the live cells
+    pub code_hash: String,
+    pub hash_type: String,
+    pub name: String,
+    pub capacity: i64, // computed incrementally since the previous reconciliation
+    pub indexer_capacity: i64, // read from the indexer
+    pub drift: i64,    // indexer_capacity - capacity
+}
+
+impl ChainStateReconciliation {
+    pub fn insert_query(&self) -> String {
+        format!(
+            "INSERT INTO {}_chain_state_reconciliation (network, time, number, code_hash, hash_type, name, capacity, indexer_capacity, drift) \
+            VALUES ('{}', '{}', {}, '{}', '{}', '{}', {}, {}, {})",
+            &self.network,
+            &self.network,
+            &self.time,
+            &self.number,
+            &self.code_hash,
+            &self.hash_type,
+            &self.name,
+            &self.capacity,
+            &self.indexer_capacity,
+            &self.drift,
+        )
+    }
+
+    /// Delete the rows of the given block, before re-inserting them
+    pub fn delete_query(network: &str, number: i64) -> String {
+        format!(
+            "DELETE FROM {}_chain_state_reconciliation WHERE network = '{}' AND number = {}",
+            network, network, number,
+        )
+    }
+
+    /// Delete the rows since the given block, which are rolled back
+    pub fn delete_since_query(network: &str, number: i64) -> String {
+        format!(
+            "DELETE FROM {}_chain_state_reconciliation WHERE network = '{}' AND number >= {}",
+            network, network, number,
+        )
+    }
 }
 
 /// ```
@@ -136,10 +922,27 @@ impl TwoPCCommitment {
 ///     number      BIGINT          NOT NULL,
 ///     length      INT             NOT NULL,
 ///     duration    INT             NOT NULL,
-///     n_uncles    INT             NOT NULL
+///     n_uncles    INT             NOT NULL,
+///     compact_target      BIGINT              NOT NULL,
+///     difficulty          NUMERIC ( 78, 0 )   NOT NULL,
+///     difficulty_change   DOUBLE PRECISION    NOT NULL,
+///     target_duration     BIGINT              NOT NULL,
+///     adjusted_length     INT                 NOT NULL,
+///     uncle_rate_target   DOUBLE PRECISION    NOT NULL
 /// );
 ///
 /// SELECT create_hypertable('$network_epoch', 'time');
+///
+/// -- Upgrade an existing table, then re-derive the rows via `ckb-analyzer backfill --tables epoch`
+/// ALTER TABLE $network_epoch
+///     ADD COLUMN IF NOT EXISTS compact_target     BIGINT              NOT NULL DEFAULT 0,
+///     ADD COLUMN IF NOT EXISTS difficulty         NUMERIC ( 78, 0 )   NOT NULL DEFAULT 0,
+///     ADD COLUMN IF NOT EXISTS difficulty_change  DOUBLE PRECISION    NOT NULL DEFAULT 0,
+///     ADD COLUMN IF NOT EXISTS target_duration    BIGINT              NOT NULL DEFAULT 0,
+///     ADD COLUMN IF NOT EXISTS adjusted_length    INT                 NOT NULL DEFAULT 0,
+///     ADD COLUMN IF NOT EXISTS uncle_rate_target  DOUBLE PRECISION    NOT NULL DEFAULT 0,
+///     -- The per-epoch hash rate is in the `hash_rate` table
+///     DROP COLUMN IF EXISTS hash_rate;
 /// ```
 #[derive(Clone, Debug)]
 pub struct Epoch {
@@ -149,13 +952,19 @@ pub struct Epoch {
     pub length: i32,
     pub duration: i32, // ms
     pub n_uncles: i32,
+    pub compact_target: i64,
+    pub difficulty: String,     // decimal
+    pub difficulty_change: f64, // ratio to the previous epoch's difficulty
+    pub target_duration: i64,   // ms
+    pub adjusted_length: i32,   // the next epoch's length
+    pub uncle_rate_target: f64, // the consensus orphan rate target
 }
 
 impl Epoch {
     pub fn insert_query(&self) -> String {
         format!(
-            "INSERT INTO {}_epoch (network, time, number, length, duration, n_uncles) \
-            VALUES ('{}', '{}', {}, {}, {}, {})",
+            "INSERT INTO {}_epoch (network, time, number, length, duration, n_uncles, compact_target, difficulty, difficulty_change, target_duration, adjusted_length, uncle_rate_target) \
+            VALUES ('{}', '{}', {}, {}, {}, {}, {}, {}, {}, {}, {}, {})",
             &self.network,
             &self.network,
             &self.time,
@@ -163,6 +972,227 @@ impl Epoch {
             &self.length,
             &self.duration,
             &self.n_uncles,
+            &self.compact_target,
+            &self.difficulty,
+            &self.difficulty_change,
+            &self.target_duration,
+            &self.adjusted_length,
+            &self.uncle_rate_target,
+        )
+    }
+
+    /// Delete the rows of the same epoch, before re-inserting it
+    pub fn delete_query(&self) -> String {
+        format!(
+            "DELETE FROM {}_epoch WHERE network = '{}' AND number = {}",
+            &self.network, &self.network, &self.number,
+        )
+    }
+
+    /// Delete the rows since the given epoch, whose ends are rolled back
+    pub fn delete_since_query(network: &str, number: i64) -> String {
+        format!(
+            "DELETE FROM {}_epoch WHERE network = '{}' AND number >= {}",
+            network, network, number,
+        )
+    }
+}
+
+/// ```
+/// CREATE TABLE IF NOT EXISTS $network_hash_rate (
+///     network     VARCHAR ( 10 )      NOT NULL,
+///     time        TIMESTAMP           NOT NULL,
+///     number      BIGINT              NOT NULL,
+///     epoch       BIGINT              NOT NULL,
+///     window_size INT                 NOT NULL,
+///     per_epoch   BOOLEAN             NOT NULL,
+///     duration    BIGINT              NOT NULL,
+///     hash_rate   DOUBLE PRECISION    NOT NULL
+/// );
+///
+/// SELECT create_hypertable('$network_hash_rate', 'time');
+/// ```
+#[derive(Clone, Debug)]
+pub struct HashRate {
+    pub network: String,
+    pub time: chrono::NaiveDateTime, // the last block timestamp of the window
+    pub number: i64,                 // the last block number of the window
+    pub epoch: i64,
+    pub window_size: i32, // blocks, the epoch length for the per-epoch rows
+    pub per_epoch: bool,
+    pub duration: i64,  // ms
+    pub hash_rate: f64, // hashes per second
+}
+
+impl HashRate {
+    pub fn insert_query(&self) -> String {
+        format!(
+            "INSERT INTO {}_hash_rate (network, time, number, epoch, window_size, per_epoch, duration, hash_rate) \
+            VALUES ('{}', '{}', {}, {}, {}, {}, {}, {})",
+            &self.network,
+            &self.network,
+            &self.time,
+            &self.number,
+            &self.epoch,
+            &self.window_size,
+            &self.per_epoch,
+            &self.duration,
+            &self.hash_rate,
+        )
+    }
+
+    /// Delete the rows of the same window, before re-inserting it
+    pub fn delete_query(&self) -> String {
+        format!(
+            "DELETE FROM {}_hash_rate WHERE network = '{}' AND number = {} AND window_size = {} AND per_epoch = {}",
+            &self.network, &self.network, &self.number, &self.window_size, &self.per_epoch,
+        )
+    }
+
+    /// Delete the sliding window rows since the given block number, and the per-epoch rows since
+    /// the given epoch, which are rolled back
+    pub fn delete_since_query(network: &str, number: i64, epoch: i64) -> String {
+        format!(
+            "DELETE FROM {}_hash_rate WHERE network = '{}' AND (number >= {} OR (per_epoch AND epoch >= {}))",
+            network, network, number, epoch,
+        )
+    }
+}
+
+/// ```
+/// CREATE TABLE IF NOT EXISTS $network_miner_share (
+///     network     VARCHAR ( 10 )      NOT NULL,
+///     time        TIMESTAMP           NOT NULL,
+///     start_time  TIMESTAMP           NOT NULL,
+///     period      VARCHAR ( 10 )      NOT NULL,
+///     number      BIGINT              NOT NULL,
+///     miner       VARCHAR ( 200 )     NOT NULL,
+///     n_blocks    INT                 NOT NULL,
+///     n_uncles    INT                 NOT NULL,
+///     share       DOUBLE PRECISION    NOT NULL
+/// );
+///
+/// SELECT create_hypertable('$network_miner_share', 'time');
+/// ```
+#[derive(Clone, Debug)]
+pub struct MinerShare {
+    pub network: String,
+    pub time: chrono::NaiveDateTime, // the last block timestamp of the window
+    pub start_time: chrono::NaiveDateTime, // the first block timestamp of the window
+    pub period: String,              // "day" or "epoch"
+    pub number: i64,                 // the last block number of the window
+    pub miner: String,
+    pub n_blocks: i32,
+    pub n_uncles: i32,
+    pub share: f64, // of the blocks and uncles of the window
+}
+
+impl MinerShare {
+    pub fn insert_query(&self) -> String {
+        format!(
+            "INSERT INTO {}_miner_share (network, time, start_time, period, number, miner, n_blocks, n_uncles, share) \
+            VALUES ('{}', '{}', '{}', '{}', {}, '{}', {}, {}, {})",
+            &self.network,
+            &self.network,
+            &self.time,
+            &self.start_time,
+            &self.period,
+            &self.number,
+            &self.miner,
+            &self.n_blocks,
+            &self.n_uncles,
+            &self.share,
+        )
+    }
+
+    /// Delete the rows of the same window, before re-inserting it
+    pub fn delete_window_query(network: &str, period: &str, number: i64) -> String {
+        format!(
+            "DELETE FROM {}_miner_share WHERE network = '{}' AND period = '{}' AND number = {}",
+            network, network, period, number,
+        )
+    }
+
+    /// Delete the rows of the windows ended since the given block number, which are rolled back
+    pub fn delete_since_query(network: &str, number: i64) -> String {
+        format!(
+            "DELETE FROM {}_miner_share WHERE network = '{}' AND number >= {}",
+            network, network, number,
+        )
+    }
+}
+
+/// ```
+/// CREATE TABLE IF NOT EXISTS $network_miner_concentration (
+///     network                 VARCHAR ( 10 )      NOT NULL,
+///     time                    TIMESTAMP           NOT NULL,
+///     start_time              TIMESTAMP           NOT NULL,
+///     period                  VARCHAR ( 10 )      NOT NULL,
+///     number                  BIGINT              NOT NULL,
+///     n_blocks                INT                 NOT NULL,
+///     n_uncles                INT                 NOT NULL,
+///     n_miners                INT                 NOT NULL,
+///     hhi                     DOUBLE PRECISION    NOT NULL,
+///     nakamoto_coefficient    INT                 NOT NULL,
+///     top_miner               VARCHAR ( 200 )     NOT NULL,
+///     top_share               DOUBLE PRECISION    NOT NULL,
+///     alert                   BOOLEAN             NOT NULL
+/// );
+///
+/// SELECT create_hypertable('$network_miner_concentration', 'time');
+/// ```
+#[derive(Clone, Debug)]
+pub struct MinerConcentration {
+    pub network: String,
+    pub time: chrono::NaiveDateTime, // the last block timestamp of the window
+    pub start_time: chrono::NaiveDateTime, // the first block timestamp of the window
+    pub period: String,              // "day" or "epoch"
+    pub number: i64,                 // the last block number of the window
+    pub n_blocks: i32,
+    pub n_uncles: i32,
+    pub n_miners: i32,
+    pub hhi: f64,                  // the sum of the squared shares, within (0, 1]
+    pub nakamoto_coefficient: i32, // the fewest miners whose shares sum over a half
+    pub top_miner: String,
+    pub top_share: f64,
+    pub alert: bool, // whether the top share exceeds the alert threshold
+}
+
+impl MinerConcentration {
+    pub fn insert_query(&self) -> String {
+        format!(
+            "INSERT INTO {}_miner_concentration (network, time, start_time, period, number, n_blocks, n_uncles, n_miners, hhi, nakamoto_coefficient, top_miner, top_share, alert) \
+            VALUES ('{}', '{}', '{}', '{}', {}, {}, {}, {}, {}, {}, '{}', {}, {})",
+            &self.network,
+            &self.network,
+            &self.time,
+            &self.start_time,
+            &self.period,
+            &self.number,
+            &self.n_blocks,
+            &self.n_uncles,
+            &self.n_miners,
+            &self.hhi,
+            &self.nakamoto_coefficient,
+            &self.top_miner,
+            &self.top_share,
+            &self.alert,
+        )
+    }
+
+    /// Delete the rows of the same window, before re-inserting it
+    pub fn delete_query(&self) -> String {
+        format!(
+            "DELETE FROM {}_miner_concentration WHERE network = '{}' AND period = '{}' AND number = {}",
+            &self.network, &self.network, &self.period, &self.number,
+        )
+    }
+
+    /// Delete the rows of the windows ended since the given block number, which are rolled back
+    pub fn delete_since_query(network: &str, number: i64) -> String {
+        format!(
+            "DELETE FROM {}_miner_concentration WHERE network = '{}' AND number >= {}",
+            network, network, number,
         )
     }
 }
@@ -427,6 +1457,47 @@ impl SubscribeProposedTransaction {
     }
 }
 
+/// ```
+/// CREATE TABLE IF NOT EXISTS $network_rpc_cache (
+///     network             VARCHAR ( 10 )  NOT NULL,
+///     time                TIMESTAMP       NOT NULL,
+///     cache               VARCHAR ( 20 )  NOT NULL,
+///     hits                BIGINT          NOT NULL,
+///     misses              BIGINT          NOT NULL,
+///     len                 INT             NOT NULL,
+///     capacity            INT             NOT NULL
+/// );
+///
+/// SELECT create_hypertable('$network_rpc_cache', 'time');
+/// ```
+#[derive(Clone, Debug)]
+pub struct RpcCache {
+    pub network: String,
+    pub time: chrono::NaiveDateTime,
+    pub cache: String, // "blocks" | "headers" | "headers_by_number"
+    pub hits: i64,     // accumulated since the analyzer started
+    pub misses: i64,   // accumulated since the analyzer started
+    pub len: i32,
+    pub capacity: i32,
+}
+
+impl RpcCache {
+    pub fn insert_query(&self) -> String {
+        format!(
+            "INSERT INTO {}_rpc_cache(network, time, cache, hits, misses, len, capacity)\
+            VALUES ('{}', '{}', '{}', {}, {}, {}, {})",
+            &self.network,
+            &self.network,
+            &self.time,
+            &self.cache,
+            &self.hits,
+            &self.misses,
+            &self.len,
+            &self.capacity,
+        )
+    }
+}
+
 // /// # Create trigger
 // /// ```sql
 // /// CREATE OR REPLACE FUNCTION label_nth_propagation() RETURNS trigger AS $$
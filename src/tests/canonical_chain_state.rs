@@ -0,0 +1,1170 @@
+use crate::config::{MinerShareWindow, ScriptConfig};
+use crate::dao::NERVOS_DAO_CODE_HASH;
+use crate::mock_node::{
+    memory_sink, transaction, MockChain, MockNode, BLOCK_INTERVAL, COMPACT_TARGET,
+};
+use crate::pool::PoolLabels;
+use crate::topic::{AnalyzedTable, Backfill, CanonicalChainState};
+use ckb_suite_rpc::AsyncJsonrpc;
+use ckb_types::bytes::Bytes;
+use ckb_types::core::{ScriptHashType, TransactionBuilder, TransactionView};
+use ckb_types::packed::{Byte, Byte32, CellInput, CellOutput, CellbaseWitness, OutPoint, Script};
+use ckb_types::prelude::*;
+use ckb_types::utils::compact_to_difficulty;
+use ckb_types::H256;
+use std::time::Duration;
+
+#[tokio::test(flavor = "multi_thread")]
+async fn analyze_blocks_uncles_commitments_and_epochs() {
+    // Epoch 0 is blocks [0, 10)
+    let mut chain = MockChain::new(10);
+    let tx = transaction(1);
+    chain.mine();
+    chain.mine();
+    chain.mine_with(|builder| builder.proposal(tx.proposal_short_id())); // #3
+    let parent = chain.tip();
+    let uncle = chain.fork_with(&parent, |builder| builder); // #4 uncle
+    chain.mine(); // #4
+    chain.mine_with(|builder| builder.uncle(uncle.as_uncle())); // #5
+    chain.mine_with(|builder| builder.transaction(tx.clone())); // #6
+    while chain.tip().number() < 30 {
+        chain.mine();
+    }
+
+    let node = MockNode::start(chain).await;
+    let (query_sender, mut sink) = memory_sink();
+    let jsonrpc = AsyncJsonrpc::connect(&[node.config().rpc_url()]);
+    let mut config = node.config();
+    config.hash_rate_windows = vec![5];
+    config.miner_share_windows = vec![MinerShareWindow::Epoch];
+    let pool_labels = pool_labels_file(
+        "analyze_blocks",
+        r#"
+        [[pools]]
+        name = "MessagePool"
+        message_patterns = ["^mock$"]
+
+        [[pools]]
+        name = "ArgsPool"
+        lock_args = ["0x0202020202020202020202020202020202020202"]
+        "#,
+    );
+    config.pool_labels = Some(pool_labels.path());
+    let mut handler = CanonicalChainState::new(config, jsonrpc, query_sender, 0);
+    tokio::spawn(async move { handler.run().await });
+
+    // Blocks are analyzed up to 11 blocks below the tip
+    sink.wait_until(|sink| {
+        sink.inserts("block").len() >= 19 && sink.inserts("committed_transaction").len() >= 20
+    })
+    .await;
+
+    let blocks = sink.inserts("block");
+    let numbers = blocks.iter().map(|row| row[2].clone()).collect::<Vec<_>>();
+    let expected = (1..=19)
+        .map(|number| number.to_string())
+        .collect::<Vec<_>>();
+    assert_eq!(numbers, expected);
+    assert!(blocks
+        .iter()
+        .all(|row| row[3] == BLOCK_INTERVAL.to_string()));
+    assert_eq!(blocks[4][6], "1", "#5 has 1 uncle");
+    assert!(
+        blocks.iter().all(|row| row[10] == "ArgsPool"),
+        "lock args take precedence over message patterns"
+    );
+    // The mock chain is not mainnet, and the mock miner lock is not a default lock
+    assert_eq!(
+        blocks[0][11..15],
+        [
+            "ckt1qsqszqgpqyqszqgpqyqszqgpqyqszqgpqyqszqgpqyqszqgpqyqszqszqgpqyqszqgpqyqszqgpqyqszqgpqyvq93fy".to_string(),
+            format!("0x{}", "01".repeat(32)),
+            "type".to_string(),
+            format!("0x{}", "02".repeat(20)),
+        ]
+    );
+
+    let uncles = sink.inserts("uncle");
+    assert_eq!(uncles.len(), 1);
+    assert_eq!(uncles[0][2], "4");
+    assert_eq!(uncles[0][3], "0", "lag to canonical");
+    assert_eq!(uncles[0][6], format!("{:#x}", uncle.hash()));
+    assert_eq!(uncles[0][9], "ArgsPool");
+    assert_eq!(uncles[0][10], blocks[0][11]);
+
+    let transactions = sink.inserts("committed_transaction");
+    assert_eq!(transactions.len(), 20, "19 cellbases and tx");
+    let committed = transactions
+        .iter()
+        .find(|row| row[4] == format!("{:#x}", tx.hash()))
+        .unwrap();
+    assert_eq!(
+        committed[2..4],
+        ["6".to_string(), "1".to_string()],
+        "number and index"
+    );
+    assert_eq!(
+        committed[5..12],
+        [
+            "0",
+            "1",
+            "0",
+            "0",
+            tx.data().serialized_size_in_block().to_string().as_str(),
+            "1",
+            "0"
+        ]
+    );
+    assert_eq!(committed[12], format!("{{0x{}}}", "01".repeat(32)));
+    assert_eq!(committed[13], "{}");
+
+    let commitments = sink.inserts("two_pc_commitment");
+    assert_eq!(commitments.len(), 1);
+    assert_eq!(commitments[0][2..], ["6".to_string(), "3".to_string()]);
+
+    // Epoch 1 is ended by #20, which is analyzed once #31 is mined
+    node.update_chain(|chain| {
+        chain.mine();
+    });
+    sink.wait_until(|sink| {
+        sink.inserts("block").len() >= 20
+            && sink.inserts("epoch").len() >= 2
+            && sink.inserts("hash_rate").len() >= 17
+            && !sink.inserts("miner_concentration").is_empty()
+    })
+    .await;
+
+    let epochs = sink.inserts("epoch");
+    assert_eq!(epochs.len(), 2);
+    let duration = 9 * BLOCK_INTERVAL;
+    let difficulty = compact_to_difficulty(COMPACT_TARGET);
+    assert_eq!(
+        epochs[0][2..10],
+        [
+            "0".to_string(),
+            "10".to_string(),
+            duration.to_string(),
+            "1".to_string(),
+            COMPACT_TARGET.to_string(),
+            difficulty.to_string(),
+            "1".to_string(), // difficulty_change
+            "14400000".to_string(),
+        ]
+    );
+    assert_eq!(epochs[0][10], "10", "adjusted length");
+    assert_eq!(epochs[0][11], "0.025", "uncle rate target");
+    assert_eq!(epochs[0].len(), 12);
+
+    let hash_rates = sink.inserts("hash_rate");
+    let (per_epoch, windows): (Vec<_>, Vec<_>) =
+        hash_rates.iter().partition(|row| row[5] == "true");
+    let numbers = windows.iter().map(|row| row[2].clone()).collect::<Vec<_>>();
+    let expected = (6..=20)
+        .map(|number| number.to_string())
+        .collect::<Vec<_>>();
+    assert_eq!(numbers, expected, "the first window is [#2, #6] since #1");
+    assert_eq!(windows[0][4], "5");
+    assert_eq!(windows[0][6], (5 * BLOCK_INTERVAL).to_string());
+    let difficulty = difficulty.to_string().parse::<f64>().unwrap();
+    let hash_rate = windows[0][7].parse::<f64>().unwrap();
+    let expected = difficulty * 6.0 * 1000.0 / (5 * BLOCK_INTERVAL) as f64; // the uncle of #5
+    assert!((hash_rate - expected).abs() / expected < 1e-9);
+
+    assert_eq!(per_epoch.len(), 2);
+    assert_eq!(per_epoch[0][2..6], ["9", "0", "10", "true"]);
+    let hash_rate = per_epoch[0][7].parse::<f64>().unwrap();
+    let expected = difficulty * 11.0 * 1000.0 / duration as f64; // the uncle of #5
+    assert!((hash_rate - expected).abs() / expected < 1e-9);
+
+    // Epoch 0 is partial since the analysis starts within it
+    let miner = format!("{:#x}", MockChain::miner_lock().args());
+    let concentrations = sink.inserts("miner_concentration");
+    assert_eq!(concentrations.len(), 1);
+    assert_eq!(
+        concentrations[0][3..],
+        [
+            "epoch".to_string(),
+            "19".to_string(),
+            "10".to_string(),
+            "0".to_string(),
+            "1".to_string(),
+            "1".to_string(), // hhi
+            "1".to_string(), // nakamoto coefficient
+            miner.clone(),
+            "1".to_string(),
+            "true".to_string(), // over the alert threshold
+        ]
+    );
+    let shares = sink.inserts("miner_share");
+    assert_eq!(shares.len(), 1);
+    assert_eq!(
+        shares[0][3..],
+        [
+            "epoch".to_string(),
+            "19".to_string(),
+            miner,
+            "10".to_string(),
+            "0".to_string(),
+            "1".to_string(),
+        ]
+    );
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn warm_up_hash_rate_windows_on_start() {
+    let mut chain = MockChain::new(1000);
+    while chain.tip().number() < 20 {
+        chain.mine();
+    }
+
+    let node = MockNode::start(chain).await;
+    let (query_sender, mut sink) = memory_sink();
+    let jsonrpc = AsyncJsonrpc::connect(&[node.config().rpc_url()]);
+    let mut config = node.config();
+    config.canonical_chain_lag = 0;
+    config.hash_rate_windows = vec![5];
+    // Resume after #10
+    let mut handler = CanonicalChainState::new(config, jsonrpc, query_sender, 10);
+    tokio::spawn(async move { handler.run().await });
+    sink.wait_until(|sink| sink.inserts("hash_rate").len() >= 10)
+        .await;
+
+    // The window of #11 is [#6, #11], warmed up by the blocks before the analysis
+    let numbers = sink
+        .inserts("hash_rate")
+        .iter()
+        .map(|row| row[2].clone())
+        .collect::<Vec<_>>();
+    let expected = (11..=20)
+        .map(|number| number.to_string())
+        .collect::<Vec<_>>();
+    assert_eq!(numbers, expected);
+    assert_eq!(sink.inserts("block")[0][2], "11");
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn backfill_replaces_rows_of_the_range() {
+    // Epoch 0 is blocks [0, 10)
+    let mut chain = MockChain::new(10);
+    while chain.tip().number() < 30 {
+        chain.mine();
+    }
+
+    let node = MockNode::start(chain).await;
+    let (query_sender, mut sink) = memory_sink();
+    let jsonrpc = AsyncJsonrpc::connect(&[node.config().rpc_url()]);
+    let backfill = Backfill {
+        from: 8,
+        to: 12,
+        tables: vec![AnalyzedTable::Block, AnalyzedTable::Epoch]
+            .into_iter()
+            .collect(),
+    };
+    let mut handler =
+        CanonicalChainState::new(node.config(), jsonrpc, query_sender, 0).with_backfill(backfill);
+    handler.run().await;
+    sink.wait_until(|_| true).await;
+
+    let numbers = sink
+        .inserts("block")
+        .iter()
+        .map(|row| row[2].clone())
+        .collect::<Vec<_>>();
+    let expected = (8..=12)
+        .map(|number| number.to_string())
+        .collect::<Vec<_>>();
+    assert_eq!(numbers, expected);
+    for number in 8..=12 {
+        let delete = format!(
+            "DELETE FROM mock_block WHERE network = 'mock' AND number = {}",
+            number
+        );
+        let position = |query: &str| sink.queries().iter().position(|q| q == query);
+        let insert = sink
+            .queries()
+            .iter()
+            .position(|query| {
+                query.starts_with("INSERT INTO mock_block")
+                    && query.contains(&format!(", {}, ", number))
+            })
+            .unwrap();
+        assert!(
+            position(&delete).unwrap() < insert,
+            "#{} is deleted before inserted",
+            number
+        );
+    }
+
+    // Epoch 0 ends at #9, within the range
+    let epochs = sink.inserts("epoch");
+    assert_eq!(epochs.len(), 1);
+    assert_eq!(epochs[0][2], "0");
+    assert!(sink
+        .queries()
+        .iter()
+        .any(|query| query == "DELETE FROM mock_epoch WHERE network = 'mock' AND number = 0"));
+
+    // The tables not chosen are untouched
+    assert!(sink.inserts("uncle").is_empty());
+    assert!(sink.inserts("two_pc_commitment").is_empty());
+    assert!(!sink
+        .queries()
+        .iter()
+        .any(|query| query.contains("two_pc_commitment")));
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn backfill_epochs_ended_within_the_range() {
+    // Epoch 0 is blocks [0, 10), and epoch 1 [10, 20)
+    let mut chain = MockChain::new(10);
+    while chain.tip().number() < 31 {
+        chain.mine();
+    }
+
+    let node = MockNode::start(chain).await;
+    let (query_sender, mut sink) = memory_sink();
+    let jsonrpc = AsyncJsonrpc::connect(&[node.config().rpc_url()]);
+    let backfill = Backfill {
+        from: 10,
+        to: 19,
+        tables: vec![AnalyzedTable::Epoch, AnalyzedTable::HashRate]
+            .into_iter()
+            .collect(),
+    };
+    let mut handler =
+        CanonicalChainState::new(node.config(), jsonrpc, query_sender, 0).with_backfill(backfill);
+    handler.run().await;
+    sink.wait_until(|_| true).await;
+
+    // Epoch 1 ends at #19, the end of the range, and epoch 0 before the range
+    let epochs = sink
+        .inserts("epoch")
+        .iter()
+        .map(|row| row[2].clone())
+        .collect::<Vec<_>>();
+    assert_eq!(epochs, ["1"]);
+    let per_epoch_hash_rates = sink
+        .inserts("hash_rate")
+        .iter()
+        .filter(|row| row[5] == "true")
+        .map(|row| row[2..4].to_vec())
+        .collect::<Vec<_>>();
+    assert_eq!(per_epoch_hash_rates, [["19", "1"]]);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn fetch_batches_concurrently_ahead_of_the_analysis() {
+    // 5 batches of 200 blocks
+    let mut chain = MockChain::new(1000);
+    while chain.tip().number() < 1001 {
+        chain.mine();
+    }
+
+    let node = MockNode::start(chain).await;
+    node.set_latency(Duration::from_millis(100));
+    let (query_sender, mut sink) = memory_sink();
+    let mut config = node.config();
+    config.canonical_chain_lag = 0;
+    let concurrency = config.backfill_concurrency;
+    let jsonrpc = AsyncJsonrpc::connect(&[config.rpc_url()]);
+    let backfill = Backfill {
+        from: 1,
+        to: 1000,
+        tables: vec![AnalyzedTable::Block].into_iter().collect(),
+    };
+    let mut handler =
+        CanonicalChainState::new(config, jsonrpc, query_sender, 0).with_backfill(backfill);
+    handler.run().await;
+    sink.wait_until(|_| true).await;
+    assert_eq!(sink.inserts("block").len(), 1000);
+
+    // Bounded by the batches queued for the analysis, plus the one being analyzed
+    let fetching = node.max_concurrent_requests("get_block_by_number");
+    assert!(
+        (2..=concurrency + 1).contains(&fetching),
+        "{} batches fetched at the same time",
+        fetching
+    );
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn roll_back_reorganized_blocks() {
+    let mut chain = MockChain::new(1000);
+    let old_branch = (1..=5).map(|_| chain.mine()).collect::<Vec<_>>();
+
+    let node = MockNode::start(chain).await;
+    let (query_sender, mut sink) = memory_sink();
+    let jsonrpc = AsyncJsonrpc::connect(&[node.config().rpc_url()]);
+    let mut config = node.config();
+    config.canonical_chain_lag = 0;
+    let mut handler = CanonicalChainState::new(config, jsonrpc, query_sender, 0);
+    tokio::spawn(async move { handler.run().await });
+    sink.wait_until(|sink| sink.inserts("block").len() >= 5)
+        .await;
+
+    // Replace #4 and #5 with a longer branch
+    let mut new_branch = Vec::new();
+    node.update_chain(|chain| {
+        let new_4 = chain.fork_with(&old_branch[2], |builder| builder);
+        let new_5 = chain.fork_with(&new_4, |builder| builder);
+        let new_6 = chain.fork_with(&new_5, |builder| builder);
+        chain.reorganize(&new_6);
+        new_branch = vec![new_4, new_5, new_6];
+    });
+    let new_tip_hash = format!("{:#x}", new_branch[2].hash());
+    sink.wait_until(|sink| {
+        sink.inserts("block")
+            .iter()
+            .any(|row| row[7] == new_tip_hash)
+    })
+    .await;
+
+    let rollback = sink
+        .queries()
+        .iter()
+        .position(|query| query == "DELETE FROM mock_block WHERE network = 'mock' AND number >= 4")
+        .expect("roll back the blocks after #3");
+    let rewritten = sink.queries()[rollback..]
+        .iter()
+        .filter(|query| query.starts_with("INSERT INTO mock_block"))
+        .count();
+    assert_eq!(rewritten, 3);
+    let hashes = sink
+        .inserts("block")
+        .iter()
+        .skip(5)
+        .map(|row| row[7].clone())
+        .collect::<Vec<_>>();
+    let expected = new_branch
+        .iter()
+        .map(|block| format!("{:#x}", block.hash()))
+        .collect::<Vec<_>>();
+    assert_eq!(hashes, expected);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn roll_back_reorganizations_deeper_than_the_recent_blocks() {
+    // The recent 1000 blocks are #3 to #1002
+    let mut chain = MockChain::new(10000);
+    let first = chain.mine();
+    while chain.tip().number() < 1002 {
+        chain.mine();
+    }
+
+    let node = MockNode::start(chain).await;
+    let (query_sender, mut sink) = memory_sink();
+    let jsonrpc = AsyncJsonrpc::connect(&[node.config().rpc_url()]);
+    let mut config = node.config();
+    config.canonical_chain_lag = 0;
+    config.hash_rate_windows = vec![5];
+    let mut handler = CanonicalChainState::new(config, jsonrpc, query_sender, 0);
+    tokio::spawn(async move { handler.run().await });
+    sink.wait_until(|sink| sink.inserts("block").len() >= 1002)
+        .await;
+
+    // Replace #2 and the blocks after with a longer branch
+    let mut new_tip = first.clone();
+    node.update_chain(|chain| {
+        for _ in 2..=1003 {
+            new_tip = chain.fork_with(&new_tip, |builder| builder);
+        }
+        chain.reorganize(&new_tip);
+    });
+    let new_tip_hash = format!("{:#x}", new_tip.hash());
+    sink.wait_until(|sink| {
+        sink.inserts("block")
+            .iter()
+            .any(|row| row[7] == new_tip_hash)
+    })
+    .await;
+
+    // The ancestor #1 is found below the recent blocks, and the analysis is resynced after it
+    let rollback = sink
+        .queries()
+        .iter()
+        .position(|query| query == "DELETE FROM mock_block WHERE network = 'mock' AND number >= 2")
+        .expect("roll back the blocks after #1");
+    let rewritten = sink.queries()[rollback..]
+        .iter()
+        .filter(|query| query.starts_with("INSERT INTO mock_block"))
+        .count();
+    assert_eq!(rewritten, 1002);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn identify_pools_by_cellbase_message() {
+    let mut chain = MockChain::new(10);
+    while chain.tip().number() < 16 {
+        chain.mine();
+    }
+
+    let node = MockNode::start(chain).await;
+    let (query_sender, mut sink) = memory_sink();
+    let jsonrpc = AsyncJsonrpc::connect(&[node.config().rpc_url()]);
+    let mut config = node.config();
+    let pool_labels = pool_labels_file(
+        "identify_pools",
+        r#"
+        [[pools]]
+        name = "ArgsPool"
+        lock_args = ["0x0303030303030303030303030303030303030303"]
+
+        [[pools]]
+        name = "MessagePool"
+        message_patterns = ["(?i)^MOCK$"]
+        "#,
+    );
+    config.pool_labels = Some(pool_labels.path());
+    let mut handler = CanonicalChainState::new(config, jsonrpc, query_sender, 0);
+    tokio::spawn(async move { handler.run().await });
+
+    sink.wait_until(|sink| sink.inserts("block").len() >= 5)
+        .await;
+    assert!(sink
+        .inserts("block")
+        .iter()
+        .all(|row| row[10] == "MessagePool" && row[15] == "mock"));
+}
+
+#[test]
+fn reject_pool_names_not_fitting_the_pool_column() {
+    let labels = |name: &str| format!("[[pools]]\nname = {:?}\n", name);
+    assert!(PoolLabels::parse(&labels(&"P".repeat(50))).is_ok());
+    assert!(PoolLabels::parse(&labels(&"P".repeat(51))).is_err());
+    assert!(PoolLabels::parse(&labels("Pool's")).is_err());
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn analyze_miner_locks_of_unknown_hash_types() {
+    let mut chain = MockChain::new(10);
+    // The cellbase witness lock is not verified, so its hash type may be unknown
+    let lock = MockChain::miner_lock()
+        .as_builder()
+        .hash_type(Byte::new(7))
+        .build();
+    let witness = CellbaseWitness::new_builder().lock(lock).build();
+    let cellbase = TransactionBuilder::default()
+        .input(CellInput::new_cellbase_input(1))
+        .witness(witness.as_bytes().pack())
+        .build();
+    chain.mine_with(|builder| builder.set_transactions(vec![cellbase])); // #1
+    chain.mine();
+
+    let node = MockNode::start(chain).await;
+    let (query_sender, mut sink) = memory_sink();
+    let jsonrpc = AsyncJsonrpc::connect(&[node.config().rpc_url()]);
+    let mut config = node.config();
+    config.canonical_chain_lag = 0;
+    let mut handler = CanonicalChainState::new(config, jsonrpc, query_sender, 0);
+    tokio::spawn(async move { handler.run().await });
+    sink.wait_until(|sink| sink.inserts("block").len() >= 2)
+        .await;
+
+    let blocks = sink.inserts("block");
+    let block = blocks.iter().find(|row| row[2] == "1").unwrap();
+    // The address is empty, as no address format encodes the hash type
+    assert_eq!(
+        block[11..14],
+        [
+            "".to_string(),
+            format!("{:#x}", H256([1; 32])),
+            "unknown".to_string()
+        ]
+    );
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn analyze_fees_and_script_usages_of_resolved_inputs() {
+    let mut chain = MockChain::new(10);
+    let genesis_cellbase = chain.canonical_block(0).unwrap().transaction(0).unwrap();
+    // Mine #1
+    let cellbase = chain.mine().transaction(0).unwrap();
+    // Resolved via RPC, as the genesis block is not analyzed
+    let tx1 = spend(&genesis_cellbase, 1000);
+    // Resolved via the outputs of the recently analyzed blocks
+    let tx2 = spend(&cellbase, 4000);
+    chain.mine_with(|builder| builder.transaction(tx1.clone()).transaction(tx2.clone())); // #2
+    while chain.tip().number() < 5 {
+        chain.mine();
+    }
+
+    let node = MockNode::start(chain).await;
+    let (query_sender, mut sink) = memory_sink();
+    let jsonrpc = AsyncJsonrpc::connect(&[node.config().rpc_url()]);
+    let mut config = node.config();
+    config.canonical_chain_lag = 0;
+    let mut handler = CanonicalChainState::new(config, jsonrpc, query_sender, 0);
+    tokio::spawn(async move { handler.run().await });
+
+    sink.wait_until(|sink| sink.inserts("block_fee").len() >= 5)
+        .await;
+    let fee_rate = |tx: &TransactionView, fee: u64| {
+        (fee * 1000 / tx.data().serialized_size_in_block() as u64).to_string()
+    };
+    let (fee_rate1, fee_rate2) = (fee_rate(&tx1, 1000), fee_rate(&tx2, 4000));
+    let fees = sink.inserts("block_fee");
+    let fees = fees.iter().find(|row| row[2] == "2").unwrap();
+    assert_eq!(
+        fees[3..8],
+        [
+            "2".to_string(),
+            "5000".to_string(),
+            fee_rate1.clone(), // min
+            fee_rate1,         // median
+            fee_rate2,         // p90
+        ]
+    );
+    let n_transactions = sink
+        .inserts("fee_rate_histogram")
+        .iter()
+        .filter(|row| row[2] == "2")
+        .map(|row| row[4].parse::<u32>().unwrap())
+        .sum::<u32>();
+    assert_eq!(n_transactions, 2);
+
+    // The cellbase and the 2 transactions create 3 cells of the miner lock, and consume 2
+    sink.wait_until(|sink| sink.inserts("script_usage").len() >= 5)
+        .await;
+    let usages = sink.inserts("script_usage");
+    let usage = usages.iter().find(|row| row[2] == "2").unwrap();
+    let code_hash = format!("{:#x}", MockChain::miner_lock().code_hash());
+    assert_eq!(
+        usage[3..9],
+        [
+            "lock".to_string(),
+            code_hash,
+            "type".to_string(),
+            "".to_string(),
+            "3".to_string(),
+            "2".to_string(),
+        ]
+    );
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn skip_transactions_spending_cells_not_found() {
+    let mut chain = MockChain::new(10);
+    let genesis_cellbase = chain.canonical_block(0).unwrap().transaction(0).unwrap();
+    chain.mine();
+    let tx1 = spend(&genesis_cellbase, 1000);
+    // Spends a transaction the node does not have
+    let tx2 = spend(&transaction(99), 50);
+    chain.mine_with(|builder| builder.transaction(tx1.clone()).transaction(tx2.clone())); // #2
+    while chain.tip().number() < 5 {
+        chain.mine();
+    }
+
+    let node = MockNode::start(chain).await;
+    let (query_sender, mut sink) = memory_sink();
+    let jsonrpc = AsyncJsonrpc::connect(&[node.config().rpc_url()]);
+    let mut config = node.config();
+    config.canonical_chain_lag = 0;
+    let mut handler = CanonicalChainState::new(config, jsonrpc, query_sender, 0);
+    tokio::spawn(async move { handler.run().await });
+
+    // The analysis goes on, without the fee of the transaction
+    sink.wait_until(|sink| sink.inserts("block_fee").len() >= 5)
+        .await;
+    let fees = sink.inserts("block_fee");
+    let fees = fees.iter().find(|row| row[2] == "2").unwrap();
+    assert_eq!(fees[3..5], ["1".to_string(), "1000".to_string()]);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn track_dao_deposits_and_withdrawals() {
+    let mut chain = MockChain::new(10);
+    let genesis_cellbase = chain.canonical_block(0).unwrap().transaction(0).unwrap();
+    let deposit = dao_transaction(&genesis_cellbase, 0, 1000);
+    chain.mine_with(|builder| {
+        builder
+            .transaction(deposit.clone())
+            .dao(dao_field(10_000_000_000_000_000))
+    }); // #1
+    let phase1 = dao_transaction(&deposit, 1, 0);
+    chain.mine_with(|builder| {
+        builder
+            .transaction(phase1.clone())
+            .dao(dao_field(11_000_000_000_000_000))
+    }); // #2
+        // The lock, the DAO type, the capacity and the data occupy 53 + 33 + 8 + 8 bytes
+    let capacity = 100_000_000_000u64 - 1000;
+    let occupied = 102 * 100_000_000;
+    let compensation = (capacity - occupied) / 10;
+    // Withdraw the capacity and the compensation, paying a fee of 500
+    let phase2 = spend(&phase1, 0);
+    let output = phase2
+        .output(0)
+        .unwrap()
+        .as_builder()
+        .capacity((capacity + compensation - 500).pack())
+        .build();
+    let phase2 = phase2
+        .as_advanced_builder()
+        .set_outputs(vec![output])
+        .build();
+    chain.mine_with(|builder| builder.transaction(phase2.clone())); // #3
+    while chain.tip().number() < 5 {
+        chain.mine();
+    }
+
+    let node = MockNode::start(chain).await;
+    let (query_sender, mut sink) = memory_sink();
+    let jsonrpc = AsyncJsonrpc::connect(&[node.config().rpc_url()]);
+    let mut config = node.config();
+    config.canonical_chain_lag = 0;
+    let mut handler = CanonicalChainState::new(config, jsonrpc, query_sender, 0);
+    tokio::spawn(async move { handler.run().await });
+
+    sink.wait_until(|sink| sink.inserts("dao_event").len() >= 3)
+        .await;
+    let states = sink.inserts("dao_state");
+    let state = states.iter().find(|row| row[2] == "2").unwrap();
+    assert_eq!(state[4], "11000000000000000");
+
+    let compensation = compensation.to_string();
+    let capacity = capacity.to_string();
+    let events = sink.inserts("dao_event");
+    let event = |kind: &str| events.iter().find(|row| row[4] == kind).unwrap()[2..].to_vec();
+    let hash = |transaction: &TransactionView| format!("{:#x}", transaction.hash());
+    assert_eq!(
+        event("deposit"),
+        [
+            "1".to_string(),
+            hash(&deposit),
+            "deposit".to_string(),
+            capacity.clone(),
+            "1".to_string(),
+            "0".to_string(),
+            "0".to_string(),
+            "0".to_string(),
+        ]
+    );
+    for (kind, number, transaction) in &[("phase1", "2", &phase1), ("phase2", "3", &phase2)] {
+        assert_eq!(
+            event(kind),
+            [
+                number.to_string(),
+                hash(transaction),
+                kind.to_string(),
+                capacity.clone(),
+                "1".to_string(),
+                "2".to_string(),
+                BLOCK_INTERVAL.to_string(),
+                compensation.clone(),
+            ]
+        );
+    }
+
+    // The fee of the withdrawal counts the compensation in
+    sink.wait_until(|sink| sink.inserts("block_fee").len() >= 3)
+        .await;
+    let fees = sink.inserts("block_fee");
+    let fees = fees.iter().find(|row| row[2] == "3").unwrap();
+    assert_eq!(fees[3..5], ["1".to_string(), "500".to_string()]);
+
+    // The withdrawal's type code hashes count the DAO type of its input
+    let transactions = sink.inserts("committed_transaction");
+    let committed = transactions
+        .iter()
+        .find(|row| row[4] == hash(&phase2))
+        .unwrap();
+    assert_eq!(committed[13], format!("{{{:#x}}}", NERVOS_DAO_CODE_HASH));
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn analyze_block_rewards() {
+    // Epoch 1 is blocks [10, 20), finalized by blocks [21, 31)
+    let mut chain = MockChain::new(10);
+    while chain.tip().number() < 30 {
+        chain.mine();
+    }
+
+    let node = MockNode::start(chain).await;
+    let (query_sender, mut sink) = memory_sink();
+    let jsonrpc = AsyncJsonrpc::connect(&[node.config().rpc_url()]);
+    let mut config = node.config();
+    config.canonical_chain_lag = 0;
+    let mut handler = CanonicalChainState::new(config, jsonrpc, query_sender, 0);
+    tokio::spawn(async move { handler.run().await });
+
+    // Blocks #12 to #30 finalize the rewards of #1 to #19
+    sink.wait_until(|sink| sink.inserts("block_reward").len() >= 19)
+        .await;
+    let rewards = sink.inserts("block_reward");
+    // An epoch of 10 blocks splits the primary reward 125_000_000_000_000 evenly, and the
+    // secondary reward 61_369_863_013_698 into 6_136_986_301_369 with the remainder 8 going to
+    // the epoch indices 0 to 7
+    for reward in rewards.iter() {
+        let number = reward[2].parse::<u64>().unwrap();
+        let secondary = if number % 10 < 8 {
+            "6136986301370"
+        } else {
+            "6136986301369"
+        };
+        assert_eq!(
+            reward[5..9],
+            ["12500000000000", secondary, "12500000000000", secondary],
+            "#{} issuance",
+            number
+        );
+    }
+    // The remainder of the secondary epoch reward goes to the first 8 blocks
+    let reward = rewards.iter().find(|row| row[2] == "1").unwrap();
+    assert_eq!(
+        reward[3..14],
+        [
+            "0",
+            "12",
+            "12500000000000",
+            "6136986301370",
+            "12500000000000",
+            "6136986301370",
+            "12500000000000",
+            "3068493150685",
+            "0",
+            "0",
+            "0",
+        ]
+    );
+    let secondary_issuance = rewards
+        .iter()
+        .filter(|row| row[3] == "1")
+        .map(|row| row[6].parse::<u64>().unwrap())
+        .sum::<u64>();
+    assert_eq!(secondary_issuance, 61_369_863_013_698);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn backfill_block_rewards_of_the_range() {
+    let mut chain = MockChain::new(10);
+    while chain.tip().number() < 40 {
+        chain.mine();
+    }
+
+    let node = MockNode::start(chain).await;
+    let (query_sender, mut sink) = memory_sink();
+    let jsonrpc = AsyncJsonrpc::connect(&[node.config().rpc_url()]);
+    let backfill = Backfill {
+        from: 8,
+        to: 12,
+        tables: vec![AnalyzedTable::BlockReward].into_iter().collect(),
+    };
+    let mut handler =
+        CanonicalChainState::new(node.config(), jsonrpc, query_sender, 0).with_backfill(backfill);
+    handler.run().await;
+    sink.wait_until(|_| true).await;
+
+    // The rewards of #8 to #12, finalized by #19 to #23
+    let rewards = sink
+        .inserts("block_reward")
+        .iter()
+        .map(|row| (row[2].clone(), row[4].clone()))
+        .collect::<Vec<_>>();
+    let expected = (8..=12)
+        .map(|number| (number.to_string(), (number + 11).to_string()))
+        .collect::<Vec<_>>();
+    assert_eq!(rewards, expected);
+    let deletes = sink
+        .queries()
+        .iter()
+        .filter(|query| query.starts_with("DELETE FROM mock_block_reward"))
+        .cloned()
+        .collect::<Vec<_>>();
+    let expected = (8..=12)
+        .map(|number| {
+            format!(
+                "DELETE FROM mock_block_reward WHERE network = 'mock' AND number = {}",
+                number
+            )
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(deletes, expected);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn track_chain_state_and_reconcile_with_indexer() {
+    let mut chain = MockChain::new(1000);
+    let first = chain.mine();
+    chain.mine();
+    chain.mine_with(|builder| builder.transaction(spend(&first.transactions()[0], 1_000)));
+    while chain.tip().number() < 12 {
+        chain.mine();
+    }
+
+    let node = MockNode::start(chain).await;
+    let (query_sender, mut sink) = memory_sink();
+    let jsonrpc = AsyncJsonrpc::connect(&[node.config().rpc_url()]);
+    let mut config = node.config();
+    config.canonical_chain_lag = 0;
+    config.node.indexer_rpc_url = Some(node.config().rpc_url());
+    config.chain_state_reconciliation_interval = 5;
+    config.scripts.push(ScriptConfig {
+        name: "miner".to_string(),
+        code_hash: format!("{:#x}", H256([1; 32])),
+        hash_type: "type".to_string(),
+    });
+    let mut handler = CanonicalChainState::new(config, jsonrpc, query_sender, 0);
+    tokio::spawn(async move { handler.run().await });
+    sink.wait_until(|sink| sink.inserts("chain_state").len() >= 12)
+        .await;
+
+    // The genesis and every block create a cellbase cell of 61 bytes; #3 spends one paying 1000
+    let states = sink.inserts("chain_state");
+    assert_eq!(
+        states[2][2..9],
+        [
+            "3",
+            "2",
+            "1",
+            "4",
+            "399999999000",
+            "24400000000",
+            "375599999000"
+        ]
+    );
+    assert_eq!(
+        states[11][2..9],
+        [
+            "12",
+            "1",
+            "0",
+            "13",
+            "1299999999000",
+            "79300000000",
+            "1220699999000"
+        ]
+    );
+    // The indexer reading at #5 is ahead, at #12, so only establishes the capacity of the lock
+    assert!(sink.inserts("chain_state_reconciliation").is_empty());
+
+    node.update_chain(|chain| {
+        while chain.tip().number() < 15 {
+            chain.mine();
+        }
+    });
+    sink.wait_until(|sink| !sink.inserts("chain_state_reconciliation").is_empty())
+        .await;
+    let reconciliations = sink.inserts("chain_state_reconciliation");
+    assert_eq!(
+        reconciliations,
+        [vec![
+            "mock".to_string(),
+            reconciliations[0][1].clone(),
+            "15".to_string(),
+            format!("{:#x}", H256([1; 32])),
+            "type".to_string(),
+            "miner".to_string(),
+            "1599999999000".to_string(),
+            "1599999999000".to_string(),
+            "0".to_string(),
+        ]]
+    );
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn record_expired_proposals() {
+    let mut chain = MockChain::new(1000);
+    let (committed, expired, uncle_proposed) = (transaction(1), transaction(2), transaction(3));
+    chain.mine();
+    chain.mine();
+    let proposer = chain.mine_with(|builder| {
+        builder
+            .proposal(committed.proposal_short_id())
+            .proposal(expired.proposal_short_id())
+    }); // #3
+    let parent = chain.tip();
+    let uncle = chain.fork_with(&parent, |builder| {
+        builder.proposal(uncle_proposed.proposal_short_id())
+    }); // #4 uncle
+    chain.mine(); // #4
+    chain.mine_with(|builder| builder.uncle(uncle.as_uncle())); // #5
+    chain.mine_with(|builder| builder.transaction(committed.clone())); // #6
+    while chain.tip().number() < 20 {
+        chain.mine();
+    }
+
+    let node = MockNode::start(chain).await;
+    let (query_sender, mut sink) = memory_sink();
+    let jsonrpc = AsyncJsonrpc::connect(&[node.config().rpc_url()]);
+    let mut config = node.config();
+    config.canonical_chain_lag = 0;
+    let mut handler = CanonicalChainState::new(config, jsonrpc, query_sender, 0);
+    tokio::spawn(async move { handler.run().await });
+    sink.wait_until(|sink| sink.inserts("block").len() >= 20)
+        .await;
+
+    // The proposal windows of #3 and #5 close at #13 and #15
+    let miner = format!("0x{}", "02".repeat(20));
+    let expired_proposals = sink
+        .inserts("expired_proposal")
+        .into_iter()
+        .map(|row| row[2..].to_vec())
+        .collect::<Vec<_>>();
+    assert_eq!(
+        expired_proposals,
+        [
+            vec![
+                "13".to_string(),
+                format!("{:#x}", expired.proposal_short_id()),
+                "3".to_string(),
+                format!("{:#x}", proposer.hash()),
+                miner.clone(),
+                "false".to_string(),
+            ],
+            vec![
+                "15".to_string(),
+                format!("{:#x}", uncle_proposed.proposal_short_id()),
+                "5".to_string(),
+                format!("{:#x}", uncle.hash()),
+                miner,
+                "true".to_string(),
+            ],
+        ]
+    );
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn commit_re_proposed_transactions() {
+    let mut chain = MockChain::new(1000);
+    let re_proposed = transaction(1);
+    chain.mine();
+    chain.mine();
+    let first_proposer =
+        chain.mine_with(|builder| builder.proposal(re_proposed.proposal_short_id())); // #3
+    chain.mine(); // #4
+    let second_proposer =
+        chain.mine_with(|builder| builder.proposal(re_proposed.proposal_short_id())); // #5
+    chain.mine(); // #6
+    let parent = chain.mine(); // #7
+    chain.mine_with(|builder| builder.transaction(re_proposed.clone())); // #8
+    while chain.tip().number() < 16 {
+        chain.mine();
+    }
+
+    let node = MockNode::start(chain).await;
+    let (query_sender, mut sink) = memory_sink();
+    let jsonrpc = AsyncJsonrpc::connect(&[node.config().rpc_url()]);
+    let mut config = node.config();
+    config.canonical_chain_lag = 0;
+    let mut handler = CanonicalChainState::new(config, jsonrpc, query_sender, 0);
+    tokio::spawn(async move { handler.run().await });
+    sink.wait_until(|sink| sink.inserts("block").len() >= 16)
+        .await;
+
+    // Committed once, counting from the earliest proposal, and expired from neither zone
+    let commitments = sink.inserts("two_pc_commitment");
+    assert_eq!(commitments.len(), 1);
+    assert_eq!(commitments[0][2..], ["8".to_string(), "5".to_string()]);
+    assert!(sink.inserts("expired_proposal").is_empty());
+
+    // Replace #8 with a branch never committing it, both proposals expire then
+    let mut new_tip = None;
+    node.update_chain(|chain| {
+        let mut tip = chain.fork_with(&parent, |builder| builder);
+        while tip.number() < 17 {
+            tip = chain.fork_with(&tip, |builder| builder);
+        }
+        chain.reorganize(&tip);
+        new_tip = Some(tip);
+    });
+    let new_tip_hash = format!("{:#x}", new_tip.unwrap().hash());
+    sink.wait_until(|sink| {
+        sink.inserts("block")
+            .iter()
+            .any(|row| row[7] == new_tip_hash)
+    })
+    .await;
+
+    let miner = format!("0x{}", "02".repeat(20));
+    let expired_proposals = sink
+        .inserts("expired_proposal")
+        .into_iter()
+        .map(|row| row[2..].to_vec())
+        .collect::<Vec<_>>();
+    assert_eq!(
+        expired_proposals,
+        [
+            vec![
+                "13".to_string(),
+                format!("{:#x}", re_proposed.proposal_short_id()),
+                "3".to_string(),
+                format!("{:#x}", first_proposer.hash()),
+                miner.clone(),
+                "false".to_string(),
+            ],
+            vec![
+                "15".to_string(),
+                format!("{:#x}", re_proposed.proposal_short_id()),
+                "5".to_string(),
+                format!("{:#x}", second_proposer.hash()),
+                miner,
+                "false".to_string(),
+            ],
+        ]
+    );
+}
+
+// Spend the first output of the previous transaction, paying the fee
+fn spend(previous: &TransactionView, fee: u64) -> TransactionView {
+    let capacity: u64 = previous.outputs().get(0).unwrap().capacity().unpack();
+    TransactionBuilder::default()
+        .input(CellInput::new(OutPoint::new(previous.hash(), 0), 0))
+        .output(
+            CellOutput::new_builder()
+                .capacity((capacity - fee).pack())
+                .lock(MockChain::miner_lock())
+                .build(),
+        )
+        .output_data(Bytes::new().pack())
+        .build()
+}
+
+// Deposit the first output of the previous transaction into the DAO, or withdraw it in phase 1
+// when `deposit_number` is not 0
+fn dao_transaction(previous: &TransactionView, deposit_number: u64, fee: u64) -> TransactionView {
+    let capacity: u64 = previous.outputs().get(0).unwrap().capacity().unpack();
+    let dao_type = Script::new_builder()
+        .code_hash(NERVOS_DAO_CODE_HASH.pack())
+        .hash_type(ScriptHashType::Type.into())
+        .build();
+    TransactionBuilder::default()
+        .input(CellInput::new(OutPoint::new(previous.hash(), 0), 0))
+        .output(
+            CellOutput::new_builder()
+                .capacity((capacity - fee).pack())
+                .lock(MockChain::miner_lock())
+                .type_(Some(dao_type).pack())
+                .build(),
+        )
+        .output_data(Bytes::from(deposit_number.to_le_bytes().to_vec()).pack())
+        .build()
+}
+
+// The header dao field with the accumulated rate
+fn dao_field(accumulated_rate: u64) -> Byte32 {
+    let mut dao = [0u8; 32];
+    dao[8..16].copy_from_slice(&accumulated_rate.to_le_bytes());
+    Byte32::new(dao)
+}
+
+// The labels file is removed when the guard is dropped
+struct PoolLabelsFile(std::path::PathBuf);
+
+impl PoolLabelsFile {
+    fn path(&self) -> String {
+        self.0.to_string_lossy().to_string()
+    }
+}
+
+impl Drop for PoolLabelsFile {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_file(&self.0);
+    }
+}
+
+fn pool_labels_file(name: &str, content: &str) -> PoolLabelsFile {
+    let path =
+        std::env::temp_dir().join(format!("ckb-analyzer-{}-{}.toml", name, std::process::id()));
+    std::fs::write(&path, content).unwrap();
+    PoolLabelsFile(path)
+}
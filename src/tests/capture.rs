@@ -0,0 +1,51 @@
+use crate::capture::{CaptureWriter, Replay};
+use crate::subscribe::Topic;
+use ckb_suite_rpc::{Recorder, Responder};
+use flate2::read::GzDecoder;
+use serde_json::json;
+use std::fs::{remove_file, File};
+use std::io::Read;
+
+#[test]
+fn replay_the_finished_capture() {
+    let path = std::env::temp_dir().join(format!(
+        "ckb-analyzer-capture-{}.jsonl.gz",
+        std::process::id()
+    ));
+    let path = path.to_str().unwrap();
+    let writer = CaptureWriter::create(path).unwrap();
+    writer.record_notification(Topic::NewTipHeader, "header 1");
+    writer.record(
+        "get_block_hash",
+        &json!(["0x1"]),
+        &json!({ "jsonrpc": "2.0", "id": 0, "result": "0x01" }),
+    );
+    writer.record_notification(Topic::NewTransaction, "transaction 1");
+    writer.finish();
+    // Dropped after the trailer
+    writer.record_notification(Topic::NewTipHeader, "header 2");
+
+    // The gzip stream is complete
+    let mut capture = String::new();
+    GzDecoder::new(File::open(path).unwrap())
+        .read_to_string(&mut capture)
+        .unwrap();
+    assert_eq!(capture.lines().count(), 3);
+
+    let replay = Replay::open(path, 0.0).unwrap();
+    remove_file(path).unwrap();
+    let messages = |topic| {
+        replay
+            .notifications(topic)
+            .into_iter()
+            .map(|(_, message)| message)
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(messages(Topic::NewTipHeader), ["header 1"]);
+    assert_eq!(messages(Topic::NewTransaction), ["transaction 1"]);
+    assert_eq!(
+        replay.respond("get_block_hash", &json!(["0x1"])),
+        Some(json!({ "jsonrpc": "2.0", "id": 0, "result": "0x01" }))
+    );
+    assert_eq!(replay.respond("get_block_hash", &json!(["0x2"])), None);
+}
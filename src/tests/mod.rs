@@ -0,0 +1,6 @@
+//! End-to-end tests of the topics against the mock node
+
+mod canonical_chain_state;
+mod capture;
+mod reorganization;
+mod tx_transition;
@@ -0,0 +1,44 @@
+use crate::mock_node::{memory_sink, spawn_subscription, MockChain, MockNode};
+use crate::subscribe::Notification;
+use crate::topic::Reorganization;
+use ckb_suite_rpc::AsyncJsonrpc;
+use std::iter::once;
+
+#[tokio::test(flavor = "multi_thread")]
+async fn detect_reorganization() {
+    let mut chain = MockChain::new(1000);
+    let old_branch = (1..=5).map(|_| chain.mine()).collect::<Vec<_>>();
+    let ancestor = old_branch[2].clone(); // #3
+    let new_4 = chain.fork_with(&ancestor, |builder| builder);
+    let new_5 = chain.fork_with(&new_4, |builder| builder);
+    let new_tip = chain.fork_with(&new_5, |builder| builder);
+    chain.reorganize(&new_tip);
+
+    let node = MockNode::start(chain).await;
+    let (query_sender, mut sink) = memory_sink();
+    let jsonrpc = AsyncJsonrpc::connect(&[node.config().rpc_url()]);
+    let (handler, subscription) = Reorganization::new(node.config(), jsonrpc, query_sender);
+    spawn_subscription(subscription);
+    tokio::spawn(handler.run());
+    for block in old_branch.iter().chain(once(&new_tip)) {
+        node.notify(Notification::NewTipHeader(block.header().into()));
+    }
+
+    sink.wait_until(|sink| !sink.inserts("reorganization").is_empty())
+        .await;
+
+    let reorganizations = sink.inserts("reorganization");
+    assert_eq!(reorganizations.len(), 1);
+    assert_eq!(
+        reorganizations[0][2..],
+        [
+            "3".to_string(), // attached_length
+            "5".to_string(),
+            "6".to_string(),
+            "3".to_string(),
+            format!("{:#x}", old_branch[4].hash()),
+            format!("{:#x}", new_tip.hash()),
+            format!("{:#x}", ancestor.hash()),
+        ]
+    );
+}
@@ -0,0 +1,77 @@
+use crate::mock_node::{
+    memory_sink, pool_entry, spawn_subscription, transaction, MockChain, MockNode, NETWORK,
+};
+use crate::subscribe::Notification;
+use crate::topic::TxTransition;
+use ckb_suite_rpc::AsyncJsonrpc;
+use serde_json::{json, Value};
+use std::time::Duration;
+
+#[tokio::test(flavor = "multi_thread")]
+async fn report_enter_commit_and_remove() {
+    let mut chain = MockChain::new(1000);
+    let committed = transaction(1);
+    let removed = transaction(2);
+    let pending = transaction(3);
+    chain.submit_transaction(removed.clone());
+    chain.submit_transaction(pending.clone());
+    let block = chain.mine_with(|builder| builder.transaction(committed.clone()));
+
+    let node = MockNode::start(chain).await;
+    // The node has evicted `removed` from its pool
+    node.script(
+        "get_transaction",
+        json!([format!("{:#x}", removed.hash())]),
+        Value::Null,
+    );
+    let (query_sender, mut sink) = memory_sink();
+    let jsonrpc = AsyncJsonrpc::connect(&[node.config().rpc_url()]);
+    let (handler, subscription) = TxTransition::new(node.config(), jsonrpc, query_sender);
+    let handler = handler.with_check_intervals(Duration::from_secs(1), chrono::Duration::zero());
+    spawn_subscription(subscription);
+    tokio::spawn(handler.run());
+    for tx in [&committed, &removed, &pending].iter() {
+        node.notify(Notification::NewTransaction(pool_entry(tx)));
+    }
+
+    let update_prefix = format!("UPDATE {}_transaction SET ", NETWORK);
+    let updates = |queries: &[String]| {
+        queries
+            .iter()
+            .filter(|query| query.starts_with(&update_prefix))
+            .cloned()
+            .collect::<Vec<_>>()
+    };
+    sink.wait_until(|sink| {
+        sink.inserts("transaction").len() == 3 && updates(sink.queries()).len() >= 2
+    })
+    .await;
+
+    let entered = sink
+        .inserts("transaction")
+        .into_iter()
+        .map(|row| row[2].clone())
+        .collect::<Vec<_>>();
+    for tx in [&committed, &removed, &pending].iter() {
+        assert!(entered.contains(&format!("{:#x}", tx.hash())));
+    }
+
+    let commit_time = chrono::NaiveDateTime::from_timestamp(
+        (block.timestamp() / 1000) as i64,
+        (block.timestamp() % 1000 * 1000) as u32,
+    );
+    let updates = updates(sink.queries());
+    assert!(updates.contains(&format!(
+        "{}commit_time='{}' WHERE hash='{:#x}'",
+        update_prefix,
+        commit_time,
+        committed.hash()
+    )));
+    assert!(updates.iter().any(|update| {
+        update.starts_with(&format!("{}remove_time=", update_prefix))
+            && update.ends_with(&format!("WHERE hash='{:#x}'", removed.hash()))
+    }));
+    assert!(!updates
+        .iter()
+        .any(|update| update.ends_with(&format!("WHERE hash='{:#x}'", pending.hash()))));
+}
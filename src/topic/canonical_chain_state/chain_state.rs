@@ -0,0 +1,300 @@
+//! The live cells and capacity of the chain, reconciled with the indexer
+
+use super::{block_time, CanonicalChainState, ResolvedCell, Write};
+use crate::address::script_components;
+use crate::script_registry::ScriptRegistry;
+use crate::table;
+use crate::util::{retry_rpc, retry_send};
+use ckb_suite_rpc::ckb_jsonrpc_types::{
+    Script as JsonScript, ScriptHashType as JsonScriptHashType,
+};
+use ckb_suite_rpc::indexer::{ScriptType, SearchKey};
+use ckb_suite_rpc::{AsyncJsonrpc, Error as RpcError};
+use ckb_types::bytes::Bytes;
+use ckb_types::core::{BlockNumber, BlockView, Capacity};
+use ckb_types::packed::{Byte32, CellOutput, OutPoint};
+use ckb_types::prelude::*;
+use ckb_types::H256;
+use std::cmp::max;
+use std::collections::HashMap;
+use std::str::FromStr;
+
+/// The live cells of the chain after a block
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ChainOccupancy {
+    pub live_cells: u64,
+    pub capacity: u64,
+    pub occupied_capacity: u64,
+}
+
+impl ChainOccupancy {
+    fn create(&mut self, output: &CellOutput, data: &Bytes) {
+        let capacity: u64 = output.capacity().unpack();
+        self.live_cells += 1;
+        self.capacity += capacity;
+        self.occupied_capacity += occupied_capacity(output, data);
+    }
+
+    fn consume(&mut self, output: &CellOutput, data: &Bytes) {
+        let capacity: u64 = output.capacity().unpack();
+        let occupied = occupied_capacity(output, data);
+        if self.live_cells == 0 || self.capacity < capacity || self.occupied_capacity < occupied {
+            log::warn!(
+                "the chain state underflows by consuming a cell of {} shannons, clamped to 0; \
+                backfill chain_state from the genesis",
+                capacity
+            );
+        }
+        self.live_cells = self.live_cells.saturating_sub(1);
+        self.capacity = self.capacity.saturating_sub(capacity);
+        self.occupied_capacity = self.occupied_capacity.saturating_sub(occupied);
+    }
+}
+
+// The capacities of the registered lock scripts read from the indexer at a block
+struct IndexerReading {
+    number: BlockNumber,
+    hash: Byte32,
+    // (code hash, hash type) => capacity
+    capacities: HashMap<(String, String), u64>,
+}
+
+// The capacities of the live cells locked by the registered scripts, established by the first
+// indexer reading and followed incrementally, to be compared with the later readings
+#[derive(Default)]
+pub(super) struct Reconciliation {
+    // (code hash, hash type) => capacity
+    lock_capacities: HashMap<(String, String), u64>,
+    // The reading ahead of the analysis, compared once the analysis reaches its block
+    pending: Option<IndexerReading>,
+}
+
+impl Reconciliation {
+    fn count(&mut self, output: &CellOutput, created: bool) {
+        if self.lock_capacities.is_empty() {
+            return;
+        }
+        let (code_hash, hash_type, _) = script_components(&output.lock());
+        if let Some(lock_capacity) = self.lock_capacities.get_mut(&(code_hash, hash_type)) {
+            let capacity: u64 = output.capacity().unpack();
+            if created {
+                *lock_capacity += capacity;
+            } else {
+                *lock_capacity = lock_capacity.saturating_sub(capacity);
+            }
+        }
+    }
+}
+
+impl CanonicalChainState {
+    // Count the cells of the genesis block, as the chain state before the first block
+    pub(super) async fn count_genesis_cells(&mut self) -> Result<(), RpcError> {
+        let jsonrpc = &self.jsonrpc;
+        let genesis: BlockView = retry_rpc(|| jsonrpc.get_block_by_number(0)).await?.into();
+        let mut occupancy = ChainOccupancy::default();
+        for transaction in genesis.transactions() {
+            for (output, data) in transaction.outputs_with_data_iter() {
+                occupancy.create(&output, &data);
+            }
+        }
+        self.occupancy = Some((0, occupancy));
+        Ok(())
+    }
+
+    // Advance the chain state by the cells created and consumed by the block, and reconcile it
+    // with the indexer
+    pub(super) async fn analyze_chain_state(
+        &mut self,
+        block: &BlockView,
+        inputs: &HashMap<OutPoint, ResolvedCell>,
+        write: Write,
+    ) {
+        let number = block.number();
+        let mut occupancy = match self.occupancy {
+            Some((last, occupancy)) if last + 1 == number => occupancy,
+            // Resumed after the block, e.g. in the warm-up of a backfill
+            Some((last, _)) if last >= number => return,
+            Some((last, _)) => {
+                log::warn!(
+                    "the chain state is unknown since #{}, as the last known one is after #{}; \
+                    backfill chain_state from the genesis",
+                    number,
+                    last
+                );
+                self.occupancy = None;
+                return;
+            }
+            None => return,
+        };
+        let mut n_created_cells = 0;
+        for transaction in block.transactions() {
+            for (output, data) in transaction.outputs_with_data_iter() {
+                occupancy.create(&output, &data);
+                self.reconciliation.count(&output, true);
+                n_created_cells += 1;
+            }
+        }
+        for cell in inputs.values() {
+            occupancy.consume(&cell.output, &cell.data);
+            self.reconciliation.count(&cell.output, false);
+        }
+        self.occupancy = Some((number, occupancy));
+        let reconciliations = self.reconcile(block).await;
+
+        let network = self.config.network();
+        let time = block_time(block);
+        let (_, occupancy) = self.occupancy.expect("advanced above");
+        let mut queries = Vec::new();
+        if write == Write::Replace {
+            queries.push(table::ChainState::delete_query(&network, number as i64));
+            queries.push(table::ChainStateReconciliation::delete_query(
+                &network,
+                number as i64,
+            ));
+        }
+        let point = table::ChainState {
+            network: network.clone(),
+            time,
+            number: number as i64,
+            n_created_cells,
+            n_consumed_cells: inputs.len() as i32,
+            live_cells: occupancy.live_cells as i64,
+            capacity: occupancy.capacity as i64,
+            occupied_capacity: occupancy.occupied_capacity as i64,
+            free_capacity: occupancy
+                .capacity
+                .saturating_sub(occupancy.occupied_capacity) as i64,
+        };
+        queries.push(point.insert_query());
+        for (code_hash, hash_type, capacity, indexer_capacity) in reconciliations {
+            let name = self
+                .script_registry
+                .name(&code_hash, &hash_type)
+                .unwrap_or_default()
+                .to_string();
+            let point = table::ChainStateReconciliation {
+                network: network.clone(),
+                time,
+                number: number as i64,
+                code_hash,
+                hash_type,
+                name,
+                capacity: capacity as i64,
+                indexer_capacity: indexer_capacity as i64,
+                drift: indexer_capacity as i64 - capacity as i64,
+            };
+            queries.push(point.insert_query());
+        }
+        for query in queries {
+            retry_send(&self.query_sender, query).await;
+        }
+    }
+
+    // Compare the indexer readings at the block with the chain state, and correct the chain state
+    // capacity by the drifts. Only the capacity is corrected, as the indexer reads the capacities
+    // of the scripts but not their cells or occupied capacities. Return the compared capacities,
+    // as code hash, hash type, the incremental capacity and the indexer one.
+    async fn reconcile(&mut self, block: &BlockView) -> Vec<(String, String, u64, u64)> {
+        let indexer = match &self.indexer {
+            Some(indexer) => indexer.clone(),
+            None => return Vec::new(),
+        };
+        let mut readings = Vec::new();
+        match self.reconciliation.pending.take() {
+            Some(reading) if reading.number == block.number() => readings.push(reading),
+            Some(reading) if reading.number > block.number() => {
+                self.reconciliation.pending = Some(reading)
+            }
+            _ => {}
+        }
+        let interval = max(self.config.chain_state_reconciliation_interval, 1);
+        if block.number() % interval == 0 && self.reconciliation.pending.is_none() {
+            // The indexer is usually ahead of the analysis, and is behind when it is catching up
+            match read_indexer(&indexer, &self.script_registry).await {
+                Some(reading) if reading.number == block.number() => readings.push(reading),
+                Some(reading) if reading.number > block.number() => {
+                    self.reconciliation.pending = Some(reading)
+                }
+                _ => {}
+            }
+        }
+
+        let mut compared = Vec::new();
+        // The readings of reorganized blocks are dropped
+        for reading in readings.into_iter().filter(|r| r.hash == block.hash()) {
+            for ((code_hash, hash_type), indexer_capacity) in reading.capacities {
+                let key = (code_hash.clone(), hash_type.clone());
+                let capacity = self
+                    .reconciliation
+                    .lock_capacities
+                    .insert(key, indexer_capacity);
+                if let (Some(capacity), Some((_, occupancy))) = (capacity, self.occupancy.as_mut())
+                {
+                    occupancy.capacity =
+                        (occupancy.capacity + indexer_capacity).saturating_sub(capacity);
+                    compared.push((code_hash, hash_type, capacity, indexer_capacity));
+                }
+            }
+        }
+        compared
+    }
+}
+
+// Read the capacities of the live cells locked by the registered scripts from the indexer. None if
+// the indexer fails, or moves on to another block between the requests.
+async fn read_indexer(indexer: &AsyncJsonrpc, registry: &ScriptRegistry) -> Option<IndexerReading> {
+    let handles = registry
+        .scripts()
+        .map(|(code_hash, hash_type, _)| {
+            let key = (code_hash.to_string(), hash_type.to_string());
+            let search_key = SearchKey {
+                script: JsonScript {
+                    code_hash: H256::from_str(&code_hash[2..]).expect("checked code hash"),
+                    hash_type: if hash_type == "data" {
+                        JsonScriptHashType::Data
+                    } else {
+                        JsonScriptHashType::Type
+                    },
+                    args: Default::default(),
+                },
+                script_type: ScriptType::Lock,
+                filter: None,
+            };
+            let indexer = indexer.clone();
+            tokio::spawn(async move { (key, indexer.get_cells_capacity(search_key).await) })
+        })
+        .collect::<Vec<_>>();
+    let mut reading: Option<IndexerReading> = None;
+    for handle in handles {
+        let (key, cells_capacity) = handle.await.expect("get cells capacity task panicked");
+        let cells_capacity = match cells_capacity {
+            Ok(cells_capacity) => cells_capacity,
+            // No cell is locked by the script
+            Err(err) if err.is_not_found() => continue,
+            Err(err) => {
+                log::warn!("failed to read the indexer, error: {}", err);
+                return None;
+            }
+        };
+        let hash: Byte32 = cells_capacity.block_hash.pack();
+        let reading = reading.get_or_insert_with(|| IndexerReading {
+            number: cells_capacity.block_number.value(),
+            hash: hash.clone(),
+            capacities: HashMap::new(),
+        });
+        if reading.hash != hash {
+            return None;
+        }
+        reading
+            .capacities
+            .insert(key, cells_capacity.capacity.value());
+    }
+    reading
+}
+
+fn occupied_capacity(output: &CellOutput, data: &Bytes) -> u64 {
+    Capacity::bytes(data.len())
+        .and_then(|data_capacity| output.occupied_capacity(data_capacity))
+        .map(|occupied| occupied.as_u64())
+        .unwrap_or_else(|_| output.capacity().unpack())
+}
@@ -0,0 +1,188 @@
+//! The NervosDAO deposits and withdrawals, and the header dao field
+
+use super::{block_time, CanonicalChainState, FetchContext, ResolvedCell, Write};
+use crate::dao::{
+    deposit_number, extract_dao_data, is_dao_cell, is_deposit_data, maximum_withdraw,
+};
+use crate::table;
+use crate::util::{retry_rpc, retry_send};
+use ckb_suite_rpc::Error as RpcError;
+use ckb_types::core::{BlockNumber, BlockView, HeaderView};
+use ckb_types::packed::{Byte32, OutPoint};
+use ckb_types::prelude::*;
+use std::collections::HashMap;
+
+// A phase 2 input, spending a withdrawing cell
+pub(super) struct DaoWithdraw {
+    pub(super) deposit: HeaderView,
+    pub(super) withdrawing: HeaderView,
+    // The withdrawing cell's capacity plus the compensation
+    pub(super) capacity: u64,
+}
+
+impl CanonicalChainState {
+    // Record the DAO deposits and withdrawals of the block, a row per DAO cell
+    pub(super) async fn report_dao_events(
+        &self,
+        block: &BlockView,
+        inputs: &HashMap<OutPoint, ResolvedCell>,
+        withdraws: &HashMap<OutPoint, DaoWithdraw>,
+        write: Write,
+    ) {
+        let network = self.config.network();
+        let number = block.number() as i64;
+        let time = block_time(block);
+        let mut queries = Vec::new();
+        if write == Write::Replace {
+            queries.push(table::DaoEvent::delete_query(&network, number));
+        }
+        for transaction in block.transactions().iter().skip(1) {
+            let transaction_hash = format!("{:#x}", transaction.hash());
+            for (output, data) in transaction.outputs_with_data_iter() {
+                if is_dao_cell(&output) && is_deposit_data(&data) {
+                    let capacity: u64 = output.capacity().unpack();
+                    let point = table::DaoEvent {
+                        network: network.clone(),
+                        time,
+                        number,
+                        transaction_hash: transaction_hash.clone(),
+                        kind: "deposit".to_string(),
+                        capacity: capacity as i64,
+                        deposit_number: number,
+                        withdrawing_number: 0,
+                        duration: 0,
+                        compensation: 0,
+                    };
+                    queries.push(point.insert_query());
+                }
+            }
+            for out_point in transaction.input_pts_iter() {
+                let cell = match inputs.get(&out_point) {
+                    Some(cell) if is_dao_cell(&cell.output) => cell,
+                    _ => continue,
+                };
+                // Phase 1 spends a deposit cell, and phase 2 a withdrawing cell, whose headers
+                // are got along with `withdraws`
+                let (kind, deposit, withdrawing) = if is_deposit_data(&cell.data) {
+                    match self.get_header(cell.block_hash.clone()).await {
+                        Ok(deposit) => ("phase1", deposit, block.header()),
+                        Err(err) => {
+                            log::error!(
+                                "skip the DAO event of transaction {}, error: {}",
+                                transaction_hash,
+                                err
+                            );
+                            continue;
+                        }
+                    }
+                } else if let Some(withdraw) = withdraws.get(&out_point) {
+                    (
+                        "phase2",
+                        withdraw.deposit.clone(),
+                        withdraw.withdrawing.clone(),
+                    )
+                } else {
+                    continue;
+                };
+                let capacity: u64 = cell.output.capacity().unpack();
+                let withdraw = maximum_withdraw(
+                    &cell.output,
+                    &cell.data,
+                    extract_dao_data(&deposit.dao()).accumulated_rate,
+                    extract_dao_data(&withdrawing.dao()).accumulated_rate,
+                );
+                let point = table::DaoEvent {
+                    network: network.clone(),
+                    time,
+                    number,
+                    transaction_hash: transaction_hash.clone(),
+                    kind: kind.to_string(),
+                    capacity: capacity as i64,
+                    deposit_number: deposit.number() as i64,
+                    withdrawing_number: withdrawing.number() as i64,
+                    duration: withdrawing.timestamp().saturating_sub(deposit.timestamp()) as i64,
+                    compensation: withdraw.saturating_sub(capacity) as i64,
+                };
+                queries.push(point.insert_query());
+            }
+        }
+        for query in queries {
+            retry_send(&self.query_sender, query).await;
+        }
+    }
+
+    // The phase 2 inputs of the block, with their headers and withdrawn capacities. The inputs
+    // whose headers are failed to get are absent.
+    pub(super) async fn get_dao_withdraws(
+        &self,
+        block: &BlockView,
+        inputs: &HashMap<OutPoint, ResolvedCell>,
+    ) -> HashMap<OutPoint, DaoWithdraw> {
+        let mut withdraws = HashMap::new();
+        for transaction in block.transactions().iter().skip(1) {
+            for out_point in transaction.input_pts_iter() {
+                let (cell, deposit_number) = match inputs.get(&out_point) {
+                    Some(cell) if is_dao_cell(&cell.output) => match deposit_number(&cell.data) {
+                        Some(deposit_number) => (cell, deposit_number),
+                        None => continue,
+                    },
+                    _ => continue,
+                };
+                match self.get_withdrawing_headers(cell, deposit_number).await {
+                    Ok((deposit, withdrawing)) => {
+                        let capacity = maximum_withdraw(
+                            &cell.output,
+                            &cell.data,
+                            extract_dao_data(&deposit.dao()).accumulated_rate,
+                            extract_dao_data(&withdrawing.dao()).accumulated_rate,
+                        );
+                        let withdraw = DaoWithdraw {
+                            deposit,
+                            withdrawing,
+                            capacity,
+                        };
+                        withdraws.insert(out_point, withdraw);
+                    }
+                    Err(err) => log::error!(
+                        "failed to get the DAO compensation of transaction {:#x}, error: {}",
+                        transaction.hash(),
+                        err
+                    ),
+                }
+            }
+        }
+        withdraws
+    }
+
+    // The deposit and withdrawing headers of a withdrawing cell
+    async fn get_withdrawing_headers(
+        &self,
+        cell: &ResolvedCell,
+        deposit_number: BlockNumber,
+    ) -> Result<(HeaderView, HeaderView), RpcError> {
+        let deposit = retry_rpc(|| self.jsonrpc.get_header_by_number(deposit_number))
+            .await?
+            .into();
+        let withdrawing = self.get_header(cell.block_hash.clone()).await?;
+        Ok((deposit, withdrawing))
+    }
+
+    async fn get_header(&self, block_hash: Byte32) -> Result<HeaderView, RpcError> {
+        retry_rpc(|| self.jsonrpc.get_header(block_hash.clone()))
+            .await
+            .map(Into::into)
+    }
+}
+
+pub(super) fn analyze_dao_state(context: &FetchContext, block: &BlockView) -> table::DaoState {
+    let dao = extract_dao_data(&block.header().dao());
+    table::DaoState {
+        network: context.network.clone(),
+        time: block_time(block),
+        number: block.number() as i64,
+        total_issuance: dao.total_issuance as i64,
+        accumulated_rate: dao.accumulated_rate as i64,
+        unissued_secondary_issuance: dao.unissued_secondary_issuance as i64,
+        occupied_capacity: dao.occupied_capacity as i64,
+    }
+}
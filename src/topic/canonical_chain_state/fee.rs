@@ -0,0 +1,121 @@
+//! The fees of the committed transactions, from their resolved inputs
+
+use super::dao::DaoWithdraw;
+use super::{block_time, CanonicalChainState, ResolvedCell, Write};
+use crate::dao::{deposit_number, is_dao_cell};
+use crate::table;
+use crate::util::retry_send;
+use ckb_types::core::BlockView;
+use ckb_types::packed::OutPoint;
+use ckb_types::prelude::*;
+use std::cmp::{max, min};
+use std::collections::{BTreeMap, HashMap};
+
+impl CanonicalChainState {
+    pub(super) async fn report_fees(
+        &self,
+        block: &BlockView,
+        fees: &[(u64, u64, u64)],
+        write: Write,
+    ) {
+        let network = self.config.network();
+        let number = block.number() as i64;
+        let time = block_time(block);
+        let mut fee_rates = fees
+            .iter()
+            .map(|(_, fee_rate, _)| *fee_rate)
+            .collect::<Vec<_>>();
+        fee_rates.sort_unstable();
+        // The nearest-rank percentile
+        let percentile = |p: f64| {
+            let rank = (fee_rates.len() as f64 * p).ceil() as usize;
+            fee_rates
+                .get(rank.saturating_sub(1))
+                .copied()
+                .unwrap_or_default()
+        };
+        let total_fee: u64 = fees.iter().map(|(fee, _, _)| fee).sum();
+        let point = table::BlockFee {
+            network: network.clone(),
+            time,
+            number,
+            n_transactions: fees.len() as i32,
+            total_fee: total_fee as i64,
+            min_fee_rate: fee_rates.first().copied().unwrap_or_default() as i64,
+            median_fee_rate: percentile(0.5) as i64,
+            p90_fee_rate: percentile(0.9) as i64,
+        };
+
+        // Bucket the fee rates by the powers of 2
+        let mut histogram: BTreeMap<u64, (u32, u64)> = BTreeMap::new();
+        for (_, fee_rate, size) in fees {
+            let bucket = if *fee_rate == 0 {
+                0
+            } else {
+                1 << (63 - fee_rate.leading_zeros())
+            };
+            let (n_transactions, total_size) = histogram.entry(bucket).or_default();
+            *n_transactions += 1;
+            *total_size += size;
+        }
+
+        let mut queries = Vec::new();
+        if write == Write::Replace {
+            queries.push(table::BlockFee::delete_query(&network, number));
+            queries.push(table::FeeRateHistogram::delete_query(&network, number));
+        }
+        queries.push(point.insert_query());
+        for (bucket, (n_transactions, size)) in histogram {
+            let point = table::FeeRateHistogram {
+                network: network.clone(),
+                time,
+                number,
+                bucket: bucket as i64,
+                n_transactions: n_transactions as i32,
+                size: size as i64,
+            };
+            queries.push(point.insert_query());
+        }
+        for query in queries {
+            retry_send(&self.query_sender, query).await;
+        }
+    }
+}
+
+// Return the fee, the fee rate in shannons per KB, and the serialized size of each transaction
+// except the cellbase. A DAO withdrawing input counts with its compensation, as in `withdraws`.
+// The transactions with inputs not resolved are skipped.
+pub(super) fn compute_fees(
+    block: &BlockView,
+    inputs: &HashMap<OutPoint, ResolvedCell>,
+    withdraws: &HashMap<OutPoint, DaoWithdraw>,
+) -> Vec<(u64, u64, u64)> {
+    block
+        .transactions()
+        .iter()
+        .skip(1)
+        .filter_map(|transaction| {
+            let input_capacity: u64 = transaction
+                .input_pts_iter()
+                .map(|out_point| -> Option<u64> {
+                    let cell = inputs.get(&out_point)?;
+                    if is_dao_cell(&cell.output) && deposit_number(&cell.data).is_some() {
+                        withdraws.get(&out_point).map(|withdraw| withdraw.capacity)
+                    } else {
+                        Some(cell.output.capacity().unpack())
+                    }
+                })
+                .sum::<Option<u64>>()?;
+            let output_capacity: u64 = transaction
+                .outputs()
+                .into_iter()
+                .map(|output| -> u64 { output.capacity().unpack() })
+                .sum();
+            let fee = input_capacity.saturating_sub(output_capacity);
+            let size = transaction.data().serialized_size_in_block() as u64;
+            // Computed in u128, as `fee * 1000` may overflow u64
+            let fee_rate = u128::from(fee) * 1000 / u128::from(max(size, 1));
+            Some((fee, min(fee_rate, u128::from(u64::MAX)) as u64, size))
+        })
+        .collect()
+}
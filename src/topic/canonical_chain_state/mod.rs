@@ -0,0 +1,1693 @@
+mod chain_state;
+mod dao;
+mod fee;
+mod proposal;
+mod reward;
+
+use crate::address::{encode_address, script_components, AddressPrefix};
+use crate::config::{Config, MinerShareWindow};
+use crate::pool::{decode_message, PoolLabels};
+use crate::script_registry::ScriptRegistry;
+use crate::table;
+use crate::util::{retry_rpc, retry_send};
+pub use chain_state::ChainOccupancy;
+use chain_state::Reconciliation;
+use ckb_suite_rpc::ckb_jsonrpc_types::Consensus;
+use ckb_suite_rpc::{AsyncJsonrpc, Error as RpcError};
+use ckb_types::bytes::Bytes;
+use ckb_types::core::{BlockNumber, HeaderView};
+use ckb_types::core::{BlockView, EpochNumber};
+use ckb_types::packed::{Byte32, CellOutput, CellbaseWitness, OutPoint, ProposalShortId, Script};
+use ckb_types::prelude::*;
+use ckb_types::utils::compact_to_difficulty;
+use ckb_types::{H256, U256};
+use dao::analyze_dao_state;
+use fee::compute_fees;
+use proposal::{ProposalsZone, Proposer};
+use reward::{analyze_block_reward, get_block_rewards, EmissionSchedule, FINALIZATION_DELAY};
+use std::cmp::{max, min};
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet, VecDeque};
+use std::ops::Range;
+use std::str::FromStr;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+pub const PROPOSAL_WINDOW: (u64, u64) = (2, 10);
+
+// The number of blocks fetched per round trip
+const BLOCKS_BATCH_SIZE: u64 = 200;
+
+// The number of recently analyzed blocks kept to detect and roll back reorganizations
+const RECENT_BLOCKS: usize = 1000;
+
+const MILLISECONDS_PER_DAY: u64 = 24 * 60 * 60 * 1000;
+
+/// The tables derived by CanonicalChainState
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum AnalyzedTable {
+    Block,
+    Uncle,
+    TwoPCCommitment,
+    Epoch,
+    HashRate,
+    // `miner_share` and `miner_concentration`
+    MinerShare,
+    CommittedTransaction,
+    // `block_fee` and `fee_rate_histogram`
+    Fee,
+    ScriptUsage,
+    // `dao_state` and `dao_event`
+    Dao,
+    BlockReward,
+    // `chain_state` and `chain_state_reconciliation`
+    ChainState,
+    ExpiredProposal,
+}
+
+impl AnalyzedTable {
+    pub fn all() -> HashSet<Self> {
+        vec![
+            AnalyzedTable::Block,
+            AnalyzedTable::Uncle,
+            AnalyzedTable::TwoPCCommitment,
+            AnalyzedTable::Epoch,
+            AnalyzedTable::HashRate,
+            AnalyzedTable::MinerShare,
+            AnalyzedTable::CommittedTransaction,
+            AnalyzedTable::Fee,
+            AnalyzedTable::ScriptUsage,
+            AnalyzedTable::Dao,
+            AnalyzedTable::BlockReward,
+            AnalyzedTable::ChainState,
+            AnalyzedTable::ExpiredProposal,
+        ]
+        .into_iter()
+        .collect()
+    }
+}
+
+impl FromStr for AnalyzedTable {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "block" => Ok(AnalyzedTable::Block),
+            "uncle" => Ok(AnalyzedTable::Uncle),
+            "two_pc_commitment" => Ok(AnalyzedTable::TwoPCCommitment),
+            "epoch" => Ok(AnalyzedTable::Epoch),
+            "hash_rate" => Ok(AnalyzedTable::HashRate),
+            "miner_share" => Ok(AnalyzedTable::MinerShare),
+            "committed_transaction" => Ok(AnalyzedTable::CommittedTransaction),
+            "fee" => Ok(AnalyzedTable::Fee),
+            "script_usage" => Ok(AnalyzedTable::ScriptUsage),
+            "dao" => Ok(AnalyzedTable::Dao),
+            "block_reward" => Ok(AnalyzedTable::BlockReward),
+            "chain_state" => Ok(AnalyzedTable::ChainState),
+            "expired_proposal" => Ok(AnalyzedTable::ExpiredProposal),
+            _ => Err(format!("unknown table \"{}\"", s)),
+        }
+    }
+}
+
+/// Re-analyze the blocks in `from..=to`, replacing the existing rows of the chosen tables which
+/// are derived from them. Rows of the other tables and blocks are untouched, so it can run beside
+/// the live-following instance.
+#[derive(Debug, Clone)]
+pub struct Backfill {
+    pub from: BlockNumber,
+    pub to: BlockNumber,
+    pub tables: HashSet<AnalyzedTable>,
+}
+
+// How to write a row derived from a block
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Write {
+    Insert,
+    // Delete the existing rows first
+    Replace,
+    Skip,
+}
+
+fn write_mode(backfill: &Option<Backfill>, table: AnalyzedTable, number: BlockNumber) -> Write {
+    match backfill {
+        None => Write::Insert,
+        Some(backfill) => {
+            if backfill.tables.contains(&table) && (backfill.from..=backfill.to).contains(&number) {
+                Write::Replace
+            } else {
+                Write::Skip
+            }
+        }
+    }
+}
+
+// The blocks and uncles of each miner within the current window
+#[derive(Debug, Clone)]
+struct MinerShareState {
+    window: MinerShareWindow,
+    // The day or the epoch number of the window
+    key: u64,
+    // Whether the window is analyzed since its first block
+    complete: bool,
+    start_time: u64,
+    miners: HashMap<String, (u32, u32)>,
+}
+
+impl MinerShareState {
+    fn new(window: MinerShareWindow, first: &HeaderView, complete: bool) -> Self {
+        Self {
+            window,
+            key: window_key(window, first),
+            complete,
+            start_time: first.timestamp(),
+            miners: Default::default(),
+        }
+    }
+
+    fn count(&mut self, miner: &str, uncle_miners: &[String]) {
+        self.miners.entry(miner.to_string()).or_default().0 += 1;
+        for uncle_miner in uncle_miners {
+            self.miners.entry(uncle_miner.clone()).or_default().1 += 1;
+        }
+    }
+
+    fn uncount(&mut self, miner: &str, uncle_miners: &[String]) {
+        if let Some((n_blocks, _)) = self.miners.get_mut(miner) {
+            *n_blocks -= 1;
+        }
+        for uncle_miner in uncle_miners {
+            if let Some((_, n_uncles)) = self.miners.get_mut(uncle_miner) {
+                *n_uncles -= 1;
+            }
+        }
+        self.miners
+            .retain(|_, (n_blocks, n_uncles)| *n_blocks > 0 || *n_uncles > 0);
+    }
+}
+
+// A cell created by a committed transaction, with its data and the committing block
+#[derive(Clone)]
+struct ResolvedCell {
+    output: CellOutput,
+    data: Bytes,
+    block_hash: Byte32,
+}
+
+// The cells created by the recently analyzed transactions, to resolve inputs without RPC calls.
+// The transactions of rolled back blocks are kept, as their outputs are only spent if they are
+// committed again.
+#[derive(Default)]
+struct RecentOutputs {
+    cells: HashMap<Byte32, Vec<ResolvedCell>>,
+    order: VecDeque<Byte32>,
+}
+
+impl RecentOutputs {
+    fn insert(&mut self, transaction_hash: Byte32, cells: Vec<ResolvedCell>, limit: usize) {
+        if self.cells.insert(transaction_hash.clone(), cells).is_none() {
+            self.order.push_back(transaction_hash);
+        }
+        while self.order.len() > limit {
+            let oldest = self.order.pop_front().unwrap();
+            self.cells.remove(&oldest);
+        }
+    }
+
+    fn get(&self, out_point: &OutPoint) -> Option<ResolvedCell> {
+        let index: u32 = out_point.index().unpack();
+        self.cells
+            .get(&out_point.tx_hash())
+            .and_then(|cells| cells.get(index as usize))
+            .cloned()
+    }
+}
+
+// A recently analyzed block, with what is needed to undo its analysis if it is reorganized
+struct RecentBlock {
+    number: BlockNumber,
+    hash: Byte32,
+    timestamp: u64,
+    // The difficulty of the block plus the ones of its uncles
+    work: f64,
+    // The uncles whose rows were written
+    uncle_hashes: Vec<Byte32>,
+    // The proposals committed by the block, removed from their proposals zones
+    committed: Vec<(BlockNumber, ProposalShortId, Proposer)>,
+    // The proposals zone pruned after the block, whose proposals expired
+    pruned: Option<(BlockNumber, ProposalsZone)>,
+    // The epoch number and uncles total before the block
+    epoch: (EpochNumber, u32),
+    miner: String,
+    uncle_miners: Vec<String>,
+    // The miner share windows ended by the block
+    ended_miner_shares: Vec<MinerShareState>,
+    // The chain state before the block
+    occupancy: Option<(BlockNumber, ChainOccupancy)>,
+    // Loaded before the analysis starts, whose state before the block is unknown
+    warm_up: bool,
+}
+
+pub struct CanonicalChainState {
+    config: Config,
+    query_sender: crossbeam::channel::Sender<String>,
+    jsonrpc: AsyncJsonrpc,
+    start_number: BlockNumber,
+    proposals_zones: HashMap<BlockNumber, ProposalsZone>,
+    // The current epoch number and its uncles total so far
+    epoch: (EpochNumber, u32),
+    recent_blocks: VecDeque<RecentBlock>,
+    miner_shares: Vec<MinerShareState>,
+    recent_outputs: RecentOutputs,
+    script_registry: ScriptRegistry,
+    // The chain state after the last analyzed block, unknown unless the analysis starts from the
+    // genesis or is resumed
+    occupancy: Option<(BlockNumber, ChainOccupancy)>,
+    indexer: Option<AsyncJsonrpc>,
+    reconciliation: Reconciliation,
+    // Provides the parameters of the epoch adjustment
+    consensus: Option<Consensus>,
+    backfill: Option<Backfill>,
+    pool_labels: Arc<PoolLabels>,
+}
+
+impl CanonicalChainState {
+    pub fn new(
+        config: Config,
+        jsonrpc: AsyncJsonrpc,
+        query_sender: crossbeam::channel::Sender<String>,
+        start_number: BlockNumber,
+    ) -> Self {
+        let pool_labels = Arc::new(PoolLabels::from_config(&config));
+        let script_registry = ScriptRegistry::from_config(&config);
+        let indexer = config.node.indexer_rpc_url.as_ref().map(|url| {
+            let mut client_config = config.rpc_client_config();
+            client_config.indexer_only = true;
+            AsyncJsonrpc::connect_with_config(&[url], client_config)
+        });
+        Self {
+            config,
+            jsonrpc,
+            query_sender,
+            start_number,
+            proposals_zones: Default::default(),
+            epoch: (0, 0),
+            recent_blocks: Default::default(),
+            miner_shares: Vec::new(),
+            recent_outputs: Default::default(),
+            script_registry,
+            occupancy: None,
+            indexer,
+            reconciliation: Default::default(),
+            consensus: None,
+            backfill: None,
+            pool_labels,
+        }
+    }
+
+    /// Analyze the backfill range and stop, instead of following the chain from `start_number`
+    pub fn with_backfill(mut self, backfill: Backfill) -> Self {
+        self.backfill = Some(backfill);
+        self
+    }
+
+    /// Resume the chain state after the given block
+    pub fn with_chain_state(mut self, number: BlockNumber, occupancy: ChainOccupancy) -> Self {
+        self.occupancy = Some((number, occupancy));
+        self
+    }
+
+    pub async fn run(&mut self) {
+        if let Err(err) = self.analyze_blocks().await {
+            log::error!("stop analyzing the canonical chain, error: {}", err);
+        }
+    }
+
+    // Blocks are fetched, and their block and uncle rows computed, by concurrent tasks; the
+    // batches are then consumed in block order, where the state carried across blocks, the
+    // proposals zones and the epoch accumulation, is updated.
+    //
+    // Every block is checked to extend the previous analyzed one. Otherwise the chain was
+    // reorganized: the analysis is rolled back to the common ancestor and restarted after it.
+    async fn analyze_blocks(&mut self) -> Result<(), RpcError> {
+        let (mut start_number, end_number) = match self.backfill.clone() {
+            // One block more, to tell whether the epoch and the miner share windows end at `to`,
+            // and up to the block finalizing the reward of `to`
+            Some(backfill) => {
+                let end_number = if backfill.tables.contains(&AnalyzedTable::BlockReward) {
+                    backfill.to + FINALIZATION_DELAY + 1
+                } else {
+                    backfill.to + 2
+                };
+                (self.backfill_start_number(&backfill).await?, end_number)
+            }
+            None => (max(1, self.start_number + 1), BlockNumber::MAX),
+        };
+        let jsonrpc = self.jsonrpc.clone();
+        let parent: HeaderView = retry_rpc(|| jsonrpc.get_header_by_number(start_number - 1))
+            .await?
+            .into();
+        self.start_after(&parent);
+        if self.backfill.is_none() {
+            self.warm_up_recent_blocks(start_number).await?;
+        }
+        self.consensus = Some(retry_rpc(|| jsonrpc.get_consensus()).await?);
+        // The chain state is counted from the genesis unless resumed
+        let from_genesis = match &self.backfill {
+            Some(backfill) => backfill.from <= 1,
+            None => start_number == 1,
+        };
+        if from_genesis && self.occupancy.is_none() {
+            self.count_genesis_cells().await?;
+        }
+
+        let total = if self.backfill.is_some() {
+            end_number.saturating_sub(start_number)
+        } else {
+            retry_rpc(|| jsonrpc.get_tip_block_number())
+                .await?
+                .saturating_sub(start_number)
+        };
+        let mut progress = (total, 0, Instant::now());
+        while let Some(ancestor) = self
+            .analyze_range(start_number..end_number, &mut progress)
+            .await?
+        {
+            self.rollback(ancestor).await?;
+            start_number = ancestor + 1;
+        }
+        Ok(())
+    }
+
+    // Analyze the blocks in order until the end of the range, or until the chain is found
+    // reorganized, then return the common ancestor.
+    async fn analyze_range(
+        &mut self,
+        numbers: Range<BlockNumber>,
+        progress: &mut (u64, u64, Instant),
+    ) -> Result<Option<BlockNumber>, RpcError> {
+        let (batch_sender, mut batch_receiver) =
+            tokio::sync::mpsc::channel(max(1, self.config.backfill_concurrency));
+        let consensus = self
+            .consensus
+            .as_ref()
+            .expect("get consensus before analyzing");
+        let context = FetchContext {
+            network: self.config.network(),
+            backfill: self.backfill.clone(),
+            pool_labels: Arc::clone(&self.pool_labels),
+            address_prefix: AddressPrefix::from_chain_id(&consensus.id),
+            emission: EmissionSchedule::from_consensus(consensus),
+        };
+        tokio::spawn(fetch_batches(
+            self.jsonrpc.clone(),
+            context,
+            numbers,
+            self.config.canonical_chain_lag,
+            batch_sender,
+        ));
+
+        while let Some(batch) = batch_receiver.recv().await {
+            let BlockBatch {
+                blocks,
+                queries,
+                uncle_miners,
+            } = batch.await.expect("fetch blocks task panicked")?;
+            let mut parent_hash = self.recent_blocks.back().map(|recent| recent.hash.clone());
+            for block in blocks.iter() {
+                if parent_hash.map_or(false, |parent_hash| parent_hash != block.parent_hash()) {
+                    log::info!(
+                        "block #{}({:#x}) does not extend the analyzed chain",
+                        block.number(),
+                        block.hash()
+                    );
+                    return self.locate_ancestor().await.map(Some);
+                }
+                parent_hash = Some(block.hash());
+            }
+
+            for query in queries {
+                retry_send(&self.query_sender, query).await;
+            }
+            for block in blocks {
+                let epoch = self.epoch;
+                let work = block_work(&block);
+                let miner = format!("{:#x}", extract_miner_lock(&block).args());
+                let block_uncle_miners = block
+                    .uncle_hashes()
+                    .into_iter()
+                    .filter_map(|uncle_hash| uncle_miners.get(&uncle_hash).cloned())
+                    .collect::<Vec<_>>();
+                let (committed, pruned) = self
+                    .analyze_block_transactions(&block, &miner, &uncle_miners)
+                    .await;
+                let occupancy = self.occupancy;
+                self.analyze_inputs(&block).await;
+                self.analyze_epoch(&block).await;
+                let ended_miner_shares = self
+                    .analyze_miner_shares(&block, &miner, &block_uncle_miners)
+                    .await;
+
+                let uncle_hashes =
+                    if write_mode(&self.backfill, AnalyzedTable::Uncle, block.number())
+                        == Write::Skip
+                    {
+                        Vec::new()
+                    } else {
+                        block.uncle_hashes().into_iter().collect()
+                    };
+                self.recent_blocks.push_back(RecentBlock {
+                    number: block.number(),
+                    hash: block.hash(),
+                    timestamp: block.timestamp(),
+                    work,
+                    uncle_hashes,
+                    committed,
+                    pruned,
+                    epoch,
+                    miner,
+                    uncle_miners: block_uncle_miners,
+                    ended_miner_shares,
+                    occupancy,
+                    warm_up: false,
+                });
+                if self.recent_blocks.len() > self.recent_blocks_capacity() {
+                    self.recent_blocks.pop_front();
+                }
+                self.analyze_hash_rate(&block).await;
+
+                // Only prompt while catching up with the tip observed at start
+                let (total, processed, start) = &mut *progress;
+                *processed += 1;
+                if *processed <= *total {
+                    prompt_progress(*total, *processed, *start);
+                }
+            }
+        }
+        Ok(None)
+    }
+
+    // The state carried across blocks when the analysis starts after `parent`. The epoch and the
+    // miner share windows which `parent` is in are partial.
+    fn start_after(&mut self, parent: &HeaderView) {
+        self.epoch = (parent.epoch().number(), 0);
+        self.miner_shares = self
+            .config
+            .miner_share_windows
+            .iter()
+            .map(|window| MinerShareState::new(*window, parent, false))
+            .collect();
+    }
+
+    // Load the blocks before `start_number` into the recent blocks, so that the hash rate windows
+    // are full since the first analyzed block, and the reorganizations of them are rolled back
+    async fn warm_up_recent_blocks(&mut self, start_number: BlockNumber) -> Result<(), RpcError> {
+        let jsonrpc = self.jsonrpc.clone();
+        let mut number = max(1, start_number.saturating_sub(self.max_hash_rate_window()));
+        while number < start_number {
+            let numbers = number..min(number + BLOCKS_BATCH_SIZE, start_number);
+            let blocks = retry_rpc(|| jsonrpc.get_blocks_by_numbers(numbers.clone())).await?;
+            for (number, block) in numbers.clone().zip(blocks) {
+                let block: BlockView = block
+                    .ok_or_else(|| RpcError::NotFound(format!("block #{}", number)))?
+                    .into();
+                self.recent_blocks.push_back(RecentBlock {
+                    number: block.number(),
+                    hash: block.hash(),
+                    timestamp: block.timestamp(),
+                    work: block_work(&block),
+                    uncle_hashes: block.uncle_hashes().into_iter().collect(),
+                    committed: Vec::new(),
+                    pruned: None,
+                    epoch: self.epoch,
+                    miner: String::new(),
+                    uncle_miners: Vec::new(),
+                    ended_miner_shares: Vec::new(),
+                    occupancy: None,
+                    warm_up: true,
+                });
+            }
+            number = numbers.end;
+        }
+        Ok(())
+    }
+
+    // The highest analyzed block which is still on the canonical chain. Beyond the recent blocks,
+    // the reorganized chain is walked back via `get_fork_block`.
+    async fn locate_ancestor(&self) -> Result<BlockNumber, RpcError> {
+        for recent in self.recent_blocks.iter().rev() {
+            if self.is_canonical(recent.number, &recent.hash).await? {
+                return Ok(recent.number);
+            }
+        }
+
+        let (mut number, mut hash) = match self.recent_blocks.front() {
+            Some(oldest) => (oldest.number, oldest.hash.clone()),
+            None => return Ok(self.start_number),
+        };
+        log::warn!(
+            "reorganization is deeper than the recent {} blocks, walk back the reorganized chain",
+            self.recent_blocks.len()
+        );
+        // The genesis is shared
+        while number > 1 {
+            let fork_block = retry_rpc(|| self.jsonrpc.get_fork_block(hash.clone())).await;
+            let block: BlockView = match fork_block {
+                Ok(block) => block.into(),
+                Err(err) if err.is_not_found() => {
+                    log::error!(
+                        "reorganized block #{}({:#x}) is unknown to the node, roll back to #{}; \
+                        backfill the blocks before it",
+                        number,
+                        hash,
+                        number - 1
+                    );
+                    return Ok(number - 1);
+                }
+                Err(err) => return Err(err),
+            };
+            number -= 1;
+            hash = block.parent_hash();
+            if self.is_canonical(number, &hash).await? {
+                return Ok(number);
+            }
+        }
+        Ok(0)
+    }
+
+    async fn is_canonical(&self, number: BlockNumber, hash: &Byte32) -> Result<bool, RpcError> {
+        match retry_rpc(|| self.jsonrpc.get_block_hash(number)).await {
+            Ok(canonical) => Ok(&canonical.pack() == hash),
+            // The chain is shorter now
+            Err(err) if err.is_not_found() => Ok(false),
+            Err(err) => Err(err),
+        }
+    }
+
+    // Undo the analysis of the blocks after the ancestor, and delete their rows
+    async fn rollback(&mut self, ancestor: BlockNumber) -> Result<(), RpcError> {
+        log::info!("roll back the analyzed blocks after #{}", ancestor);
+        self.jsonrpc.invalidate_headers_since(ancestor + 1);
+
+        // The block which the state carried across blocks is restored after
+        let mut restored = None;
+        let mut warm_up = false;
+        let mut uncle_hashes = Vec::new();
+        while self
+            .recent_blocks
+            .back()
+            .map_or(false, |recent| recent.number > ancestor)
+        {
+            let recent = self.recent_blocks.pop_back().unwrap();
+            restored = Some(recent.number - 1);
+            warm_up |= recent.warm_up;
+            self.proposals_zones.remove(&recent.number);
+            if let Some((number, proposals_zone)) = recent.pruned {
+                self.proposals_zones.insert(number, proposals_zone);
+            }
+            for (number, proposal_id, proposer) in recent.committed {
+                self.proposals_zones
+                    .entry(number)
+                    .or_default()
+                    .insert(proposal_id, proposer);
+            }
+            self.epoch = recent.epoch;
+            self.occupancy = recent.occupancy;
+            self.reconciliation = Default::default();
+            let mut ended_miner_shares = recent.ended_miner_shares;
+            for state in self.miner_shares.iter_mut() {
+                match ended_miner_shares
+                    .iter()
+                    .position(|ended| ended.window == state.window)
+                {
+                    Some(index) => *state = ended_miner_shares.swap_remove(index),
+                    None => state.uncount(&recent.miner, &recent.uncle_miners),
+                }
+            }
+            uncle_hashes.extend(
+                recent
+                    .uncle_hashes
+                    .iter()
+                    .map(|uncle_hash| format!("{:#x}", uncle_hash)),
+            );
+        }
+        // Deeper than the recent blocks or the analysis, resync the state as when the analysis
+        // starts after the ancestor
+        if warm_up || restored.map_or(false, |restored| restored > ancestor) {
+            log::warn!(
+                "resync the analysis after #{}; the chain state is unknown since, backfill \
+                chain_state from the genesis",
+                ancestor
+            );
+            let jsonrpc = self.jsonrpc.clone();
+            let parent: HeaderView = retry_rpc(|| jsonrpc.get_header_by_number(ancestor))
+                .await?
+                .into();
+            self.start_after(&parent);
+            self.proposals_zones.clear();
+            self.occupancy = None;
+        }
+        let network = self.config.network();
+        let mut queries = Vec::new();
+        if !uncle_hashes.is_empty() {
+            queries.push(table::Uncle::delete_by_hashes_query(
+                &network,
+                &uncle_hashes,
+            ));
+        }
+        // The backfill replaces the rows when rewriting them, and must not touch the rows out of
+        // its range
+        if self.backfill.is_none() {
+            let since = (ancestor + 1) as i64;
+            queries.push(table::Block::delete_since_query(&network, since));
+            queries.push(table::TwoPCCommitment::delete_since_query(&network, since));
+            queries.push(table::CommittedTransaction::delete_since_query(
+                &network, since,
+            ));
+            queries.push(table::BlockFee::delete_since_query(&network, since));
+            queries.push(table::FeeRateHistogram::delete_since_query(&network, since));
+            queries.push(table::ScriptUsage::delete_since_query(&network, since));
+            queries.push(table::DaoState::delete_since_query(&network, since));
+            queries.push(table::DaoEvent::delete_since_query(&network, since));
+            queries.push(table::BlockReward::delete_since_query(&network, since));
+            queries.push(table::ExpiredProposal::delete_since_query(&network, since));
+            queries.push(table::ChainState::delete_since_query(&network, since));
+            queries.push(table::ChainStateReconciliation::delete_since_query(
+                &network, since,
+            ));
+            queries.push(table::HashRate::delete_since_query(
+                &network,
+                since,
+                self.epoch.0 as i64,
+            ));
+            queries.push(table::Epoch::delete_since_query(
+                &network,
+                self.epoch.0 as i64,
+            ));
+            // The windows ended at the ancestor were reported by the block after it
+            queries.push(table::MinerShare::delete_since_query(
+                &network,
+                ancestor as i64,
+            ));
+            queries.push(table::MinerConcentration::delete_since_query(
+                &network,
+                ancestor as i64,
+            ));
+        }
+        for query in queries {
+            retry_send(&self.query_sender, query).await;
+        }
+        Ok(())
+    }
+
+    // Start early enough to rebuild the state carried across blocks: the proposals zones of the
+    // proposal window before `from`, the hash rate windows, and the uncles total of the epoch
+    // which `from - 1` is in
+    async fn backfill_start_number(&self, backfill: &Backfill) -> Result<BlockNumber, RpcError> {
+        let jsonrpc = &self.jsonrpc;
+        let from = max(1, backfill.from);
+        let header: HeaderView = retry_rpc(|| jsonrpc.get_header_by_number(from - 1))
+            .await?
+            .into();
+        let epoch = retry_rpc(|| jsonrpc.get_epoch_by_number(header.epoch().number())).await?;
+        Ok(max(
+            1,
+            min(
+                from.saturating_sub(max(PROPOSAL_WINDOW.1, self.max_hash_rate_window())),
+                epoch.start_number.value(),
+            ),
+        ))
+    }
+
+    // Resolve the inputs of the block's transactions, to analyze the committed transactions, the
+    // fees, the script usages and the DAO events
+    async fn analyze_inputs(&mut self, block: &BlockView) {
+        // Cache the outputs first, for the inputs spending the outputs of the same block
+        for transaction in block.transactions() {
+            let cells = transaction
+                .outputs_with_data_iter()
+                .map(|(output, data)| ResolvedCell {
+                    output,
+                    data,
+                    block_hash: block.hash(),
+                })
+                .collect();
+            self.recent_outputs.insert(
+                transaction.hash(),
+                cells,
+                self.config.recent_transactions_cache_size,
+            );
+        }
+
+        let fee_write = write_mode(&self.backfill, AnalyzedTable::Fee, block.number());
+        let script_usage_write =
+            write_mode(&self.backfill, AnalyzedTable::ScriptUsage, block.number());
+        let dao_write = write_mode(&self.backfill, AnalyzedTable::Dao, block.number());
+        let chain_state_write =
+            write_mode(&self.backfill, AnalyzedTable::ChainState, block.number());
+        let committed_transaction_write = write_mode(
+            &self.backfill,
+            AnalyzedTable::CommittedTransaction,
+            block.number(),
+        );
+        if fee_write == Write::Skip
+            && committed_transaction_write == Write::Skip
+            && script_usage_write == Write::Skip
+            && dao_write == Write::Skip
+            && chain_state_write == Write::Skip
+        {
+            return;
+        }
+        let inputs = self.resolve_inputs(block).await;
+        if committed_transaction_write != Write::Skip {
+            self.report_committed_transactions(block, &inputs, committed_transaction_write)
+                .await;
+        }
+        // Both the fees and the DAO events need the headers of the phase 2 inputs
+        let withdraws = if fee_write != Write::Skip || dao_write != Write::Skip {
+            self.get_dao_withdraws(block, &inputs).await
+        } else {
+            HashMap::new()
+        };
+        if fee_write != Write::Skip {
+            let fees = compute_fees(block, &inputs, &withdraws);
+            self.report_fees(block, &fees, fee_write).await;
+        }
+        if script_usage_write != Write::Skip {
+            self.report_script_usages(block, &inputs, script_usage_write)
+                .await;
+        }
+        if dao_write != Write::Skip {
+            self.report_dao_events(block, &inputs, &withdraws, dao_write)
+                .await;
+        }
+        if chain_state_write != Write::Skip {
+            self.analyze_chain_state(block, &inputs, chain_state_write)
+                .await;
+        }
+    }
+
+    // Resolve the cells spent by the block's transactions except the cellbase, from the recent
+    // outputs, or via RPC. The cells of the transactions failed to get are skipped.
+    async fn resolve_inputs(&self, block: &BlockView) -> HashMap<OutPoint, ResolvedCell> {
+        let out_points = block
+            .transactions()
+            .iter()
+            .skip(1)
+            .flat_map(|transaction| transaction.input_pts_iter())
+            .collect::<Vec<_>>();
+        let missing = out_points
+            .iter()
+            .filter(|out_point| self.recent_outputs.get(out_point).is_none())
+            .map(|out_point| out_point.tx_hash())
+            .collect::<HashSet<_>>();
+        let handles = missing
+            .into_iter()
+            .map(|transaction_hash| {
+                let jsonrpc = self.jsonrpc.clone();
+                tokio::spawn(async move {
+                    let transaction =
+                        match retry_rpc(|| jsonrpc.get_transaction(transaction_hash.clone())).await
+                        {
+                            Ok(transaction) => transaction,
+                            Err(err) => {
+                                log::error!(
+                                    "failed to get the spent transaction {:#x}, error: {}",
+                                    transaction_hash,
+                                    err
+                                );
+                                return (transaction_hash, Vec::new());
+                            }
+                        };
+                    let block_hash: H256 = match transaction.tx_status.block_hash {
+                        Some(block_hash) => block_hash,
+                        None => {
+                            log::error!(
+                                "the spent transaction {:#x} is not committed",
+                                transaction_hash
+                            );
+                            return (transaction_hash, Vec::new());
+                        }
+                    };
+                    let inner = transaction.transaction.inner;
+                    let cells = inner
+                        .outputs
+                        .into_iter()
+                        .zip(inner.outputs_data)
+                        .map(|(output, data)| ResolvedCell {
+                            output: output.into(),
+                            data: data.into_bytes(),
+                            block_hash: block_hash.pack(),
+                        })
+                        .collect::<Vec<_>>();
+                    (transaction_hash, cells)
+                })
+            })
+            .collect::<Vec<_>>();
+        let mut fetched = HashMap::new();
+        for handle in handles {
+            let (transaction_hash, cells) = handle.await.expect("get transaction task panicked");
+            fetched.insert(transaction_hash, cells);
+        }
+
+        out_points
+            .into_iter()
+            .filter_map(|out_point| {
+                let cell = self.recent_outputs.get(&out_point).or_else(|| {
+                    let index: u32 = out_point.index().unpack();
+                    fetched
+                        .get(&out_point.tx_hash())
+                        .and_then(|cells| cells.get(index as usize))
+                        .cloned()
+                })?;
+                Some((out_point, cell))
+            })
+            .collect()
+    }
+
+    async fn report_committed_transactions(
+        &self,
+        block: &BlockView,
+        inputs: &HashMap<OutPoint, ResolvedCell>,
+        write: Write,
+    ) {
+        let network = self.config.network();
+        if write == Write::Replace {
+            let query = table::CommittedTransaction::delete_query(&network, block.number() as i64);
+            retry_send(&self.query_sender, query).await;
+        }
+        for point in analyze_committed_transactions(&network, block, inputs) {
+            retry_send(&self.query_sender, point.insert_query()).await;
+        }
+    }
+
+    // Count the cells created and consumed by the block per script
+    async fn report_script_usages(
+        &self,
+        block: &BlockView,
+        inputs: &HashMap<OutPoint, ResolvedCell>,
+        write: Write,
+    ) {
+        let network = self.config.network();
+        let number = block.number() as i64;
+        let time = block_time(block);
+        let created = block
+            .transactions()
+            .iter()
+            .flat_map(|transaction| transaction.outputs().into_iter())
+            .collect::<Vec<_>>();
+
+        // (script type, code hash, hash type) => (created, consumed)
+        let mut usages: BTreeMap<(&str, String, String), (u32, u32)> = BTreeMap::new();
+        let outputs = created
+            .iter()
+            .map(|output| (output, false))
+            .chain(inputs.values().map(|cell| (&cell.output, true)));
+        for (output, consumed) in outputs {
+            let lock = Some(("lock", output.lock()));
+            let type_ = output.type_().to_opt().map(|type_| ("type", type_));
+            for (script_type, script) in lock.into_iter().chain(type_) {
+                let (code_hash, hash_type, _) = script_components(&script);
+                let (n_created, n_consumed) = usages
+                    .entry((script_type, code_hash, hash_type))
+                    .or_default();
+                if consumed {
+                    *n_consumed += 1;
+                } else {
+                    *n_created += 1;
+                }
+            }
+        }
+
+        let mut queries = Vec::new();
+        if write == Write::Replace {
+            queries.push(table::ScriptUsage::delete_query(&network, number));
+        }
+        for ((script_type, code_hash, hash_type), (n_created, n_consumed)) in usages {
+            let name = self
+                .script_registry
+                .name(&code_hash, &hash_type)
+                .unwrap_or_default()
+                .to_string();
+            let point = table::ScriptUsage {
+                network: network.clone(),
+                time,
+                number,
+                script_type: script_type.to_string(),
+                code_hash,
+                hash_type,
+                name,
+                n_created: n_created as i32,
+                n_consumed: n_consumed as i32,
+            };
+            queries.push(point.insert_query());
+        }
+        for query in queries {
+            retry_send(&self.query_sender, query).await;
+        }
+    }
+
+    fn max_hash_rate_window(&self) -> u64 {
+        self.config
+            .hash_rate_windows
+            .iter()
+            .copied()
+            .max()
+            .unwrap_or(0)
+    }
+
+    // Keep enough blocks to cover the hash rate windows
+    fn recent_blocks_capacity(&self) -> usize {
+        max(RECENT_BLOCKS, self.max_hash_rate_window() as usize + 1)
+    }
+
+    // Estimate the hash rate over the windows of the recent blocks ending at the block, as the
+    // work of the blocks and their uncles divided by the elapsed time
+    async fn analyze_hash_rate(&self, block: &BlockView) {
+        let write = write_mode(&self.backfill, AnalyzedTable::HashRate, block.number());
+        if write == Write::Skip {
+            return;
+        }
+        let time = block_time(block);
+        let analyzed = self.recent_blocks.len();
+        for window_size in self.config.hash_rate_windows.iter() {
+            let window_size = *window_size as usize;
+            if window_size == 0 || analyzed <= window_size {
+                continue;
+            }
+            let work: f64 = self
+                .recent_blocks
+                .range(analyzed - window_size..)
+                .map(|recent| recent.work)
+                .sum();
+            let window_start = &self.recent_blocks[analyzed - window_size - 1];
+            let duration = block.timestamp().saturating_sub(window_start.timestamp);
+            let point = table::HashRate {
+                network: self.config.network(),
+                time,
+                number: block.number() as i64,
+                epoch: block.epoch().number() as i64,
+                window_size: window_size as i32,
+                per_epoch: false,
+                duration: duration as i64,
+                hash_rate: work * 1000.0 / max(duration, 1) as f64,
+            };
+            if write == Write::Replace {
+                retry_send(&self.query_sender, point.delete_query()).await;
+            }
+            retry_send(&self.query_sender, point.insert_query()).await;
+        }
+    }
+
+    // Count the block and its uncles into the miner share windows, and report the windows ended by
+    // the block. Returns the ended windows, to restore them on rollback.
+    async fn analyze_miner_shares(
+        &mut self,
+        block: &BlockView,
+        miner: &str,
+        uncle_miners: &[String],
+    ) -> Vec<MinerShareState> {
+        let header = block.header();
+        // The ended windows' rows belong to their last block, the previous one
+        let write = write_mode(
+            &self.backfill,
+            AnalyzedTable::MinerShare,
+            block.number().saturating_sub(1),
+        );
+        let mut ended_miner_shares = Vec::new();
+        for index in 0..self.miner_shares.len() {
+            let window = self.miner_shares[index].window;
+            if self.miner_shares[index].key != window_key(window, &header) {
+                let ended = std::mem::replace(
+                    &mut self.miner_shares[index],
+                    MinerShareState::new(window, &header, true),
+                );
+                // The window which the analysis starts within is partial
+                if ended.complete && write != Write::Skip {
+                    self.report_miner_share(&ended, write).await;
+                }
+                ended_miner_shares.push(ended);
+            }
+            self.miner_shares[index].count(miner, uncle_miners);
+        }
+        ended_miner_shares
+    }
+
+    // Report the shares of the miners and the concentration of a window ended at the last
+    // analyzed block
+    async fn report_miner_share(&self, ended: &MinerShareState, write: Write) {
+        let last = match self.recent_blocks.back() {
+            Some(last) => last,
+            None => return,
+        };
+        let network = self.config.network();
+        let period = window_name(ended.window);
+        let time = to_time(last.timestamp);
+        let start_time = to_time(ended.start_time);
+
+        // Sorted by the mined blocks and uncles, descending
+        let mut miners = ended.miners.iter().collect::<Vec<_>>();
+        miners.sort_by_key(|(miner, (n_blocks, n_uncles))| {
+            (std::cmp::Reverse(n_blocks + n_uncles), miner.to_string())
+        });
+        let n_blocks: u32 = miners.iter().map(|(_, (n_blocks, _))| n_blocks).sum();
+        let n_uncles: u32 = miners.iter().map(|(_, (_, n_uncles))| n_uncles).sum();
+        let share = |n: u32| n as f64 / max(1, n_blocks + n_uncles) as f64;
+        let shares = miners
+            .iter()
+            .map(|(_, (n_blocks, n_uncles))| share(n_blocks + n_uncles))
+            .collect::<Vec<_>>();
+        let hhi = shares.iter().map(|share| share * share).sum();
+        let mut cumulative_share = 0.0;
+        let nakamoto_coefficient = shares
+            .iter()
+            .take_while(|share| {
+                let below_half = cumulative_share <= 0.5;
+                cumulative_share += *share;
+                below_half
+            })
+            .count();
+        let (top_miner, top_share) = miners
+            .first()
+            .map(|(miner, _)| (miner.to_string(), shares[0]))
+            .unwrap_or_default();
+        let alert = top_share > self.config.miner_share_alert_threshold;
+        if alert {
+            log::warn!(
+                "miner {} mined {:.1}% of the blocks and uncles of the {} ended at #{}",
+                top_miner,
+                top_share * 100.0,
+                period,
+                last.number,
+            );
+        }
+
+        let mut queries = Vec::new();
+        let concentration = table::MinerConcentration {
+            network: network.clone(),
+            time,
+            start_time,
+            period: period.to_string(),
+            number: last.number as i64,
+            n_blocks: n_blocks as i32,
+            n_uncles: n_uncles as i32,
+            n_miners: miners.len() as i32,
+            hhi,
+            nakamoto_coefficient: nakamoto_coefficient as i32,
+            top_miner,
+            top_share,
+            alert,
+        };
+        if write == Write::Replace {
+            queries.push(table::MinerShare::delete_window_query(
+                &network,
+                period,
+                last.number as i64,
+            ));
+            queries.push(concentration.delete_query());
+        }
+        queries.push(concentration.insert_query());
+        for ((miner, (n_blocks, n_uncles)), share) in miners.iter().zip(shares) {
+            let point = table::MinerShare {
+                network: network.clone(),
+                time,
+                start_time,
+                period: period.to_string(),
+                number: last.number as i64,
+                miner: miner.to_string(),
+                n_blocks: *n_blocks as i32,
+                n_uncles: *n_uncles as i32,
+                share,
+            };
+            queries.push(point.insert_query());
+        }
+        for query in queries {
+            retry_send(&self.query_sender, query).await;
+        }
+    }
+
+    async fn analyze_epoch(&mut self, block: &BlockView) {
+        let (current_epoch_number, current_epoch_uncles_total) = self.epoch;
+        if current_epoch_number != block.epoch().number() {
+            // The ended epoch's rows belong to its last block, the previous one
+            let last_number = block.number() - 1;
+            let write = write_mode(&self.backfill, AnalyzedTable::Epoch, last_number);
+            let hash_rate_write = write_mode(&self.backfill, AnalyzedTable::HashRate, last_number);
+            if write != Write::Skip || hash_rate_write != Write::Skip {
+                if let Err(err) = self
+                    .analyze_ended_epoch(
+                        current_epoch_number,
+                        current_epoch_uncles_total,
+                        write,
+                        hash_rate_write,
+                    )
+                    .await
+                {
+                    log::error!(
+                        "skip analyzing the ended epoch {}, error: {}",
+                        current_epoch_number,
+                        err
+                    );
+                }
+            }
+
+            self.epoch = (block.epoch().number(), 0);
+        }
+
+        // Sum epoch uncles total
+        self.epoch.1 += block.uncle_hashes().len() as u32;
+    }
+
+    async fn analyze_ended_epoch(
+        &self,
+        epoch_number: EpochNumber,
+        uncles_total: u32,
+        write: Write,
+        hash_rate_write: Write,
+    ) -> Result<(), RpcError> {
+        let jsonrpc = &self.jsonrpc;
+        let epoch = retry_rpc(|| jsonrpc.get_epoch_by_number(epoch_number)).await?;
+        let start_number = epoch.start_number.value();
+        let length = epoch.length.value();
+        let end_number = start_number + length - 1;
+        let headers =
+            retry_rpc(|| jsonrpc.get_headers_by_numbers(vec![start_number, end_number])).await?;
+        let header = |index: usize, number: BlockNumber| {
+            headers
+                .get(index)
+                .cloned()
+                .flatten()
+                .map(HeaderView::from)
+                .ok_or_else(|| RpcError::NotFound(format!("header #{}", number)))
+        };
+        let start_header = header(0, start_number)?;
+        let end_header = header(1, end_number)?;
+        let time = to_time(end_header.timestamp());
+        let duration = end_header
+            .timestamp()
+            .saturating_sub(start_header.timestamp());
+
+        // The inputs and outputs of the epoch adjustment made at the end of this epoch
+        let consensus = self
+            .consensus
+            .as_ref()
+            .expect("get consensus before analyzing");
+        let compact_target = epoch.compact_target.value();
+        let difficulty = compact_to_difficulty(compact_target);
+        let difficulty_change = if epoch_number == 0 {
+            1.0
+        } else {
+            let previous = retry_rpc(|| jsonrpc.get_epoch_by_number(epoch_number - 1)).await?;
+            let previous_difficulty = compact_to_difficulty(previous.compact_target.value());
+            ratio(&difficulty, &previous_difficulty)
+        };
+        let next_epoch = retry_rpc(|| jsonrpc.get_epoch_by_number(epoch_number + 1)).await?;
+        let uncle_rate_target = ratio(
+            consensus.orphan_rate_target.numer(),
+            consensus.orphan_rate_target.denom(),
+        );
+        let point = table::Epoch {
+            time,
+            network: self.config.network(),
+            number: epoch_number as i64,
+            length: length as i32,
+            duration: duration as i32,
+            n_uncles: uncles_total as i32,
+            compact_target: compact_target as i64,
+            difficulty: difficulty.to_string(),
+            difficulty_change,
+            target_duration: consensus.epoch_duration_target.value() as i64 * 1000,
+            adjusted_length: next_epoch.length.value() as i32,
+            uncle_rate_target,
+        };
+        if write == Write::Replace {
+            retry_send(&self.query_sender, point.delete_query()).await;
+        }
+        if write != Write::Skip {
+            retry_send(&self.query_sender, point.insert_query()).await;
+        }
+
+        // ckb estimates the hash rate as `difficulty * (length + uncles) / duration`
+        let hash_rate = u256_to_f64(&difficulty) * (length + uncles_total as u64) as f64 * 1000.0
+            / max(duration, 1) as f64;
+        let point = table::HashRate {
+            network: self.config.network(),
+            time,
+            number: end_number as i64,
+            epoch: epoch_number as i64,
+            window_size: length as i32,
+            per_epoch: true,
+            duration: duration as i64,
+            hash_rate,
+        };
+        if hash_rate_write == Write::Replace {
+            retry_send(&self.query_sender, point.delete_query()).await;
+        }
+        if hash_rate_write != Write::Skip {
+            retry_send(&self.query_sender, point.insert_query()).await;
+        }
+        Ok(())
+    }
+}
+
+// What the fetch tasks need to derive the rows
+#[derive(Clone)]
+struct FetchContext {
+    network: String,
+    backfill: Option<Backfill>,
+    pool_labels: Arc<PoolLabels>,
+    address_prefix: AddressPrefix,
+    emission: EmissionSchedule,
+}
+
+// Blocks fetched in a batch, along with the rows which do not depend on the preceding blocks
+struct BlockBatch {
+    blocks: Vec<BlockView>,
+    queries: Vec<String>,
+    // The miners of the uncles of the blocks
+    uncle_miners: HashMap<Byte32, String>,
+}
+
+// The fetch tasks in block order, an error stops the analysis
+type BatchSender = tokio::sync::mpsc::Sender<tokio::task::JoinHandle<Result<BlockBatch, RpcError>>>;
+
+// Spawn the fetch tasks batch by batch, following the tip, until the end of `numbers`. The
+// channel's capacity bounds how many batches are fetched ahead of the analysis.
+async fn fetch_batches(
+    jsonrpc: AsyncJsonrpc,
+    context: FetchContext,
+    numbers: Range<BlockNumber>,
+    lag: BlockNumber,
+    batch_sender: BatchSender,
+) {
+    if let Err(err) = follow_tip(&jsonrpc, context, numbers, lag, &batch_sender).await {
+        // Pass the error in place of the next batch
+        let _ = batch_sender
+            .send(tokio::spawn(async move { Err(err) }))
+            .await;
+    }
+}
+
+async fn follow_tip(
+    jsonrpc: &AsyncJsonrpc,
+    context: FetchContext,
+    numbers: Range<BlockNumber>,
+    lag: BlockNumber,
+    batch_sender: &BatchSender,
+) -> Result<(), RpcError> {
+    let mut number = numbers.start;
+    let mut tip = retry_rpc(|| jsonrpc.get_tip_block_number()).await?;
+    while number < numbers.end {
+        // The blocks up to `tip - lag`
+        let confirmed = (tip + 1).saturating_sub(lag);
+        if number >= confirmed {
+            // Stop when the analysis is restarted after a reorganization
+            tokio::select! {
+                _ = tokio::time::sleep(Duration::from_secs(1)) => {}
+                _ = batch_sender.closed() => return Ok(()),
+            }
+            tip = retry_rpc(|| jsonrpc.get_tip_block_number()).await?;
+            continue;
+        }
+
+        let permit = match batch_sender.reserve().await {
+            Ok(permit) => permit,
+            Err(_) => return Ok(()),
+        };
+        let end = min(min(number + BLOCKS_BATCH_SIZE, confirmed), numbers.end);
+        permit.send(tokio::spawn(fetch_batch(
+            jsonrpc.clone(),
+            context.clone(),
+            number..end,
+        )));
+        number = end;
+    }
+    Ok(())
+}
+
+async fn fetch_batch(
+    jsonrpc: AsyncJsonrpc,
+    context: FetchContext,
+    numbers: Range<BlockNumber>,
+) -> Result<BlockBatch, RpcError> {
+    // The chain may have been reorganized to a shorter one since the tip was got. Return the
+    // blocks present; the gap is detected and handled by the analysis.
+    let parent = retry_rpc(|| jsonrpc.get_headers_by_numbers(vec![numbers.start - 1]))
+        .await?
+        .pop()
+        .flatten();
+    let mut parent: HeaderView = match parent {
+        Some(parent) => parent.into(),
+        None => {
+            return Ok(BlockBatch {
+                blocks: Vec::new(),
+                queries: Vec::new(),
+                uncle_miners: HashMap::new(),
+            })
+        }
+    };
+    let blocks: Vec<BlockView> = retry_rpc(|| jsonrpc.get_blocks_by_numbers(numbers.clone()))
+        .await?
+        .into_iter()
+        .take_while(Option::is_some)
+        .flatten()
+        .map(Into::into)
+        .collect();
+    let uncles = get_uncles(&jsonrpc, &blocks).await?;
+    // A block reward row is of the target block, finalized `FINALIZATION_DELAY` blocks later
+    let reward_write = |block: &BlockView| {
+        if block.number() > FINALIZATION_DELAY {
+            let target_number = block.number() - FINALIZATION_DELAY;
+            write_mode(&context.backfill, AnalyzedTable::BlockReward, target_number)
+        } else {
+            Write::Skip
+        }
+    };
+    let finalizing = blocks
+        .iter()
+        .filter(|block| reward_write(block) != Write::Skip)
+        .collect::<Vec<_>>();
+    let rewards = get_block_rewards(&jsonrpc, &finalizing).await?;
+
+    let mut queries = Vec::new();
+    for block in blocks.iter() {
+        let write = write_mode(&context.backfill, AnalyzedTable::Block, block.number());
+        if write != Write::Skip {
+            let point = analyze_block(&context, block, &parent);
+            if write == Write::Replace {
+                queries.push(point.delete_query());
+            }
+            queries.push(point.insert_query());
+        }
+        let write = write_mode(&context.backfill, AnalyzedTable::Dao, block.number());
+        if write != Write::Skip {
+            let point = analyze_dao_state(&context, block);
+            if write == Write::Replace {
+                queries.push(table::DaoState::delete_query(
+                    &context.network,
+                    point.number,
+                ));
+            }
+            queries.push(point.insert_query());
+        }
+        if let Some((target, state)) = rewards.get(&block.hash()) {
+            if reward_write(block) == Write::Replace {
+                queries.push(table::BlockReward::delete_query(
+                    &context.network,
+                    target.number() as i64,
+                ));
+            }
+            let point = analyze_block_reward(&context, block, target, state);
+            queries.push(point.insert_query());
+        }
+        let write = write_mode(&context.backfill, AnalyzedTable::Uncle, block.number());
+        if write != Write::Skip {
+            for uncle_hash in block.uncle_hashes() {
+                if let Some((uncle, cousin)) = uncles.get(&uncle_hash) {
+                    let point = analyze_block_uncle(&context, uncle, cousin);
+                    if write == Write::Replace {
+                        queries.push(point.delete_query());
+                    }
+                    queries.push(point.insert_query());
+                }
+            }
+        }
+        parent = block.header();
+    }
+    let uncle_miners = uncles
+        .iter()
+        .map(|(uncle_hash, (uncle, _))| {
+            let miner = format!("{:#x}", extract_miner_lock(uncle).args());
+            (uncle_hash.clone(), miner)
+        })
+        .collect();
+    Ok(BlockBatch {
+        blocks,
+        queries,
+        uncle_miners,
+    })
+}
+
+/// Fetch the uncles of the given blocks, together with the canonical headers at the same
+/// heights as the uncles. The uncles not found, or without the canonical header, are skipped.
+async fn get_uncles(
+    jsonrpc: &AsyncJsonrpc,
+    blocks: &[BlockView],
+) -> Result<HashMap<Byte32, (BlockView, HeaderView)>, RpcError> {
+    let uncle_hashes = blocks
+        .iter()
+        .flat_map(|block| block.uncle_hashes().into_iter())
+        .collect::<Vec<_>>();
+    let uncles: Vec<BlockView> = retry_rpc(|| jsonrpc.get_fork_blocks(uncle_hashes.clone()))
+        .await?
+        .into_iter()
+        .flatten()
+        .map(Into::into)
+        .collect();
+    let cousin_numbers = uncles
+        .iter()
+        .map(|uncle| uncle.number())
+        .collect::<Vec<_>>();
+    let cousins = retry_rpc(|| jsonrpc.get_headers_by_numbers(cousin_numbers.clone())).await?;
+    Ok(uncles
+        .into_iter()
+        .zip(cousins)
+        .filter_map(|(uncle, cousin)| match cousin {
+            Some(cousin) => Some((uncle.hash(), (uncle, cousin.into()))),
+            // The chain is shorter now
+            None => {
+                log::warn!(
+                    "no canonical block at the height of uncle #{}({:#x})",
+                    uncle.number(),
+                    uncle.hash()
+                );
+                None
+            }
+        })
+        .collect())
+}
+
+fn analyze_block(context: &FetchContext, block: &BlockView, parent: &HeaderView) -> table::Block {
+    let time = block_time(block);
+    let number = block.number();
+    let interval = block.timestamp() as i64 - parent.timestamp() as i64; // ms
+    let n_transactions = block.transactions().len() as u32;
+    let n_proposals = block.union_proposal_ids().len() as u32;
+    let n_uncles = block.uncles().hashes().len() as u32;
+    let version = block.version();
+    log::info!("block #{}, timestamp: {}", number, block.timestamp());
+    let miner_lock = extract_miner_lock(block);
+    let (miner_code_hash, miner_hash_type, miner_args) = script_components(&miner_lock);
+    let message = extract_message(block);
+    table::Block {
+        network: context.network.clone(),
+        time,
+        number: number as i64,
+        interval: interval as i64,
+        n_transactions: n_transactions as i32,
+        n_proposals: n_proposals as i32,
+        n_uncles: n_uncles as i32,
+        version: version as i32,
+        hash: format!("{:#x}", block.hash()),
+        miner: format!("{:#x}", miner_lock.args()),
+        pool: identify_pool(&context.pool_labels, block, &message),
+        miner_address: encode_address(context.address_prefix, &miner_lock).unwrap_or_default(),
+        miner_code_hash,
+        miner_hash_type,
+        miner_args,
+        message,
+    }
+}
+
+// The code hashes count both the outputs' scripts and the resolved inputs' scripts
+fn analyze_committed_transactions(
+    network: &str,
+    block: &BlockView,
+    inputs: &HashMap<OutPoint, ResolvedCell>,
+) -> Vec<table::CommittedTransaction> {
+    let time = block_time(block);
+    block
+        .transactions()
+        .iter()
+        .enumerate()
+        .map(|(index, transaction)| {
+            let output_capacity: u64 = transaction
+                .outputs()
+                .into_iter()
+                .map(|output| -> u64 { output.capacity().unpack() })
+                .sum();
+            let witness_size: usize = transaction
+                .witnesses()
+                .into_iter()
+                .map(|witness| witness.raw_data().len())
+                .sum();
+            let cells = transaction
+                .input_pts_iter()
+                .filter_map(|out_point| inputs.get(&out_point))
+                .map(|cell| cell.output.clone())
+                .chain(transaction.outputs().into_iter())
+                .collect::<Vec<_>>();
+            let lock_code_hashes = cells
+                .iter()
+                .map(|cell| format!("{:#x}", cell.lock().code_hash()))
+                .collect::<BTreeSet<_>>();
+            let type_code_hashes = cells
+                .iter()
+                .filter_map(|cell| cell.type_().to_opt())
+                .map(|type_| format!("{:#x}", type_.code_hash()))
+                .collect::<BTreeSet<_>>();
+            table::CommittedTransaction {
+                network: network.to_string(),
+                time,
+                number: block.number() as i64,
+                index: index as i32,
+                hash: format!("{:#x}", transaction.hash()),
+                n_inputs: transaction.inputs().len() as i32,
+                n_outputs: transaction.outputs().len() as i32,
+                n_cell_deps: transaction.cell_deps().len() as i32,
+                n_header_deps: transaction.header_deps().len() as i32,
+                size: transaction.data().serialized_size_in_block() as i32,
+                output_capacity: output_capacity as i64,
+                witness_size: witness_size as i32,
+                lock_code_hashes: lock_code_hashes.into_iter().collect(),
+                type_code_hashes: type_code_hashes.into_iter().collect(),
+            }
+        })
+        .collect()
+}
+
+fn analyze_block_uncle(
+    context: &FetchContext,
+    uncle: &BlockView,
+    cousin: &HeaderView,
+) -> table::Uncle {
+    let uncle_number = uncle.number();
+    let time = block_time(uncle);
+    let n_transactions = uncle.transactions().len() as u32;
+    let n_proposals = uncle.union_proposal_ids().len() as u32;
+    let version = uncle.version();
+    let lag_to_canonical = cousin.timestamp() as i64 - uncle.timestamp() as i64;
+    log::info!(
+        "uncle #{}({:#x}), timestamp: {}, lag_to_canonical: {}",
+        uncle_number,
+        uncle.hash(),
+        uncle.timestamp(),
+        lag_to_canonical,
+    );
+    let miner_lock = extract_miner_lock(uncle);
+    let (miner_code_hash, miner_hash_type, miner_args) = script_components(&miner_lock);
+    let message = extract_message(uncle);
+    table::Uncle {
+        time,
+        network: context.network.clone(),
+        number: uncle_number as i64,
+        lag_to_canonical,
+        n_transactions: n_transactions as i32,
+        n_proposals: n_proposals as i32,
+        version: version as i32,
+        hash: format!("{:#x}", uncle.hash()),
+        miner: format!("{:#x}", miner_lock.args()),
+        pool: identify_pool(&context.pool_labels, uncle, &message),
+        miner_address: encode_address(context.address_prefix, &miner_lock).unwrap_or_default(),
+        miner_code_hash,
+        miner_hash_type,
+        miner_args,
+        message,
+    }
+}
+
+// The time of a timestamp in milliseconds
+fn to_time(timestamp: u64) -> chrono::NaiveDateTime {
+    chrono::NaiveDateTime::from_timestamp(
+        (timestamp / 1000) as i64,
+        (timestamp % 1000 * 1000) as u32,
+    )
+}
+
+fn block_time(block: &BlockView) -> chrono::NaiveDateTime {
+    to_time(block.timestamp())
+}
+
+fn window_key(window: MinerShareWindow, header: &HeaderView) -> u64 {
+    match window {
+        MinerShareWindow::Day => header.timestamp() / MILLISECONDS_PER_DAY,
+        MinerShareWindow::Epoch => header.epoch().number(),
+    }
+}
+
+fn window_name(window: MinerShareWindow) -> &'static str {
+    match window {
+        MinerShareWindow::Day => "day",
+        MinerShareWindow::Epoch => "epoch",
+    }
+}
+
+fn block_work(block: &BlockView) -> f64 {
+    let uncles_work: f64 = block
+        .data()
+        .uncles()
+        .into_iter()
+        .map(|uncle| {
+            let compact_target: u32 = uncle.header().raw().compact_target().unpack();
+            u256_to_f64(&compact_to_difficulty(compact_target))
+        })
+        .sum();
+    u256_to_f64(&compact_to_difficulty(block.compact_target())) + uncles_work
+}
+
+fn u256_to_f64(value: &U256) -> f64 {
+    value.to_string().parse().unwrap_or_default()
+}
+
+// 1 if the denominator is zero, which happens on dummy chains without difficulty
+fn ratio(numerator: &U256, denominator: &U256) -> f64 {
+    let denominator = u256_to_f64(denominator);
+    if denominator > 0.0 {
+        u256_to_f64(numerator) / denominator
+    } else {
+        1.0
+    }
+}
+
+fn prompt_progress(total: u64, processed: u64, start: Instant) {
+    const PROMPT_STEP: u64 = 10000;
+
+    if processed % PROMPT_STEP == 0 && processed > 0 {
+        let left = total - processed;
+        let processed_duration = start.elapsed();
+        let left_duration = processed_duration
+            .mul_f64(left as f64)
+            .div_f64(processed as f64);
+        let processed_percent = processed as f64 / total as f64;
+
+        let blocks_per_sec = processed as f64 / processed_duration.as_secs_f64();
+        log::info!(
+            "Progress {:.2}, {:.1} blocks/s, left {}s ...",
+            processed_percent,
+            blocks_per_sec,
+            left_duration.as_secs()
+        );
+    }
+}
+
+fn extract_cellbase_witness(block: &BlockView) -> CellbaseWitness {
+    let cellbase = block.transaction(0).unwrap();
+    let witness = cellbase.witnesses().get(0).unwrap().raw_data();
+    CellbaseWitness::from_slice(witness.as_ref()).unwrap()
+}
+
+fn extract_miner_lock(block: &BlockView) -> Script {
+    extract_cellbase_witness(block).lock()
+}
+
+fn extract_message(block: &BlockView) -> String {
+    decode_message(&extract_cellbase_witness(block).message().raw_data())
+}
+
+// The pool name, or empty if unidentified
+fn identify_pool(pool_labels: &PoolLabels, block: &BlockView, message: &str) -> String {
+    let (_, _, lock_args) = script_components(&extract_miner_lock(block));
+    pool_labels
+        .identify(&lock_args, message)
+        .unwrap_or_default()
+        .to_string()
+}
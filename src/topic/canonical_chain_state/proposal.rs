@@ -0,0 +1,149 @@
+//! The proposals zones, committed or expired
+
+use super::{block_time, write_mode, AnalyzedTable, CanonicalChainState, Write, PROPOSAL_WINDOW};
+use crate::table;
+use crate::util::retry_send;
+use ckb_types::core::{BlockNumber, BlockView};
+use ckb_types::packed::{Byte32, ProposalShortId};
+use ckb_types::prelude::*;
+use std::collections::HashMap;
+
+// The block or uncle proposing a proposal
+#[derive(Debug, Clone)]
+pub(super) struct Proposer {
+    hash: Byte32,
+    miner: Option<String>,
+    is_uncle: bool,
+}
+
+// The proposals of a block and its uncles, not committed yet
+pub(super) type ProposalsZone = HashMap<ProposalShortId, Proposer>;
+
+impl CanonicalChainState {
+    // Return the committed proposals removed from their proposals zones, and the pruned zone
+    #[allow(clippy::type_complexity)]
+    pub(super) async fn analyze_block_transactions(
+        &mut self,
+        block: &BlockView,
+        miner: &str,
+        uncle_miners: &HashMap<Byte32, String>,
+    ) -> (
+        Vec<(BlockNumber, ProposalShortId, Proposer)>,
+        Option<(BlockNumber, ProposalsZone)>,
+    ) {
+        let number = block.number();
+        let write = write_mode(&self.backfill, AnalyzedTable::TwoPCCommitment, number);
+        if write == Write::Replace {
+            let query = table::TwoPCCommitment::delete_query(&self.config.network(), number as i64);
+            retry_send(&self.query_sender, query).await;
+        }
+        let mut committed = Vec::new();
+        for transaction in block.transactions() {
+            // A re-proposed transaction is removed from every proposals zone of the window, the
+            // commitment delay counts from its earliest proposal
+            let proposal_id = transaction.proposal_short_id();
+            let mut earliest = None;
+            for proposed_number in
+                number.saturating_sub(PROPOSAL_WINDOW.1)..=number.saturating_sub(PROPOSAL_WINDOW.0)
+            {
+                let removed = self
+                    .proposals_zones
+                    .get_mut(&proposed_number)
+                    .and_then(|proposals_zone| proposals_zone.remove(&proposal_id));
+                if let Some(proposer) = removed {
+                    earliest = earliest.or(Some(proposed_number));
+                    committed.push((proposed_number, proposal_id.clone(), proposer));
+                }
+            }
+            if let Some(proposed_number) = earliest {
+                if write != Write::Skip {
+                    let time = block_time(block);
+                    let delay = number - proposed_number;
+                    let point = table::TwoPCCommitment {
+                        time,
+                        network: self.config.network(),
+                        number: number as i64,
+                        delay: delay as i32,
+                    };
+                    retry_send(&self.query_sender, point.insert_query()).await;
+                }
+            }
+        }
+
+        // Prune outdated proposals zone, whose proposals can no longer be committed
+        let pruned_number = number.saturating_sub(PROPOSAL_WINDOW.1);
+        let pruned = self
+            .proposals_zones
+            .remove(&pruned_number)
+            .map(|proposals_zone| (pruned_number, proposals_zone));
+        if let Some((pruned_number, proposals_zone)) = &pruned {
+            self.report_expired_proposals(block, *pruned_number, proposals_zone)
+                .await;
+        }
+
+        // The block's own proposals take precedence over the same ones of its uncles
+        let mut proposals_zone = ProposalsZone::new();
+        for uncle in block.uncles().into_iter() {
+            // Unknown if the uncle is not found, e.g. it is removed from the node's database
+            let miner = uncle_miners.get(&uncle.hash()).cloned();
+            if miner.is_none() {
+                log::warn!(
+                    "uncle {:#x} of block #{} not found, its proposals have no miner",
+                    uncle.hash(),
+                    number
+                );
+            }
+            let proposer = Proposer {
+                hash: uncle.hash(),
+                miner,
+                is_uncle: true,
+            };
+            for proposal_id in uncle.data().proposals().into_iter() {
+                proposals_zone.insert(proposal_id, proposer.clone());
+            }
+        }
+        let proposer = Proposer {
+            hash: block.hash(),
+            miner: Some(miner.to_string()),
+            is_uncle: false,
+        };
+        for proposal_id in block.data().proposals().into_iter() {
+            proposals_zone.insert(proposal_id, proposer.clone());
+        }
+        self.proposals_zones.insert(number, proposals_zone);
+        (committed, pruned)
+    }
+
+    // Write the proposals never committed within the proposal window, closed by the block
+    async fn report_expired_proposals(
+        &self,
+        block: &BlockView,
+        proposed_number: BlockNumber,
+        proposals_zone: &ProposalsZone,
+    ) {
+        let number = block.number();
+        let write = write_mode(&self.backfill, AnalyzedTable::ExpiredProposal, number);
+        if write == Write::Skip {
+            return;
+        }
+        let network = self.config.network();
+        if write == Write::Replace {
+            let query = table::ExpiredProposal::delete_query(&network, number as i64);
+            retry_send(&self.query_sender, query).await;
+        }
+        let time = block_time(block);
+        for (proposal_id, proposer) in proposals_zone.iter() {
+            let point = table::ExpiredProposal {
+                network: network.clone(),
+                time,
+                number: number as i64,
+                proposal_id: format!("{:#x}", proposal_id),
+                proposed_number: proposed_number as i64,
+                proposer_hash: format!("{:#x}", proposer.hash),
+                miner: proposer.miner.clone(),
+                is_uncle: proposer.is_uncle,
+            };
+            retry_send(&self.query_sender, point.insert_query()).await;
+        }
+    }
+}
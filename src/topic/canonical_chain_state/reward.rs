@@ -0,0 +1,126 @@
+//! The block rewards once finalized, reconciled with the issuance schedule
+
+use super::{to_time, FetchContext, PROPOSAL_WINDOW};
+use crate::table;
+use crate::util::retry_rpc;
+use ckb_suite_rpc::ckb_jsonrpc_types::{BlockEconomicState, Consensus};
+use ckb_suite_rpc::{AsyncJsonrpc, Error as RpcError};
+use ckb_types::core::{BlockView, EpochNumberWithFraction, HeaderView};
+use ckb_types::packed::Byte32;
+use ckb_types::prelude::*;
+use std::cmp::max;
+use std::collections::HashMap;
+
+// A block's reward is finalized, i.e. paid by the cellbase, in the block after the farthest
+// proposal window
+pub(super) const FINALIZATION_DELAY: u64 = PROPOSAL_WINDOW.1 + 1;
+
+// The issuance schedule of the consensus, to reconcile the blocks' issuance with
+#[derive(Debug, Clone, Copy)]
+pub(super) struct EmissionSchedule {
+    initial_primary_epoch_reward: u64,
+    secondary_epoch_reward: u64,
+    primary_epoch_reward_halving_interval: u64,
+}
+
+impl EmissionSchedule {
+    pub(super) fn from_consensus(consensus: &Consensus) -> Self {
+        Self {
+            initial_primary_epoch_reward: consensus.initial_primary_epoch_reward.value(),
+            secondary_epoch_reward: consensus.secondary_epoch_reward.value(),
+            primary_epoch_reward_halving_interval: consensus
+                .primary_epoch_reward_halving_interval
+                .value(),
+        }
+    }
+
+    // Return the primary and secondary issuance of the block at the epoch position. An epoch's
+    // rewards are split evenly over its blocks, and the remainders go to its first blocks.
+    fn block_issuance(&self, epoch: EpochNumberWithFraction) -> (u64, u64) {
+        let halvings = epoch.number() / max(self.primary_epoch_reward_halving_interval, 1);
+        let primary_epoch_reward = self
+            .initial_primary_epoch_reward
+            .checked_shr(halvings as u32)
+            .unwrap_or(0);
+        let split = |reward: u64| {
+            let length = max(epoch.length(), 1);
+            reward / length + u64::from(epoch.index() < reward % length)
+        };
+        (
+            split(primary_epoch_reward),
+            split(self.secondary_epoch_reward),
+        )
+    }
+}
+
+/// Fetch the economic states of the blocks finalized by the given ones, keyed by the finalizing
+/// block hashes. The block finalized by a reorganized block is skipped.
+pub(super) async fn get_block_rewards(
+    jsonrpc: &AsyncJsonrpc,
+    finalizing: &[&BlockView],
+) -> Result<HashMap<Byte32, (HeaderView, BlockEconomicState)>, RpcError> {
+    let target_numbers = finalizing
+        .iter()
+        .filter(|block| block.number() > FINALIZATION_DELAY)
+        .map(|block| block.number() - FINALIZATION_DELAY)
+        .collect::<Vec<_>>();
+    if target_numbers.is_empty() {
+        return Ok(HashMap::new());
+    }
+    let targets = retry_rpc(|| jsonrpc.get_headers_by_numbers(target_numbers.clone())).await?;
+    let handles = targets
+        .into_iter()
+        .flatten()
+        .map(|target| {
+            let target: HeaderView = target.into();
+            let jsonrpc = jsonrpc.clone();
+            tokio::spawn(async move {
+                let state =
+                    match retry_rpc(|| jsonrpc.get_block_economic_state(target.hash())).await {
+                        Ok(state) => Some(state),
+                        // The target is reorganized, or not finalized yet
+                        Err(err) if err.is_not_found() => None,
+                        Err(err) => return Err(err),
+                    };
+                Ok((target, state))
+            })
+        })
+        .collect::<Vec<_>>();
+    let mut rewards = HashMap::new();
+    for handle in handles {
+        let (target, state) = handle
+            .await
+            .expect("get block economic state task panicked")?;
+        if let Some(state) = state {
+            let finalized_at: Byte32 = state.finalized_at.pack();
+            rewards.insert(finalized_at, (target, state));
+        }
+    }
+    Ok(rewards)
+}
+
+pub(super) fn analyze_block_reward(
+    context: &FetchContext,
+    finalizing: &BlockView,
+    target: &HeaderView,
+    state: &BlockEconomicState,
+) -> table::BlockReward {
+    let (expected_primary_issuance, expected_secondary_issuance) =
+        context.emission.block_issuance(target.epoch());
+    table::BlockReward {
+        network: context.network.clone(),
+        time: to_time(target.timestamp()),
+        number: target.number() as i64,
+        epoch: target.epoch().number() as i64,
+        finalized_number: finalizing.number() as i64,
+        primary_issuance: state.issuance.primary.value() as i64,
+        secondary_issuance: state.issuance.secondary.value() as i64,
+        expected_primary_issuance: expected_primary_issuance as i64,
+        expected_secondary_issuance: expected_secondary_issuance as i64,
+        primary_reward: state.miner_reward.primary.value() as i64,
+        secondary_reward: state.miner_reward.secondary.value() as i64,
+        commit_reward: state.miner_reward.committed.value() as i64,
+        proposal_reward: state.miner_reward.proposal.value() as i64,
+        total_fee: state.txs_fee.value() as i64,
+    }
+}
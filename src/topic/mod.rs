@@ -2,15 +2,19 @@ mod canonical_chain_state;
 mod network_propagation;
 mod network_topology;
 mod reorganization;
+mod rpc_cache_stats;
 mod subscribe_new_tip_header;
 mod subscribe_new_transaction;
 mod subscribe_proposed_transaction;
 mod tx_transition;
 
-pub(crate) use canonical_chain_state::CanonicalChainState;
+pub(crate) use canonical_chain_state::{
+    AnalyzedTable, Backfill, CanonicalChainState, ChainOccupancy,
+};
 pub(crate) use network_propagation::NetworkPropagation;
 pub(crate) use network_topology::NetworkTopology;
 pub(crate) use reorganization::Reorganization;
+pub(crate) use rpc_cache_stats::RpcCacheStats;
 pub(crate) use subscribe_new_tip_header::SubscribeNewTipHeader;
 pub(crate) use subscribe_new_transaction::SubscribeNewTransaction;
 pub(crate) use subscribe_proposed_transaction::SubscribeProposedTransaction;
@@ -1,21 +1,21 @@
 //! This module is experimentle at present.
 
 use ckb_network::{multiaddr::MultiAddr, multiaddr_to_socketaddr};
-use ckb_suite_rpc::Jsonrpc;
+use ckb_suite_rpc::{AsyncJsonrpc, Error as RpcError};
 use std::collections::{HashMap, HashSet};
 use std::net::SocketAddr;
 use std::time::Duration;
 
 pub(crate) struct NetworkTopology {
     ckb_rpc_urls: Vec<String>,
-    rpcs: Vec<Jsonrpc>,
+    rpcs: Vec<AsyncJsonrpc>,
 }
 
 impl NetworkTopology {
     pub(crate) fn new(ckb_rpc_urls: Vec<String>) -> Self {
         let rpcs: Vec<_> = ckb_rpc_urls
             .iter()
-            .map(|url| Jsonrpc::connect(url.as_ref()))
+            .map(|url| AsyncJsonrpc::connect(&[url]))
             .collect();
         Self { rpcs, ckb_rpc_urls }
     }
@@ -30,7 +30,13 @@ impl NetworkTopology {
     pub(crate) async fn analyze(&self) {
         let mut connections = HashSet::new();
         for rpc in self.rpcs.iter() {
-            self.get_connections(rpc, &mut connections).await;
+            if let Err(err) = self.get_connections(rpc, &mut connections).await {
+                log::error!(
+                    "failed to get connections of \"{}\", error: {}",
+                    rpc.uri(),
+                    err
+                );
+            }
         }
 
         log::info!("graph Topology {{");
@@ -85,10 +91,14 @@ impl NetworkTopology {
         log::info!("}}");
     }
 
-    async fn get_connections(&self, rpc: &Jsonrpc, connections: &mut HashSet<(String, String)>) {
-        let local = rpc.local_node_info();
+    async fn get_connections(
+        &self,
+        rpc: &AsyncJsonrpc,
+        connections: &mut HashSet<(String, String)>,
+    ) -> Result<(), RpcError> {
+        let local = rpc.local_node_info().await?;
         let local_ip = extract_ip(&local.addresses[0].address);
-        for remote in rpc.get_peers() {
+        for remote in rpc.get_peers().await? {
             let remote_ip = extract_ip(&remote.addresses[0].address);
             if local_ip > remote_ip {
                 connections.insert((local_ip.clone(), remote_ip));
@@ -96,6 +106,7 @@ impl NetworkTopology {
                 connections.insert((remote_ip, local_ip.clone()));
             }
         }
+        Ok(())
     }
 }
 
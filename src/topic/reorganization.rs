@@ -19,18 +19,17 @@
 // TODO rename main_ to canonical_; remove prefix main_ from main_tip_hash/main_tip_number
 
 use crate::config::Config;
-use crate::subscribe::{Subscription, Topic};
+use crate::subscribe::{Notification, Subscription, Topic};
 use crate::util::retry_send;
-use ckb_suite_rpc::{ckb_jsonrpc_types::HeaderView as JsonHeader, Jsonrpc};
+use ckb_suite_rpc::{AsyncJsonrpc, Error as RpcError};
 use ckb_types::core::{BlockNumber, HeaderView};
 use ckb_types::packed::Byte32;
-use jsonrpc_core::serde_from_str;
 use std::time::Duration;
 
 pub(crate) struct Reorganization {
     config: Config,
-    subscriber: crossbeam::channel::Receiver<(Topic, String)>,
-    jsonrpc: Jsonrpc,
+    subscriber: crossbeam::channel::Receiver<Notification>,
+    jsonrpc: AsyncJsonrpc,
     query_sender: crossbeam::channel::Sender<String>,
     main_tip_number: BlockNumber,
     main_tip_hash: Byte32,
@@ -39,7 +38,7 @@ pub(crate) struct Reorganization {
 impl Reorganization {
     pub(crate) fn new(
         config: Config,
-        jsonrpc: Jsonrpc,
+        jsonrpc: AsyncJsonrpc,
         query_sender: crossbeam::channel::Sender<String>,
     ) -> (Self, Subscription) {
         let (subscription, subscriber) =
@@ -59,19 +58,18 @@ impl Reorganization {
 
     async fn try_recv_subscription(
         &self,
-    ) -> Result<(Topic, String), crossbeam::channel::TryRecvError> {
+    ) -> Result<Notification, crossbeam::channel::TryRecvError> {
         self.subscriber.try_recv()
     }
 
     pub(crate) async fn run(mut self) {
         loop {
             match self.try_recv_subscription().await {
-                Ok((topic, message)) => {
-                    assert_eq!(Topic::NewTipHeader, topic);
-                    let header: JsonHeader = serde_from_str(&message).unwrap();
+                Ok(Notification::NewTipHeader(header)) => {
                     let header: HeaderView = header.into();
                     self.handle(&header).await;
                 }
+                Ok(notification) => unreachable!("unexpected notification {:?}", notification),
                 Err(crossbeam::channel::TryRecvError::Disconnected) => return,
                 Err(crossbeam::channel::TryRecvError::Empty) => {
                     tokio::time::sleep(Duration::from_secs(1)).await
@@ -85,36 +83,54 @@ impl Reorganization {
             self.main_tip_hash = header.hash();
             self.main_tip_number = header.number();
         } else {
-            let new_tip = header;
-            let old_tip = self.get_header(self.main_tip_hash.clone()).await;
-            let ancestor = self.locate_ancestor(&old_tip, new_tip).await;
-            self.report_reorganization(new_tip, &old_tip, &ancestor)
-                .await;
+            if let Err(err) = self.handle_reorganization(header).await {
+                // The fork point is unknown, so drop all the cached canonical headers
+                self.jsonrpc.invalidate_headers_since(0);
+                log::error!(
+                    "failed to handle reorganization to #{}({:#x}), error: {}",
+                    header.number(),
+                    header.hash(),
+                    err
+                );
+            }
 
             self.main_tip_hash = header.hash();
             self.main_tip_number = header.number();
         }
     }
 
-    async fn locate_ancestor(&mut self, old_tip: &HeaderView, new_tip: &HeaderView) -> HeaderView {
+    async fn handle_reorganization(&mut self, new_tip: &HeaderView) -> Result<(), RpcError> {
+        let old_tip = self.get_header(self.main_tip_hash.clone()).await?;
+        let ancestor = self.locate_ancestor(&old_tip, new_tip).await?;
+        self.jsonrpc.invalidate_headers_since(ancestor.number() + 1);
+        self.report_reorganization(new_tip, &old_tip, &ancestor)
+            .await;
+        Ok(())
+    }
+
+    async fn locate_ancestor(
+        &mut self,
+        old_tip: &HeaderView,
+        new_tip: &HeaderView,
+    ) -> Result<HeaderView, RpcError> {
         let mut old_tip = old_tip.clone();
         let mut new_tip = new_tip.clone();
         #[allow(clippy::comparison_chain)]
         if old_tip.number() > new_tip.number() {
             for _ in 0..old_tip.number() - new_tip.number() {
-                old_tip = self.get_header(old_tip.parent_hash()).await;
+                old_tip = self.get_header(old_tip.parent_hash()).await?;
             }
         } else if old_tip.number() < new_tip.number() {
             for _ in 0..new_tip.number() - old_tip.number() {
-                new_tip = self.get_header(new_tip.parent_hash()).await;
+                new_tip = self.get_header(new_tip.parent_hash()).await?;
             }
         }
         assert_eq!(old_tip.number(), new_tip.number());
         while old_tip.hash() != new_tip.hash() {
-            old_tip = self.get_header(old_tip.parent_hash()).await;
-            new_tip = self.get_header(new_tip.parent_hash()).await;
+            old_tip = self.get_header(old_tip.parent_hash()).await?;
+            new_tip = self.get_header(new_tip.parent_hash()).await?;
         }
-        old_tip
+        Ok(old_tip)
     }
 
     async fn report_reorganization(
@@ -151,15 +167,13 @@ impl Reorganization {
         retry_send(&self.query_sender, point.insert_query()).await;
     }
 
-    async fn get_header(&mut self, block_hash: Byte32) -> HeaderView {
-        if let Some(header) = self.jsonrpc.get_header(block_hash.clone()) {
-            return header.into();
-        }
-
-        if let Some(block) = self.jsonrpc.get_fork_block(block_hash) {
-            return block.header.into();
+    async fn get_header(&mut self, block_hash: Byte32) -> Result<HeaderView, RpcError> {
+        match self.jsonrpc.get_header(block_hash.clone()).await {
+            Err(err) if err.is_not_found() => {
+                let block = self.jsonrpc.get_fork_block(block_hash).await?;
+                Ok(block.header.into())
+            }
+            result => result.map(Into::into),
         }
-
-        unreachable!()
     }
 }
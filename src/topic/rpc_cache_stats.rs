@@ -0,0 +1,51 @@
+use crate::config::Config;
+use crate::util::retry_send;
+use ckb_suite_rpc::AsyncJsonrpc;
+use std::time::Duration;
+
+const REPORT_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Report the hit/miss counters of the RPC client caches, which are shared by the topics using
+/// the same client.
+pub(crate) struct RpcCacheStats {
+    config: Config,
+    jsonrpc: AsyncJsonrpc,
+    query_sender: crossbeam::channel::Sender<String>,
+}
+
+impl RpcCacheStats {
+    pub(crate) fn new(
+        config: Config,
+        jsonrpc: AsyncJsonrpc,
+        query_sender: crossbeam::channel::Sender<String>,
+    ) -> Self {
+        Self {
+            config,
+            jsonrpc,
+            query_sender,
+        }
+    }
+
+    pub(crate) async fn run(self) {
+        loop {
+            tokio::time::sleep(REPORT_INTERVAL).await;
+            self.report().await;
+        }
+    }
+
+    async fn report(&self) {
+        let time = chrono::Utc::now().naive_utc();
+        for (cache, stats) in self.jsonrpc.cache_stats() {
+            let point = crate::table::RpcCache {
+                network: self.config.network(),
+                time,
+                cache: cache.to_string(),
+                hits: stats.hits as i64,
+                misses: stats.misses as i64,
+                len: stats.len as i32,
+                capacity: stats.capacity as i32,
+            };
+            retry_send(&self.query_sender, point.insert_query()).await;
+        }
+    }
+}
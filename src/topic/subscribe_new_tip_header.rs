@@ -1,14 +1,12 @@
 use crate::config::Config;
-use crate::subscribe::{Subscription, Topic};
+use crate::subscribe::{Notification, Subscription, Topic};
 use crate::util::retry_send;
-use ckb_suite_rpc::ckb_jsonrpc_types::HeaderView as JsonHeader;
 use ckb_types::core::HeaderView;
-use jsonrpc_core::serde_from_str;
 use std::time::Duration;
 
 pub(crate) struct SubscribeNewTipHeader {
     config: Config,
-    subscriber: crossbeam::channel::Receiver<(Topic, String)>,
+    subscriber: crossbeam::channel::Receiver<Notification>,
     query_sender: crossbeam::channel::Sender<String>,
     hostname: String,
 }
@@ -34,19 +32,18 @@ impl SubscribeNewTipHeader {
 
     async fn try_recv_subscription(
         &self,
-    ) -> Result<(Topic, String), crossbeam::channel::TryRecvError> {
+    ) -> Result<Notification, crossbeam::channel::TryRecvError> {
         self.subscriber.try_recv()
     }
 
     pub(crate) async fn run(mut self) {
         loop {
             match self.try_recv_subscription().await {
-                Ok((topic, message)) => {
-                    assert_eq!(Topic::NewTipHeader, topic);
-                    let header: JsonHeader = serde_from_str(&message).unwrap();
+                Ok(Notification::NewTipHeader(header)) => {
                     let header: HeaderView = header.into();
                     self.report_new_tip_header(&header).await;
                 }
+                Ok(notification) => unreachable!("unexpected notification {:?}", notification),
                 Err(crossbeam::channel::TryRecvError::Disconnected) => return,
                 Err(crossbeam::channel::TryRecvError::Empty) => {
                     tokio::time::sleep(Duration::from_secs(1)).await
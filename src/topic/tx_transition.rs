@@ -1,12 +1,11 @@
 use crate::config::Config;
-use crate::subscribe::{Subscription, Topic};
+use crate::subscribe::{Notification, Subscription, Topic};
 use crate::util::retry_send;
 use ckb_suite_rpc::{
     ckb_jsonrpc_types::{PoolTransactionEntry, Status},
-    Jsonrpc,
+    AsyncJsonrpc, Error as RpcError,
 };
 use ckb_types::{packed::Byte32, prelude::*};
-use jsonrpc_core::serde_from_str;
 use std::collections::HashMap;
 use std::time::{Duration, Instant};
 
@@ -23,20 +22,23 @@ use std::time::{Duration, Instant};
 ///   - Enable subscription at ckb
 
 const SUSPEND_SECONDS: i64 = 3 * 60;
+const CHECK_INTERVAL: Duration = Duration::from_secs(60);
 
 pub(crate) struct TxTransition {
     entries: HashMap<Byte32, TxEntry>,
-    subscriber: crossbeam::channel::Receiver<(Topic, String)>,
+    subscriber: crossbeam::channel::Receiver<Notification>,
     config: Config,
-    jsonrpc: Jsonrpc,
+    jsonrpc: AsyncJsonrpc,
     query_sender: crossbeam::channel::Sender<String>,
     last_checking_at: Instant,
+    check_interval: Duration,
+    suspend_duration: chrono::Duration,
 }
 
 impl TxTransition {
     pub(crate) fn new(
         config: Config,
-        jsonrpc: Jsonrpc,
+        jsonrpc: AsyncJsonrpc,
         query_sender: crossbeam::channel::Sender<String>,
     ) -> (Self, Subscription) {
         let (subscription, subscriber) =
@@ -49,22 +51,35 @@ impl TxTransition {
                 subscriber,
                 last_checking_at: Instant::now(),
                 entries: Default::default(),
+                check_interval: CHECK_INTERVAL,
+                suspend_duration: chrono::Duration::seconds(SUSPEND_SECONDS),
             },
             subscription,
         )
     }
 
-    async fn try_recv(&self) -> Result<(Topic, String), crossbeam::channel::TryRecvError> {
+    /// Check the entries every `check_interval`, once they stay in the pool longer than
+    /// `suspend_duration`
+    #[cfg(test)]
+    pub(crate) fn with_check_intervals(
+        mut self,
+        check_interval: Duration,
+        suspend_duration: chrono::Duration,
+    ) -> Self {
+        self.check_interval = check_interval;
+        self.suspend_duration = suspend_duration;
+        self
+    }
+
+    async fn try_recv(&self) -> Result<Notification, crossbeam::channel::TryRecvError> {
         self.subscriber.try_recv()
     }
 
     pub(crate) async fn run(mut self) {
         loop {
             match self.try_recv().await {
-                Ok((_, message)) => {
+                Ok(Notification::NewTransaction(pool_transaction_entry)) => {
                     let time = chrono::Utc::now().naive_utc();
-                    let pool_transaction_entry =
-                        serde_from_str::<PoolTransactionEntry>(&message).unwrap();
                     let txhash = pool_transaction_entry.transaction.hash.pack();
                     if !self.entries.contains_key(&txhash) {
                         let entry = TxEntry {
@@ -75,10 +90,11 @@ impl TxTransition {
                         self.entries.insert(txhash, entry);
                     }
                 }
+                Ok(notification) => unreachable!("unexpected notification {:?}", notification),
                 Err(crossbeam::channel::TryRecvError::Disconnected) => return,
                 Err(crossbeam::channel::TryRecvError::Empty) => {
                     // TODO 分批检查，或者更高效的方式
-                    if self.last_checking_at.elapsed() >= ::std::time::Duration::from_secs(60) {
+                    if self.last_checking_at.elapsed() >= self.check_interval {
                         self.check().await;
                         self.last_checking_at = Instant::now();
                     } else {
@@ -93,7 +109,7 @@ impl TxTransition {
         let now = chrono::Utc::now().naive_utc();
         let mut to_remove_hashes = Vec::new();
         for (txhash, entry) in self.entries.iter() {
-            if (now - entry.enter_time).num_seconds() > SUSPEND_SECONDS {
+            if now - entry.enter_time > self.suspend_duration {
                 to_remove_hashes.push(txhash.clone());
             }
         }
@@ -102,33 +118,49 @@ impl TxTransition {
             to_remove_entries.push(self.entries.remove(txhash).unwrap());
         }
 
-        for entry in to_remove_entries.iter() {
-            self.check_entry(entry).await;
+        for entry in to_remove_entries {
+            if let Err(err) = self.check_entry(&entry).await {
+                log::error!(
+                    "failed to check transaction {:#x}, error: {}",
+                    entry.pool_transaction_entry.transaction.hash,
+                    err
+                );
+                // Check it again in the next round
+                let txhash = entry.pool_transaction_entry.transaction.hash.pack();
+                self.entries.insert(txhash, entry);
+            }
         }
     }
 
-    async fn check_entry(&self, entry: &TxEntry) {
+    async fn check_entry(&self, entry: &TxEntry) -> Result<(), RpcError> {
         let txhash = entry.pool_transaction_entry.transaction.hash.pack();
-        let txstatus = self.jsonrpc.get_transaction(txhash);
-        match txstatus.map(|s| {
-            (
-                s.tx_status.status,
-                s.tx_status.block_hash.map(|hash| hash.pack()),
-            )
-        }) {
-            Some((Status::Committed, Some(block_hash))) => {
-                if let Some(timestamp) = self.get_block_timestamp(block_hash) {
-                    self.report_commit(entry, timestamp).await;
-                }
+        let txstatus = match self.jsonrpc.get_transaction(txhash).await {
+            Ok(txstatus) => txstatus,
+            Err(err) if err.is_not_found() => {
+                self.report_remove(entry).await;
+                return Ok(());
             }
-            Some((_status, None)) => {
-                return;
+            Err(err) => return Err(err),
+        };
+        match (
+            txstatus.tx_status.status,
+            txstatus.tx_status.block_hash.map(|hash| hash.pack()),
+        ) {
+            (Status::Committed, Some(block_hash)) => {
+                let timestamp = self.get_block_timestamp(block_hash).await?;
+                self.report_commit(entry, timestamp).await;
             }
-            None => {
-                self.report_remove(entry).await;
+            (_status, None) => {}
+            (status, Some(block_hash)) => {
+                log::warn!(
+                    "skip transaction {:#x} of status {:?} in block {:#x}",
+                    entry.pool_transaction_entry.transaction.hash,
+                    status,
+                    block_hash
+                );
             }
-            _ => unreachable!(),
         }
+        Ok(())
     }
 
     async fn report_enter(&self, entry: &TxEntry) {
@@ -164,8 +196,11 @@ impl TxTransition {
         retry_send(&self.query_sender, point.update_query()).await;
     }
 
-    fn get_block_timestamp(&self, block_hash: Byte32) -> Option<chrono::NaiveDateTime> {
-        self.jsonrpc.get_header(block_hash).map(|header| {
+    async fn get_block_timestamp(
+        &self,
+        block_hash: Byte32,
+    ) -> Result<chrono::NaiveDateTime, RpcError> {
+        self.jsonrpc.get_header(block_hash).await.map(|header| {
             chrono::NaiveDateTime::from_timestamp(
                 (header.inner.timestamp.value() / 1000) as i64,
                 (header.inner.timestamp.value() % 1000 * 1000) as u32,
@@ -1,6 +1,8 @@
 use crate::tokio01::prelude::*;
 use ckb_build_info::Version;
-use ckb_suite_rpc::Jsonrpc;
+use ckb_suite_rpc::{Error as RpcError, Jsonrpc};
+use std::cmp::min;
+use std::future::Future;
 use std::net::{Ipv4Addr, SocketAddrV4, TcpListener};
 use std::sync::atomic::{AtomicU16, Ordering::SeqCst};
 
@@ -54,8 +56,48 @@ pub async fn get_last_updated_block_number(
     }
 }
 
+/// The chain state after the latest analyzed block before `before`, to resume the analysis from,
+/// as the block number, live cells, capacity and occupied capacity. None if there is not any, e.g.
+/// the table is not created.
+pub async fn get_last_chain_state(
+    pg: &tokio_postgres::Client,
+    ckb_network_name: &str,
+    before: u64,
+) -> Option<(u64, u64, u64, u64)> {
+    let query = format!(
+        "SELECT number, live_cells, capacity, occupied_capacity FROM {}_chain_state \
+        WHERE number < {} ORDER BY number DESC LIMIT 1",
+        ckb_network_name,
+        min(before, i64::MAX as u64),
+    );
+    match pg.query_opt(query.as_str(), &[]).await {
+        Ok(raw) => raw.map(|raw| {
+            let number: i64 = raw.get(0);
+            let live_cells: i64 = raw.get(1);
+            let capacity: i64 = raw.get(2);
+            let occupied_capacity: i64 = raw.get(3);
+            (
+                number as u64,
+                live_cells as u64,
+                capacity as u64,
+                occupied_capacity as u64,
+            )
+        }),
+        Err(err) => {
+            log::warn!("failed to get the last chain state, error: {}", err);
+            None
+        }
+    }
+}
+
 pub fn get_network_identifier(jsonrpc: &Jsonrpc) -> String {
-    let consensus = jsonrpc.get_consensus();
+    let consensus = jsonrpc.get_consensus().unwrap_or_else(|err| {
+        panic!(
+            "Jsonrpc::get_consensus(\"{}\"), error: {}",
+            jsonrpc.uri(),
+            err
+        )
+    });
     let genesis_hash = format!("{:x}", consensus.genesis_hash);
     format!("/{}/{}", consensus.id, &genesis_hash[..8])
 }
@@ -108,6 +150,25 @@ pub async fn retry_send<T: Clone>(sender: &crossbeam::channel::Sender<T>, messag
     }
 }
 
+// Keep calling the RPC while it fails with transient errors, e.g. the node is unreachable. The
+// other errors, such as the requested object not found, are returned for the caller to skip or
+// propagate.
+pub async fn retry_rpc<T, F, Fut>(mut call: F) -> Result<T, RpcError>
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = Result<T, RpcError>>,
+{
+    loop {
+        match call().await {
+            Err(err) if err.is_transient() => {
+                log::error!("rpc error: {}, retry after 1s", err);
+                tokio::time::sleep(::std::time::Duration::from_secs(1)).await;
+            }
+            result => return result,
+        }
+    }
+}
+
 async fn try_send<T>(
     sender: &crossbeam::channel::Sender<T>,
     message: T,